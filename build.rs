@@ -0,0 +1,68 @@
+use std::{env, fs, process::Command};
+
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+/// Embed build metadata for `--version --verbose` and the run summary.
+fn main() {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+
+    let git_commit = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|commit| commit.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    // Honor SOURCE_DATE_EPOCH so reproducible builds stay reproducible
+    let build_date = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<i64>().ok())
+        .and_then(|epoch| OffsetDateTime::from_unix_timestamp(epoch).ok())
+        .unwrap_or_else(OffsetDateTime::now_utc)
+        .format(&Rfc3339)
+        .unwrap_or_else(|_| "unknown".to_string());
+
+    let mut features: Vec<String> = env::vars()
+        .filter_map(|(key, _)| key.strip_prefix("CARGO_FEATURE_").map(str::to_string))
+        .map(|feature| feature.to_lowercase().replace('_', "-"))
+        .collect();
+    features.sort();
+
+    let lockfile = fs::read_to_string(format!("{}/Cargo.lock", manifest_dir)).unwrap_or_default();
+
+    println!("cargo:rustc-env=BUILD_GIT_COMMIT={}", git_commit);
+    println!("cargo:rustc-env=BUILD_DATE={}", build_date);
+    println!("cargo:rustc-env=BUILD_FEATURES={}", features.join(","));
+    println!(
+        "cargo:rustc-env=BUILD_COSMRS_VERSION={}",
+        locked_version(&lockfile, "cosmrs")
+    );
+    println!(
+        "cargo:rustc-env=BUILD_TENDERMINT_VERSION={}",
+        locked_version(&lockfile, "tendermint")
+    );
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs/heads");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+}
+
+/// Find the resolved version of a package in Cargo.lock.
+fn locked_version(lockfile: &str, package: &str) -> String {
+    let name = format!("name = \"{}\"", package);
+    let mut lines = lockfile.lines();
+    while let Some(line) = lines.next() {
+        if line.trim() == name {
+            if let Some(version) = lines
+                .next()
+                .and_then(|line| line.trim().strip_prefix("version = "))
+            {
+                return version.trim_matches('"').to_string();
+            }
+        }
+    }
+
+    "unknown".to_string()
+}
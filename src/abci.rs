@@ -0,0 +1,199 @@
+use cosmrs::tendermint::abci::Event;
+use serde::Deserialize;
+
+/// An event emitted while executing a transaction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TxEvent {
+    /// Index of the message that emitted the event, when known.
+    pub msg_index: Option<u32>,
+    pub kind: String,
+    pub attributes: Vec<(String, String)>,
+}
+
+impl TxEvent {
+    /// Value of the first attribute with the given key.
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "msg_index": self.msg_index,
+            "type": self.kind,
+            "attributes": self
+                .attributes
+                .iter()
+                .map(|(key, value)| serde_json::json!({ "key": key, "value": value }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// Commission withdrawn according to the `withdraw_commission` event.
+pub fn withdrawn_amount(events: &[TxEvent]) -> Option<String> {
+    events
+        .iter()
+        .filter(|event| event.kind == "withdraw_commission")
+        .find_map(|event| event.attribute("amount"))
+        .map(str::to_string)
+}
+
+#[derive(Deserialize)]
+struct RawMessageLog {
+    #[serde(default)]
+    msg_index: u32,
+    #[serde(default)]
+    events: Vec<RawEvent>,
+}
+
+#[derive(Deserialize)]
+struct RawEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    attributes: Vec<RawAttribute>,
+}
+
+#[derive(Deserialize)]
+struct RawAttribute {
+    key: String,
+    #[serde(default)]
+    value: String,
+}
+
+/// Decode the events of a transaction result.
+///
+/// SDK 0.50+ leaves the raw log empty and tags events with a `msg_index`
+/// attribute, while older SDKs put a JSON array of per-message logs in the
+/// raw log. Both are handled; the raw log is preferred when it parses because
+/// it is the only place pre-0.50 nodes report the message index.
+pub fn decode(events: &[Event], log: &str) -> Vec<TxEvent> {
+    if let Ok(message_logs) = serde_json::from_str::<Vec<RawMessageLog>>(log) {
+        let decoded: Vec<TxEvent> = message_logs
+            .into_iter()
+            .flat_map(|message_log| {
+                let msg_index = message_log.msg_index;
+                message_log.events.into_iter().map(move |event| TxEvent {
+                    msg_index: Some(msg_index),
+                    kind: event.kind,
+                    attributes: event
+                        .attributes
+                        .into_iter()
+                        .map(|attribute| (attribute.key, attribute.value))
+                        .collect(),
+                })
+            })
+            .collect();
+        if !decoded.is_empty() {
+            return decoded;
+        }
+    }
+
+    events
+        .iter()
+        .map(|event| {
+            let attributes: Vec<(String, String)> = event
+                .attributes
+                .iter()
+                .map(|attribute| {
+                    (
+                        String::from_utf8_lossy(attribute.key_bytes()).into_owned(),
+                        String::from_utf8_lossy(attribute.value_bytes()).into_owned(),
+                    )
+                })
+                .collect();
+            let msg_index = attributes
+                .iter()
+                .find(|(key, _)| key == "msg_index")
+                .and_then(|(_, value)| value.parse().ok());
+
+            TxEvent {
+                msg_index,
+                kind: event.kind.clone(),
+                attributes: attributes
+                    .into_iter()
+                    .filter(|(key, _)| key != "msg_index")
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmrs::rpc::{endpoint::broadcast::tx_commit, Response};
+
+    /// Parse a broadcast_tx_commit fixture with the RPC dialect the node would speak.
+    fn response(fixture: &str, tendermint_0_34: bool) -> tx_commit::Response {
+        if tendermint_0_34 {
+            tx_commit::v0_34::DialectResponse::from_string(fixture)
+                .unwrap()
+                .into()
+        } else {
+            tx_commit::Response::from_string(fixture).unwrap()
+        }
+    }
+
+    fn assert_withdrawal(response: &tx_commit::Response, expect_msg_index: bool) {
+        assert!(response.tx_result.code.is_ok());
+
+        let events = decode(&response.tx_result.events, &response.tx_result.log);
+        assert_eq!(withdrawn_amount(&events).as_deref(), Some("1234567usomm"));
+
+        let action = events
+            .iter()
+            .find_map(|event| event.attribute("action"))
+            .unwrap();
+        assert_eq!(
+            action,
+            "/cosmos.distribution.v1beta1.MsgWithdrawValidatorCommission"
+        );
+
+        let withdraw = events
+            .iter()
+            .find(|event| event.kind == "withdraw_commission")
+            .unwrap();
+        assert_eq!(withdraw.msg_index.is_some(), expect_msg_index);
+        assert!(withdraw.attribute("msg_index").is_none());
+    }
+
+    #[test]
+    fn sdk_0_45() {
+        let fixture = include_str!("../tests/fixtures/broadcast/sdk-0.45.json");
+        assert_withdrawal(&response(fixture, true), true);
+    }
+
+    #[test]
+    fn sdk_0_46() {
+        let fixture = include_str!("../tests/fixtures/broadcast/sdk-0.46.json");
+        assert_withdrawal(&response(fixture, true), true);
+    }
+
+    #[test]
+    fn sdk_0_47() {
+        let fixture = include_str!("../tests/fixtures/broadcast/sdk-0.47.json");
+        assert_withdrawal(&response(fixture, false), true);
+    }
+
+    #[test]
+    fn sdk_0_50() {
+        let fixture = include_str!("../tests/fixtures/broadcast/sdk-0.50.json");
+        assert_withdrawal(&response(fixture, false), true);
+    }
+
+    #[test]
+    fn sdk_0_50_out_of_gas() {
+        let fixture = include_str!("../tests/fixtures/broadcast/sdk-0.50-out-of-gas.json");
+        let response = response(fixture, false);
+        assert_eq!(response.tx_result.code.value(), 11);
+
+        // The raw log is a plain error string, so events come from the event list
+        let events = decode(&response.tx_result.events, &response.tx_result.log);
+        assert_eq!(events.len(), 2);
+        assert_eq!(withdrawn_amount(&events), None);
+    }
+}
@@ -0,0 +1,44 @@
+use crate::endpoint::{at_height, GrpcChannel};
+use cosmrs::proto::cosmos::auth::v1beta1::{
+    query_client::QueryClient as AuthQueryClient, BaseAccount, QueryAccountRequest,
+};
+use cosmrs::proto::prost::Message;
+use eyre::Result;
+
+/// The account number and sequence of `address` at `height`.
+pub async fn account(channel: GrpcChannel, address: &str, height: u64) -> Result<(u64, u64)> {
+    let mut query_client = AuthQueryClient::new(channel);
+    let request = at_height(
+        QueryAccountRequest {
+            address: address.to_string(),
+        },
+        height,
+    );
+    let account_info = match query_client.account(request).await {
+        Ok(account_info) => account_info,
+        Err(e) => {
+            log::error!("Failed to query account info: {}", e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to query account info: {}",
+                e
+            )));
+        }
+    };
+
+    // Query the account information
+    let Some(account_any) = account_info.into_inner().account else {
+        return Err(eyre::Report::msg("Account not found"));
+    };
+    let base_account = match BaseAccount::decode(account_any.value.as_slice()) {
+        Ok(base_account) => base_account,
+        Err(e) => {
+            log::error!("Failed to decode BaseAccount: {}", e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to decode BaseAccount: {}",
+                e
+            )));
+        }
+    };
+
+    Ok((base_account.account_number, base_account.sequence))
+}
@@ -0,0 +1,322 @@
+use crate::endpoint::{at_height, GrpcChannel};
+use cosmrs::proto::cosmos::authz::v1beta1::{
+    query_client::QueryClient as AuthzQueryClient, GenericAuthorization, Grant as AuthzGrant,
+    GrantAuthorization, MsgGrant, MsgRevoke, QueryGranteeGrantsRequest, QueryGranterGrantsRequest,
+};
+use cosmrs::proto::cosmos::base::query::v1beta1::PageRequest;
+use cosmrs::proto::cosmos::staking::v1beta1::{AuthorizationType, StakeAuthorization};
+use cosmrs::proto::prost::Message;
+use cosmrs::tendermint::Time;
+use cosmrs::Any;
+use eyre::Result;
+use serde::Serialize;
+
+/// Type URL of the authorization that allows any use of one message type.
+const GENERIC_AUTHORIZATION: &str = "/cosmos.authz.v1beta1.GenericAuthorization";
+
+/// Type URL of the message that creates a grant.
+const MSG_GRANT: &str = "/cosmos.authz.v1beta1.MsgGrant";
+
+/// Type URL of the message that revokes a grant.
+const MSG_REVOKE: &str = "/cosmos.authz.v1beta1.MsgRevoke";
+
+/// Message types of authorizations that always cover one message type.
+const FIXED_MSG_TYPES: [(&str, &str); 4] = [
+    (
+        "/cosmos.bank.v1beta1.SendAuthorization",
+        "/cosmos.bank.v1beta1.MsgSend",
+    ),
+    (
+        "/ibc.applications.transfer.v1.TransferAuthorization",
+        "/ibc.applications.transfer.v1.MsgTransfer",
+    ),
+    (
+        "/cosmwasm.wasm.v1.ContractExecutionAuthorization",
+        "/cosmwasm.wasm.v1.MsgExecuteContract",
+    ),
+    (
+        "/cosmwasm.wasm.v1.ContractMigrationAuthorization",
+        "/cosmwasm.wasm.v1.MsgMigrateContract",
+    ),
+];
+
+/// Messages a generic grant may cover without being flagged, since executing
+/// them can only move funds to the configured withdraw address.
+const WITHDRAW_MSGS: [&str; 2] = [
+    "/cosmos.distribution.v1beta1.MsgWithdrawValidatorCommission",
+    "/cosmos.distribution.v1beta1.MsgWithdrawDelegatorReward",
+];
+
+/// One authz grant.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Grant {
+    pub granter: String,
+    pub grantee: String,
+    /// Type URL of the authorization
+    pub authorization: String,
+    /// Message type the grant allows, `None` for unknown authorizations
+    pub msg: Option<String>,
+    /// RFC 3339 expiration, `None` when the grant never expires
+    pub expiration: Option<String>,
+    /// A generic authorization for anything but withdrawing commission or rewards
+    pub broad: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Role {
+    Granter,
+    Grantee,
+}
+
+/// Every grant where `address` is the granter or the grantee, at `height`.
+pub async fn grants(channel: GrpcChannel, address: &str, height: u64) -> Result<Vec<Grant>> {
+    let mut grants = query_grants(channel.clone(), address, height, Role::Granter).await?;
+    grants.extend(query_grants(channel, address, height, Role::Grantee).await?);
+
+    Ok(grants)
+}
+
+async fn query_grants(
+    channel: GrpcChannel,
+    address: &str,
+    height: u64,
+    role: Role,
+) -> Result<Vec<Grant>> {
+    let mut client = AuthzQueryClient::new(channel);
+    let mut grants = Vec::new();
+    let mut next_key = Vec::new();
+    loop {
+        let pagination = Some(PageRequest {
+            key: next_key,
+            ..Default::default()
+        });
+        let response = match role {
+            Role::Granter => {
+                let request = at_height(
+                    QueryGranterGrantsRequest {
+                        granter: address.to_string(),
+                        pagination,
+                    },
+                    height,
+                );
+                client
+                    .granter_grants(request)
+                    .await
+                    .map(|response| response.into_inner())
+                    .map(|response| (response.grants, response.pagination))
+            }
+            Role::Grantee => {
+                let request = at_height(
+                    QueryGranteeGrantsRequest {
+                        grantee: address.to_string(),
+                        pagination,
+                    },
+                    height,
+                );
+                client
+                    .grantee_grants(request)
+                    .await
+                    .map(|response| response.into_inner())
+                    .map(|response| (response.grants, response.pagination))
+            }
+        };
+        let (page, pagination) = match response {
+            Ok(response) => response,
+            Err(e) => {
+                log::error!("Failed to query authz grants: {}", e);
+                return Err(eyre::Report::msg(format!(
+                    "Failed to query authz grants: {}",
+                    e
+                )));
+            }
+        };
+        grants.extend(page.into_iter().map(grant));
+
+        next_key = pagination.map(|page| page.next_key).unwrap_or_default();
+        if next_key.is_empty() {
+            return Ok(grants);
+        }
+    }
+}
+
+/// The message that revokes `grant`, or `None` when its message type is
+/// unknown.
+pub fn revoke_msg(grant: &Grant) -> Option<Any> {
+    Some(revoke(
+        &grant.granter,
+        &grant.grantee,
+        grant.msg.as_deref()?,
+    ))
+}
+
+/// Whether `grant` lets its grantee withdraw the granter's commission.
+pub fn allows_commission_withdrawal(grant: &Grant) -> bool {
+    grant.msg.as_deref() == Some(WITHDRAW_MSGS[0])
+}
+
+/// The message that lets `grantee` withdraw the commission of `granter`'s
+/// validator until `expiration`.
+pub fn grant_withdraw_msg(granter: &str, grantee: &str, expiration: Option<Time>) -> Any {
+    let authorization = GenericAuthorization {
+        msg: WITHDRAW_MSGS[0].to_string(),
+    };
+    let msg = MsgGrant {
+        granter: granter.to_string(),
+        grantee: grantee.to_string(),
+        grant: Some(AuthzGrant {
+            authorization: Some(Any {
+                type_url: GENERIC_AUTHORIZATION.to_string(),
+                value: authorization.encode_to_vec(),
+            }),
+            expiration: expiration.map(Into::into),
+        }),
+    };
+
+    Any {
+        type_url: MSG_GRANT.to_string(),
+        value: msg.encode_to_vec(),
+    }
+}
+
+/// The message that revokes the grant made by [`grant_withdraw_msg`].
+pub fn revoke_withdraw_msg(granter: &str, grantee: &str) -> Any {
+    revoke(granter, grantee, WITHDRAW_MSGS[0])
+}
+
+fn revoke(granter: &str, grantee: &str, msg_type_url: &str) -> Any {
+    let msg = MsgRevoke {
+        granter: granter.to_string(),
+        grantee: grantee.to_string(),
+        msg_type_url: msg_type_url.to_string(),
+    };
+
+    Any {
+        type_url: MSG_REVOKE.to_string(),
+        value: msg.encode_to_vec(),
+    }
+}
+
+/// The message type an authorization allows.
+fn msg_type(authorization: &Any) -> Option<String> {
+    let value = authorization.value.as_slice();
+    match authorization.type_url.as_str() {
+        GENERIC_AUTHORIZATION => GenericAuthorization::decode(value)
+            .ok()
+            .map(|generic| generic.msg),
+        "/cosmos.staking.v1beta1.StakeAuthorization" => {
+            let stake = StakeAuthorization::decode(value).ok()?;
+            match stake.authorization_type() {
+                AuthorizationType::Delegate => Some("/cosmos.staking.v1beta1.MsgDelegate"),
+                AuthorizationType::Undelegate => Some("/cosmos.staking.v1beta1.MsgUndelegate"),
+                AuthorizationType::Redelegate => Some("/cosmos.staking.v1beta1.MsgBeginRedelegate"),
+                AuthorizationType::CancelUnbondingDelegation => {
+                    Some("/cosmos.staking.v1beta1.MsgCancelUnbondingDelegation")
+                }
+                AuthorizationType::Unspecified => None,
+            }
+            .map(str::to_string)
+        }
+        type_url => FIXED_MSG_TYPES
+            .iter()
+            .find(|(authorization, _)| *authorization == type_url)
+            .map(|(_, msg)| msg.to_string()),
+    }
+}
+
+fn grant(grant: GrantAuthorization) -> Grant {
+    let authorization = grant.authorization.unwrap_or_default();
+    let msg = msg_type(&authorization);
+    let broad = authorization.type_url == GENERIC_AUTHORIZATION
+        && msg
+            .as_deref()
+            .is_some_and(|msg| !WITHDRAW_MSGS.contains(&msg));
+    let expiration = grant
+        .expiration
+        .and_then(|expiration| Time::try_from(expiration).ok())
+        .map(|expiration| expiration.to_rfc3339());
+
+    Grant {
+        granter: grant.granter,
+        grantee: grant.grantee,
+        authorization: authorization.type_url,
+        msg,
+        expiration,
+        broad,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generic(msg: &str) -> GrantAuthorization {
+        GrantAuthorization {
+            granter: "somm1granter".to_string(),
+            grantee: "somm1grantee".to_string(),
+            authorization: Some(Any {
+                type_url: GENERIC_AUTHORIZATION.to_string(),
+                value: GenericAuthorization {
+                    msg: msg.to_string(),
+                }
+                .encode_to_vec(),
+            }),
+            expiration: None,
+        }
+    }
+
+    #[test]
+    fn flags_generic_grants_beyond_withdrawing() {
+        assert!(grant(generic("/cosmos.bank.v1beta1.MsgSend")).broad);
+        assert!(!grant(generic(WITHDRAW_MSGS[0])).broad);
+    }
+
+    #[test]
+    fn revokes_by_message_type() {
+        let mut send = generic("");
+        send.authorization = Some(Any {
+            type_url: "/cosmos.bank.v1beta1.SendAuthorization".to_string(),
+            value: Vec::new(),
+        });
+        let send = grant(send);
+        assert!(!send.broad);
+        let revoke = MsgRevoke::decode(revoke_msg(&send).unwrap().value.as_slice()).unwrap();
+        assert_eq!(revoke.msg_type_url, "/cosmos.bank.v1beta1.MsgSend");
+        assert_eq!(revoke.granter, "somm1granter");
+
+        let mut unknown = generic("");
+        unknown.authorization = Some(Any {
+            type_url: "/example.v1.CustomAuthorization".to_string(),
+            value: Vec::new(),
+        });
+        assert!(revoke_msg(&grant(unknown)).is_none());
+    }
+
+    #[test]
+    fn grants_withdrawal_until_expiration() {
+        let expiration = Time::parse_from_rfc3339("2027-01-15T08:00:00Z").unwrap();
+        let msg = grant_withdraw_msg("somm1granter", "somm1grantee", Some(expiration));
+        let msg = MsgGrant::decode(msg.value.as_slice()).unwrap();
+        let authz_grant = msg.grant.unwrap();
+        let grant = grant(GrantAuthorization {
+            granter: msg.granter,
+            grantee: msg.grantee,
+            authorization: authz_grant.authorization,
+            expiration: authz_grant.expiration,
+        });
+        assert_eq!(grant.msg.as_deref(), Some(WITHDRAW_MSGS[0]));
+        assert_eq!(grant.expiration.as_deref(), Some("2027-01-15T08:00:00Z"));
+        assert!(!grant.broad);
+    }
+
+    #[test]
+    fn reads_generic_msg_and_expiration() {
+        let mut authorization = generic(WITHDRAW_MSGS[0]);
+        authorization.expiration = Some(cosmrs::proto::Timestamp {
+            seconds: 1_800_000_000,
+            nanos: 0,
+        });
+        let grant = grant(authorization);
+        assert_eq!(grant.msg.as_deref(), Some(WITHDRAW_MSGS[0]));
+        assert_eq!(grant.expiration.as_deref(), Some("2027-01-15T08:00:00Z"));
+    }
+}
@@ -0,0 +1,216 @@
+use crate::decimal;
+use crate::endpoint::{at_height, GrpcChannel};
+use cosmrs::proto::cosmos::bank::v1beta1::{
+    query_client::QueryClient as BankQueryClient, QueryBalanceRequest,
+};
+use cosmrs::proto::cosmos::base::v1beta1::{Coin, DecCoin};
+use eyre::Result;
+use serde::Serialize;
+
+/// The expected change of one denom in one account's balance.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Line {
+    pub address: String,
+    pub denom: String,
+    pub before: u128,
+    /// Whole units of commission credited; the fraction stays with the validator
+    pub commission: u128,
+    /// Fee debited from the fee payer
+    pub fee: u128,
+    /// Negative when the fee exceeds the balance plus commission
+    pub after: i128,
+}
+
+/// The accounts and denoms a withdrawal touches, before their balances are
+/// known: commission is credited to `withdraw_address` and the fee debited
+/// from `fee_payer`, which may be the same account.
+pub fn lines(
+    withdraw_address: &str,
+    fee_payer: &str,
+    commission: &[DecCoin],
+    fee: &[Coin],
+) -> Result<Vec<Line>> {
+    let mut lines = Vec::new();
+    for coin in commission {
+        let Some(amount) = decimal::whole_dec(&coin.amount) else {
+            return Err(eyre::Report::msg(format!(
+                "Invalid commission amount {}{}",
+                coin.amount, coin.denom
+            )));
+        };
+        if amount > 0 {
+            line(&mut lines, withdraw_address, &coin.denom).commission += amount;
+        }
+    }
+    for coin in fee {
+        let amount: u128 = match coin.amount.parse() {
+            Ok(amount) => amount,
+            Err(e) => {
+                return Err(eyre::Report::msg(format!(
+                    "Invalid fee amount {}{}: {}",
+                    coin.amount, coin.denom, e
+                )))
+            }
+        };
+        if amount > 0 {
+            line(&mut lines, fee_payer, &coin.denom).fee += amount;
+        }
+    }
+
+    Ok(lines)
+}
+
+/// The line of `denom` in `address`, added if missing.
+fn line<'a>(lines: &'a mut Vec<Line>, address: &str, denom: &str) -> &'a mut Line {
+    let i = match lines
+        .iter()
+        .position(|line| line.address == address && line.denom == denom)
+    {
+        Some(i) => i,
+        None => {
+            lines.push(Line {
+                address: address.to_string(),
+                denom: denom.to_string(),
+                before: 0,
+                commission: 0,
+                fee: 0,
+                after: 0,
+            });
+            lines.len() - 1
+        }
+    };
+    &mut lines[i]
+}
+
+impl Line {
+    /// Fill in the balance before the withdrawal and the resulting balance.
+    pub fn set_before(&mut self, before: u128) -> Result<()> {
+        let signed = |amount: u128| i128::try_from(amount).ok();
+        let after = signed(before)
+            .zip(signed(self.commission))
+            .and_then(|(before, commission)| before.checked_add(commission))
+            .zip(signed(self.fee))
+            .and_then(|(after, fee)| after.checked_sub(fee));
+        let Some(after) = after else {
+            return Err(eyre::Report::msg(format!(
+                "Balance of {} in {} is out of range",
+                self.denom, self.address
+            )));
+        };
+        self.before = before;
+        self.after = after;
+
+        Ok(())
+    }
+}
+
+/// The bank balance of `denom` held by `address` at `height`.
+pub async fn balance(
+    channel: GrpcChannel,
+    address: &str,
+    denom: &str,
+    height: u64,
+) -> Result<u128> {
+    let mut client = BankQueryClient::new(channel);
+    let request = at_height(
+        QueryBalanceRequest {
+            address: address.to_string(),
+            denom: denom.to_string(),
+        },
+        height,
+    );
+    match client.balance(request).await {
+        Ok(response) => match response.into_inner().balance {
+            Some(coin) => match coin.amount.parse() {
+                Ok(amount) => Ok(amount),
+                Err(e) => Err(eyre::Report::msg(format!(
+                    "Invalid balance {}{} of {}: {}",
+                    coin.amount, coin.denom, address, e
+                ))),
+            },
+            None => Ok(0),
+        },
+        Err(e) => {
+            log::error!("Failed to query balance: {}", e);
+            Err(eyre::Report::msg(format!("Failed to query balance: {}", e)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec_coin(denom: &str, amount: &str) -> DecCoin {
+        DecCoin {
+            denom: denom.to_string(),
+            amount: amount.to_string(),
+        }
+    }
+
+    fn coin(denom: &str, amount: &str) -> Coin {
+        Coin {
+            denom: denom.to_string(),
+            amount: amount.to_string(),
+        }
+    }
+
+    #[test]
+    fn combines_credit_and_debit_on_one_account() {
+        let mut lines = lines(
+            "somm1a",
+            "somm1a",
+            &[dec_coin("usomm", "2500900000000000000000")],
+            &[coin("usomm", "1000")],
+        )
+        .unwrap();
+        lines[0].set_before(10).unwrap();
+        assert_eq!(
+            lines,
+            [Line {
+                address: "somm1a".to_string(),
+                denom: "usomm".to_string(),
+                before: 10,
+                commission: 2500,
+                fee: 1000,
+                after: 1510,
+            }]
+        );
+    }
+
+    #[test]
+    fn separates_withdraw_address_and_fee_payer() {
+        let lines = lines(
+            "somm1w",
+            "somm1a",
+            &[dec_coin("usomm", "5.5"), dec_coin("uatom", "0.9")],
+            &[coin("usomm", "1000")],
+        )
+        .unwrap();
+        let summary: Vec<_> = lines
+            .iter()
+            .map(|line| (line.address.as_str(), line.commission, line.fee))
+            .collect();
+        assert_eq!(summary, [("somm1w", 5, 0), ("somm1a", 0, 1000)]);
+    }
+
+    #[test]
+    fn counts_18_exponent_commission_beyond_a_scaled_u128() {
+        // A million EVMOS, whose scaled gRPC amount does not fit a u128
+        let million = format!("1{}", "0".repeat(24 + decimal::PRECISION));
+        let mut lines = lines(
+            "evmos1a",
+            "evmos1a",
+            &[dec_coin("aevmos", &million)],
+            &[coin("aevmos", "4000000000000000")],
+        )
+        .unwrap();
+        assert_eq!(lines[0].commission, 10u128.pow(24));
+        lines[0].set_before(0).unwrap();
+        assert_eq!(lines[0].after, 10i128.pow(24) - 4_000_000_000_000_000);
+
+        assert!(super::lines("evmos1a", "evmos1a", &[dec_coin("aevmos", "lots")], &[]).is_err());
+        lines[0].commission = u128::MAX;
+        assert!(lines[0].set_before(0).is_err());
+    }
+}
@@ -0,0 +1,94 @@
+use crate::endpoint::RpcClient;
+use cosmrs::rpc::{endpoint::broadcast::tx_commit, Client};
+use cosmrs::tendermint::{abci::response::CheckTx, Hash};
+use eyre::Result;
+use sha2::{Digest, Sha256};
+use std::time::{Duration, Instant};
+
+/// SDK error code for a transaction that is already in the mempool cache.
+const CODE_TX_IN_MEMPOOL_CACHE: u32 = 19;
+
+/// What to do when the node reports that the transaction is already in its
+/// mempool cache, typically because a previous attempt was accepted before a
+/// network blip hid the response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum AlreadyInCache {
+    /// Treat the transaction as pending and wait for it to be committed
+    Confirm,
+    /// Report the broadcast as failed
+    Fail,
+}
+
+/// Broadcast a transaction and wait for it to be committed, handling
+/// "already in cache" responses according to `policy`.
+pub async fn broadcast(
+    client: &RpcClient,
+    tx_bytes: Vec<u8>,
+    policy: AlreadyInCache,
+    confirm_timeout: Duration,
+) -> Result<tx_commit::Response> {
+    let hash = tx_hash(&tx_bytes);
+    let response = match client.broadcast_tx_commit(tx_bytes).await {
+        Ok(response) => response,
+        Err(e) if policy == AlreadyInCache::Confirm && is_already_in_cache(&e.to_string()) => {
+            log::warn!("Transaction {} is already in the mempool cache", hash);
+            return wait_for_tx(client, hash, confirm_timeout).await;
+        }
+        Err(e) => {
+            return Err(eyre::Report::msg(format!(
+                "Failed to broadcast transaction: {}",
+                e
+            )))
+        }
+    };
+
+    if policy == AlreadyInCache::Confirm
+        && response.check_tx.code.value() == CODE_TX_IN_MEMPOOL_CACHE
+        && response.check_tx.codespace == "sdk"
+    {
+        log::warn!("Transaction {} is already in the mempool cache", hash);
+        return wait_for_tx(client, hash, confirm_timeout).await;
+    }
+
+    Ok(response)
+}
+
+/// Poll for a transaction by hash until it is committed or the timeout passes.
+pub async fn wait_for_tx(
+    client: &RpcClient,
+    hash: Hash,
+    timeout: Duration,
+) -> Result<tx_commit::Response> {
+    log::info!("Waiting up to {:?} for transaction {}", timeout, hash);
+    let started = Instant::now();
+    loop {
+        match client.tx(hash, false).await {
+            Ok(tx) => {
+                return Ok(tx_commit::Response {
+                    check_tx: CheckTx::default(),
+                    tx_result: tx.tx_result,
+                    hash: tx.hash,
+                    height: tx.height,
+                })
+            }
+            Err(e) => log::debug!("Transaction {} not found yet: {}", hash, e),
+        }
+
+        if started.elapsed() >= timeout {
+            return Err(eyre::Report::msg(format!(
+                "Transaction {} was not committed within {:?}",
+                hash, timeout
+            )));
+        }
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+}
+
+/// The CometBFT transaction hash (SHA-256 of the raw transaction bytes).
+pub fn tx_hash(tx_bytes: &[u8]) -> Hash {
+    Hash::Sha256(Sha256::digest(tx_bytes).into())
+}
+
+fn is_already_in_cache(message: &str) -> bool {
+    message.contains("tx already exists in cache")
+}
@@ -0,0 +1,41 @@
+/// Short git commit the binary was built from.
+pub const GIT_COMMIT: &str = env!("BUILD_GIT_COMMIT");
+
+/// RFC 3339 build timestamp (UTC).
+pub const BUILD_DATE: &str = env!("BUILD_DATE");
+
+/// Comma separated cargo features enabled at build time.
+pub const FEATURES: &str = env!("BUILD_FEATURES");
+
+pub const COSMRS_VERSION: &str = env!("BUILD_COSMRS_VERSION");
+pub const TENDERMINT_VERSION: &str = env!("BUILD_TENDERMINT_VERSION");
+
+/// Multi-line description of the build for `--version --verbose`.
+pub fn verbose_version() -> String {
+    format!(
+        "{} {}\ncommit: {}\nbuild date: {}\nfeatures: {}\ncosmrs: {}\ntendermint-rs: {}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        GIT_COMMIT,
+        BUILD_DATE,
+        if FEATURES.is_empty() {
+            "none"
+        } else {
+            FEATURES
+        },
+        COSMRS_VERSION,
+        TENDERMINT_VERSION
+    )
+}
+
+/// Build details included in machine-readable run output.
+pub fn to_json() -> serde_json::Value {
+    serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "git_commit": GIT_COMMIT,
+        "build_date": BUILD_DATE,
+        "features": FEATURES.split(',').filter(|feature| !feature.is_empty()).collect::<Vec<_>>(),
+        "cosmrs": COSMRS_VERSION,
+        "tendermint": TENDERMINT_VERSION,
+    })
+}
@@ -0,0 +1,119 @@
+use crate::outcome::RunOutcome;
+use eyre::Result;
+use std::str::FromStr;
+
+/// An HTTP endpoint that receives the result of every run.
+#[derive(Debug, Clone)]
+pub struct Callback {
+    pub url: String,
+    pub method: reqwest::Method,
+    pub headers: Vec<(String, String)>,
+    /// Body template; `None` sends the outcome as a JSON object.
+    pub body_template: Option<String>,
+}
+
+/// Substitute `{name}` placeholders in a template with values from the outcome.
+/// Unknown placeholders are left untouched.
+pub fn render(template: &str, outcome: &RunOutcome) -> String {
+    placeholders(outcome)
+        .into_iter()
+        .fold(template.to_string(), |body, (name, value)| {
+            body.replace(&format!("{{{}}}", name), &value)
+        })
+}
+
+fn placeholders(outcome: &RunOutcome) -> Vec<(&'static str, String)> {
+    let failure = outcome.failure();
+    vec![
+        ("chain_id", outcome.chain_id.clone()),
+        ("validator", outcome.validator.clone()),
+        ("status", outcome.status().to_string()),
+        ("tx_hash", outcome.tx_hash.clone()),
+        ("height", outcome.height.to_string()),
+        ("code", outcome.code.to_string()),
+        ("log", outcome.log.clone()),
+        ("amount", outcome.amount.clone()),
+        (
+            "failure_class",
+            failure
+                .map(|class| class.as_str())
+                .unwrap_or_default()
+                .to_string(),
+        ),
+        (
+            "remediation",
+            failure
+                .map(|class| class.remediation())
+                .unwrap_or_default()
+                .to_string(),
+        ),
+        (
+            "doc_link",
+            failure
+                .map(|class| class.doc_link())
+                .unwrap_or_default()
+                .to_string(),
+        ),
+    ]
+}
+
+/// Parse a `Name: value` header argument.
+pub fn parse_header(input: &str) -> Result<(String, String)> {
+    match input.split_once(':') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(eyre::Report::msg(format!(
+            "Invalid header {:?}, expected \"Name: value\"",
+            input
+        ))),
+    }
+}
+
+/// Parse an HTTP method such as `POST` or `PUT`.
+pub fn parse_method(input: &str) -> Result<reqwest::Method> {
+    match reqwest::Method::from_str(&input.to_uppercase()) {
+        Ok(method) => Ok(method),
+        Err(e) => Err(eyre::Report::msg(format!(
+            "Invalid HTTP method {:?}: {}",
+            input, e
+        ))),
+    }
+}
+
+/// Deliver the outcome to the callback endpoint.
+pub async fn send(callback: &Callback, outcome: &RunOutcome) -> Result<()> {
+    let (body, content_type) = match &callback.body_template {
+        Some(template) => (render(template, outcome), "text/plain"),
+        None => (outcome.to_json().to_string(), "application/json"),
+    };
+
+    let mut request = reqwest::Client::new()
+        .request(callback.method.clone(), &callback.url)
+        .body(body);
+    if !callback
+        .headers
+        .iter()
+        .any(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+    {
+        request = request.header(reqwest::header::CONTENT_TYPE, content_type);
+    }
+    for (name, value) in &callback.headers {
+        request = request.header(name, value);
+    }
+
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            return Err(eyre::Report::msg(format!("Failed to send callback: {}", e)));
+        }
+    };
+    if !response.status().is_success() {
+        return Err(eyre::Report::msg(format!(
+            "Callback returned {}",
+            response.status()
+        )));
+    }
+
+    Ok(())
+}
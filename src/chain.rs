@@ -0,0 +1,103 @@
+use eyre::Result;
+
+/// Bech32 prefixes and BIP-44 coin type for a chain whose conventions are not
+/// simply `<prefix>` / `<prefix>valoper` / 118, or that is common enough to name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainPreset {
+    pub name: &'static str,
+    pub account_prefix: &'static str,
+    pub valoper_prefix: &'static str,
+    /// Coin type the chain's wallets derive keys at, e.g. 330 for Terra
+    pub coin_type: u32,
+}
+
+pub const PRESETS: &[ChainPreset] = &[
+    ChainPreset {
+        name: "sommelier",
+        account_prefix: "somm",
+        valoper_prefix: "sommvaloper",
+        coin_type: 118,
+    },
+    ChainPreset {
+        name: "cosmoshub",
+        account_prefix: "cosmos",
+        valoper_prefix: "cosmosvaloper",
+        coin_type: 118,
+    },
+    ChainPreset {
+        name: "osmosis",
+        account_prefix: "osmo",
+        valoper_prefix: "osmovaloper",
+        coin_type: 118,
+    },
+    ChainPreset {
+        name: "terra",
+        account_prefix: "terra",
+        valoper_prefix: "terravaloper",
+        coin_type: 330,
+    },
+    ChainPreset {
+        name: "secret",
+        account_prefix: "secret",
+        valoper_prefix: "secretvaloper",
+        coin_type: 529,
+    },
+    ChainPreset {
+        name: "crypto-org",
+        account_prefix: "cro",
+        valoper_prefix: "crocncl",
+        coin_type: 394,
+    },
+];
+
+/// Look up a preset by name, for use as a clap value parser.
+pub fn parse_preset(name: &str) -> Result<ChainPreset> {
+    match PRESETS.iter().find(|preset| preset.name == name) {
+        Some(preset) => Ok(*preset),
+        None => Err(eyre::Report::msg(format!(
+            "Unknown chain preset {:?}, expected one of: {}",
+            name,
+            PRESETS
+                .iter()
+                .map(|preset| preset.name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))),
+    }
+}
+
+/// Resolve the HD path a mnemonic is derived at: the explicit path, or the
+/// standard Cosmos path with the preset's coin type (118 without a preset).
+pub fn hd_path(preset: Option<&ChainPreset>, hd_path: Option<&str>) -> String {
+    match hd_path {
+        Some(hd_path) => hd_path.to_string(),
+        None => crate::keys::hd_path(
+            preset
+                .map(|preset| preset.coin_type)
+                .unwrap_or(crate::keys::DEFAULT_COIN_TYPE),
+        ),
+    }
+}
+
+/// Resolve the account and validator operator prefixes. Explicit prefixes
+/// win over the preset; without either, Sommelier's prefixes are used and the
+/// operator prefix is derived as `<account prefix>valoper`.
+pub fn prefixes(
+    preset: Option<&ChainPreset>,
+    account_prefix: Option<&str>,
+    valoper_prefix: Option<&str>,
+) -> (String, String) {
+    let account = account_prefix
+        .or(preset.map(|preset| preset.account_prefix))
+        .unwrap_or("somm")
+        .to_string();
+    let valoper = match (valoper_prefix, preset, account_prefix) {
+        (Some(valoper), _, _) => valoper.to_string(),
+        // An explicit account prefix overrides the preset, so derive from it
+        (None, _, Some(account)) => format!("{}valoper", account),
+        (None, Some(preset), None) => preset.valoper_prefix.to_string(),
+        (None, None, None) => format!("{}valoper", account),
+    };
+
+    (account, valoper)
+}
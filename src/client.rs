@@ -0,0 +1,266 @@
+use crate::account;
+use crate::broadcast::{self, AlreadyInCache};
+use crate::consumer;
+use crate::cross_check::{self, Mismatch};
+use crate::decimal::Decimal;
+use crate::endpoint::{self, GrpcChannel, RpcClient};
+use crate::outcome::RunOutcome;
+use crate::slashing;
+use crate::tx::WithdrawTx;
+use cosmrs::proto::cosmos::base::abci::v1beta1::GasInfo;
+use cosmrs::proto::cosmos::tx::v1beta1::{
+    service_client::ServiceClient as TxServiceClient, SimulateRequest,
+};
+use cosmrs::rpc::endpoint::broadcast::tx_commit;
+use cosmrs::tx::{Body, Fee};
+use eyre::Result;
+use std::time::Duration;
+
+/// Environment variable that hard-disables signing and broadcasting. It is
+/// meant to be set on every host except the designated broadcaster, so a copied
+/// command line or config cannot sign from the wrong host; no flag turns it off.
+pub const READ_ONLY_ENV: &str = "WITHDRAW_COMMISSION_READ_ONLY";
+
+/// Blocks to look back for slashes unless told otherwise.
+pub const DEFAULT_SLASH_LOOKBACK_BLOCKS: u64 = 100_000;
+
+/// Whether the read-only environment variable is set.
+pub fn read_only_env() -> bool {
+    match std::env::var(READ_ONLY_ENV) {
+        Ok(value) => !matches!(value.trim(), "" | "0" | "false"),
+        Err(_) => false,
+    }
+}
+
+/// A second endpoint whose reads must match the first one's before signing.
+#[derive(Clone)]
+pub struct CrossCheck {
+    pub grpc: GrpcChannel,
+    /// How far commission amounts may differ, in percent of the larger one
+    pub tolerance_percent: Decimal,
+    pub on_mismatch: Mismatch,
+}
+
+/// A withdrawal transaction together with the connections needed to sign and
+/// broadcast it, and the safety checks to pass first.
+pub struct WithdrawClient {
+    pub tx: WithdrawTx,
+    pub grpc: GrpcChannel,
+    pub rpc: RpcClient,
+    pub on_already_in_cache: AlreadyInCache,
+    pub confirm_timeout: Duration,
+    /// Refuse to sign and broadcast; only ever turned on, see
+    /// [`WithdrawClient::set_read_only`]
+    read_only: bool,
+    /// Hold the withdrawal if the validator is jailed or was slashed within
+    /// this many blocks; `None` skips the check
+    pub slash_lookback_blocks: Option<u64>,
+    pub cross_check: Option<CrossCheck>,
+}
+
+impl WithdrawClient {
+    pub fn new(tx: WithdrawTx, grpc: GrpcChannel, rpc: RpcClient) -> Self {
+        WithdrawClient {
+            tx,
+            grpc,
+            rpc,
+            on_already_in_cache: AlreadyInCache::Confirm,
+            confirm_timeout: Duration::from_secs(60),
+            read_only: read_only_env(),
+            slash_lookback_blocks: Some(DEFAULT_SLASH_LOOKBACK_BLOCKS),
+            cross_check: None,
+        }
+    }
+
+    /// Whether the client refuses to sign and broadcast, because
+    /// [`READ_ONLY_ENV`] is set or [`WithdrawClient::set_read_only`] was
+    /// called.
+    pub fn read_only(&self) -> bool {
+        self.read_only || read_only_env()
+    }
+
+    /// Refuse to sign and broadcast from now on. There is no way back, so a
+    /// host marked read-only stays read-only.
+    pub fn set_read_only(&mut self) {
+        self.read_only = true;
+    }
+
+    /// Fail in read-only mode, before anything is signed or broadcast.
+    fn refuse_read_only(&self) -> Result<()> {
+        if self.read_only() {
+            log::error!(
+                "Read-only mode is set (--read-only or {}), refusing to sign and broadcast",
+                READ_ONLY_ENV
+            );
+            return Err(eyre::Report::msg(format!(
+                "Read-only mode is set (--read-only or {}), refusing to sign and broadcast",
+                READ_ONLY_ENV
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Connect to the gRPC and RPC endpoints directly.
+    pub async fn connect(tx: WithdrawTx, grpc_url: &str, rpc_url: &str) -> Result<Self> {
+        let grpc = endpoint::connect_grpc(grpc_url, false, None).await?;
+        let rpc = endpoint::connect_rpc(rpc_url, None).await?;
+
+        Ok(WithdrawClient::new(tx, grpc, rpc))
+    }
+
+    /// The signer's account number and sequence at `height`.
+    pub async fn account(&self, height: u64) -> Result<(u64, u64)> {
+        account::account(
+            self.grpc.clone(),
+            self.tx.account_address()?.as_ref(),
+            height,
+        )
+        .await
+    }
+
+    /// Ask the node how much gas `tx_bytes` would use, at the latest height.
+    pub async fn simulate(&self, tx_bytes: Vec<u8>) -> Result<GasInfo> {
+        let mut service_client = TxServiceClient::new(self.grpc.clone());
+        #[allow(deprecated)]
+        let request = SimulateRequest { tx: None, tx_bytes };
+        match service_client.simulate(request).await {
+            Ok(response) => Ok(response.into_inner().gas_info.unwrap_or_default()),
+            Err(e) => {
+                log::error!("Failed to simulate transaction: {}", e);
+                Err(eyre::Report::msg(format!(
+                    "Failed to simulate transaction: {}",
+                    e
+                )))
+            }
+        }
+    }
+
+    /// The fee for `body` signed at `sequence`. With a gas price set, the gas
+    /// limit and fee come from simulating the transaction; otherwise they are
+    /// the fixed fee and the gas table's estimate.
+    pub async fn fee(&self, body: &Body, sequence: u64) -> Result<Fee> {
+        let fee = self.tx.fee(body)?;
+        if self.tx.gas_price.is_none() {
+            return Ok(fee);
+        }
+        let tx_bytes = self.tx.unsigned(body, fee, sequence)?;
+        let gas_info = self.simulate(tx_bytes).await?;
+        let fee = self.tx.simulated_fee(gas_info.gas_used)?;
+        log::info!(
+            "Simulation used {} gas, so the gas limit is {} and the fee {}",
+            gas_info.gas_used,
+            fee.gas_limit,
+            fee.amount
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+
+        Ok(fee)
+    }
+
+    /// The safety checks to pass before signing, against the state at
+    /// `height`: the chain must pay commission to the validator, which must
+    /// not be jailed or recently slashed, and a cross-check endpoint must
+    /// agree with the first one.
+    pub async fn check(&self, height: u64) -> Result<()> {
+        let operator_address = self.tx.operator_address()?;
+
+        // Explain chains that cannot pay commission, such as ICS consumer chains
+        consumer::ensure_commission_supported(self.grpc.clone(), operator_address.as_ref(), height)
+            .await?;
+
+        if let Some(lookback_blocks) = self.slash_lookback_blocks {
+            let concerns = slashing::slashing_concerns(
+                self.grpc.clone(),
+                operator_address.as_ref(),
+                height,
+                lookback_blocks,
+            )
+            .await?;
+            if !concerns.is_empty() {
+                for concern in &concerns {
+                    log::error!("Withdrawal paused for review: {}", concern);
+                }
+                return Err(eyre::Report::msg(format!(
+                    "Withdrawal paused for review ({}); rerun with --ignore-jailed to override",
+                    concerns.join(", ")
+                )));
+            }
+        }
+
+        // Guard against a single lying or stale endpoint
+        if let Some(check) = &self.cross_check {
+            let disagreements = cross_check::cross_check(
+                self.grpc.clone(),
+                check.grpc.clone(),
+                self.tx.account_address()?.as_ref(),
+                operator_address.as_ref(),
+                height,
+                check.tolerance_percent,
+            )
+            .await?;
+            for disagreement in &disagreements {
+                log::warn!("Endpoints disagree: {}", disagreement);
+            }
+            if !disagreements.is_empty() && check.on_mismatch == Mismatch::Abort {
+                return Err(eyre::Report::msg(format!(
+                    "Endpoints disagree ({}); rerun with --on-cross-check-mismatch warn to override",
+                    disagreements.join(", ")
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sign `body` with the signer's account at `height`, unless the client
+    /// is read-only. The fee is worked out first, so a read-only run still
+    /// simulates the transaction.
+    pub async fn sign(&self, body: &Body, height: u64) -> Result<Vec<u8>> {
+        let (account_number, sequence) = self.account(height).await?;
+        let fee = self.fee(body, sequence).await?;
+
+        // Stop before signing on hosts that must never broadcast
+        self.refuse_read_only()?;
+
+        self.tx.sign(body, fee, account_number, sequence)
+    }
+
+    /// Broadcast signed transaction bytes and wait for the result, unless the
+    /// client is read-only.
+    pub async fn broadcast(&self, tx_bytes: Vec<u8>) -> Result<tx_commit::Response> {
+        self.refuse_read_only()?;
+        broadcast::broadcast(
+            &self.rpc,
+            tx_bytes,
+            self.on_already_in_cache,
+            self.confirm_timeout,
+        )
+        .await
+    }
+}
+
+/// Withdraw the validator's commission: run the client's safety checks
+/// ([`WithdrawClient::check`]), then build, sign and broadcast the transaction
+/// against the latest state, refusing to sign if the client is read-only.
+/// Warnings about untested node software ([`crate::compat`]) are left to the
+/// caller.
+pub async fn withdraw_commission(client: &WithdrawClient) -> Result<RunOutcome> {
+    let height = endpoint::latest_height(client.grpc.clone()).await?;
+    client.check(height).await?;
+    let body = client.tx.body()?;
+    let tx_bytes = client.sign(&body, height).await?;
+
+    let mut outcome = RunOutcome {
+        chain_id: client.tx.chain_id.clone(),
+        validator: client.tx.operator_address()?.to_string(),
+        ..Default::default()
+    };
+    let response = client.broadcast(tx_bytes).await?;
+    outcome.record(&response);
+
+    Ok(outcome)
+}
@@ -0,0 +1,206 @@
+use crate::decimal::{self, Decimal, Rounding, PRECISION as DEC_PRECISION};
+use crate::endpoint::{at_height, GrpcChannel};
+use cosmrs::proto::cosmos::base::v1beta1::DecCoin;
+use cosmrs::proto::cosmos::distribution::v1beta1::{
+    query_client::QueryClient as DistributionQueryClient, QueryDelegationTotalRewardsRequest,
+    QueryDelegatorWithdrawAddressRequest, QueryValidatorCommissionRequest,
+};
+use eyre::Result;
+
+/// Commission the validator has accrued but not withdrawn yet, at `height`.
+pub async fn pending_commission(
+    channel: GrpcChannel,
+    validator_operator_address: &str,
+    height: u64,
+) -> Result<Vec<DecCoin>> {
+    let mut client = DistributionQueryClient::new(channel);
+    let request = at_height(
+        QueryValidatorCommissionRequest {
+            validator_address: validator_operator_address.to_string(),
+        },
+        height,
+    );
+    match client.validator_commission(request).await {
+        Ok(response) => Ok(response
+            .into_inner()
+            .commission
+            .map(|commission| commission.commission)
+            .unwrap_or_default()),
+        Err(e) => {
+            log::error!("Failed to query validator commission: {}", e);
+            Err(eyre::Report::msg(format!(
+                "Failed to query validator commission: {}",
+                e
+            )))
+        }
+    }
+}
+
+/// The address that receives withdrawn commission and rewards of `address`,
+/// which is `address` itself unless a withdraw address was set.
+pub async fn withdraw_address(channel: GrpcChannel, address: &str, height: u64) -> Result<String> {
+    let mut client = DistributionQueryClient::new(channel);
+    let request = at_height(
+        QueryDelegatorWithdrawAddressRequest {
+            delegator_address: address.to_string(),
+        },
+        height,
+    );
+    match client.delegator_withdraw_address(request).await {
+        Ok(response) => Ok(response.into_inner().withdraw_address),
+        Err(e) => {
+            log::error!("Failed to query withdraw address: {}", e);
+            Err(eyre::Report::msg(format!(
+                "Failed to query withdraw address: {}",
+                e
+            )))
+        }
+    }
+}
+
+/// Pending delegation rewards of a delegator.
+#[derive(Debug, Clone, Default)]
+pub struct DelegationRewards {
+    /// Validators with pending rewards
+    pub validators: Vec<String>,
+    /// Rewards summed over all validators
+    pub total: Vec<DecCoin>,
+}
+
+/// The delegation rewards `delegator_address` has pending, at `height`.
+pub async fn delegation_rewards(
+    channel: GrpcChannel,
+    delegator_address: &str,
+    height: u64,
+) -> Result<DelegationRewards> {
+    let mut client = DistributionQueryClient::new(channel);
+    let request = at_height(
+        QueryDelegationTotalRewardsRequest {
+            delegator_address: delegator_address.to_string(),
+        },
+        height,
+    );
+    match client.delegation_total_rewards(request).await {
+        Ok(response) => {
+            let response = response.into_inner();
+            Ok(DelegationRewards {
+                validators: response
+                    .rewards
+                    .into_iter()
+                    .filter(|reward| !reward.reward.is_empty())
+                    .map(|reward| reward.validator_address)
+                    .collect(),
+                total: response.total,
+            })
+        }
+        Err(e) => {
+            log::error!("Failed to query delegation rewards: {}", e);
+            Err(eyre::Report::msg(format!(
+                "Failed to query delegation rewards: {}",
+                e
+            )))
+        }
+    }
+}
+
+/// The whole units of the pending `denom` commission that `percent` of it
+/// comes to, rounded down so the shares never exceed what is withdrawn.
+pub fn commission_share(commission: &[DecCoin], denom: &str, percent: Decimal) -> u128 {
+    let whole = whole_amount(commission, denom);
+
+    // A fraction of at most one cannot overflow
+    percent
+        .percent()
+        .mul_integer(whole, Rounding::Down)
+        .unwrap_or_default()
+}
+
+/// The whole units of `denom` in `coins`, rounded down as the distribution
+/// module does when it pays them out. Whatever the denom's exponent, the
+/// amount is read without going through a scaled integer that could overflow.
+pub fn whole_amount(coins: &[DecCoin], denom: &str) -> u128 {
+    coins
+        .iter()
+        .find(|coin| coin.denom == denom)
+        .and_then(|coin| decimal::whole_dec(&coin.amount))
+        .unwrap_or_default()
+}
+
+/// Parse a percentage between 0 and 100 with at most 16 decimal places.
+pub fn parse_percent(input: &str) -> Result<Decimal> {
+    let number = input.trim_end_matches('%');
+    // Two decimal places go to turning the percentage into a fraction
+    let places = number.split_once('.').map_or(0, |(_, places)| places.len());
+    match number.parse::<Decimal>() {
+        Ok(percent) if percent <= Decimal::HUNDRED && places <= DEC_PRECISION - 2 => Ok(percent),
+        _ => Err(eyre::Report::msg(format!(
+            "Invalid percentage {:?}, expected a number between 0 and 100",
+            input
+        ))),
+    }
+}
+
+/// Render a `DecCoin` amount as a decimal number. Amounts that already carry
+/// a decimal point (amino JSON style) are passed through.
+pub fn format_amount(amount: &str) -> String {
+    if amount.contains('.') || amount.is_empty() {
+        return amount.to_string();
+    }
+    let padded = format!("{:0>width$}", amount, width = DEC_PRECISION + 1);
+    let (integer, fraction) = padded.split_at(padded.len() - DEC_PRECISION);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        integer.to_string()
+    } else {
+        format!("{}.{}", integer, fraction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{commission_share, format_amount, parse_percent, DecCoin, DEC_PRECISION};
+
+    #[test]
+    fn formats_scaled_decimals() {
+        assert_eq!(format_amount("1234500000000000000000"), "1234.5");
+        assert_eq!(format_amount("5000000000000000"), "0.005");
+        assert_eq!(format_amount("2000000000000000000"), "2");
+        assert_eq!(format_amount("0"), "0");
+        assert_eq!(format_amount("12.5"), "12.5");
+    }
+
+    #[test]
+    fn shares_whole_units_of_the_denom() {
+        let commission = vec![
+            DecCoin {
+                denom: "ibc/27394FB0".to_string(),
+                amount: "9000000000000000000000".to_string(),
+            },
+            DecCoin {
+                denom: "usomm".to_string(),
+                amount: "1234567800000000000000".to_string(),
+            },
+        ];
+        let percent = |input| parse_percent(input).unwrap();
+        assert_eq!(commission_share(&commission, "usomm", percent("50")), 617);
+        assert_eq!(commission_share(&commission, "usomm", percent("12.5")), 154);
+        // Rounding to basis points would have made this all of it
+        assert_eq!(
+            commission_share(&commission, "ibc/27394FB0", percent("99.995")),
+            8999
+        );
+        assert_eq!(commission_share(&commission, "uatom", percent("50")), 0);
+        // An 18-exponent denom with a million whole tokens of commission
+        let evmos = vec![DecCoin {
+            denom: "aevmos".to_string(),
+            amount: format!("1{}", "0".repeat(24 + DEC_PRECISION)),
+        }];
+        assert_eq!(
+            commission_share(&evmos, "aevmos", percent("12.5")),
+            125_000_000_000_000_000_000_000
+        );
+        assert_eq!(percent("25%").to_string(), "25");
+        assert!(parse_percent("101").is_err());
+        assert!(parse_percent("0.00000000000000001").is_err());
+    }
+}
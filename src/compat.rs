@@ -0,0 +1,82 @@
+use crate::endpoint::GrpcChannel;
+use cosmrs::proto::cosmos::base::tendermint::v1beta1::{
+    service_client::ServiceClient, GetNodeInfoRequest,
+};
+use eyre::Result;
+
+/// Cosmos SDK release lines this tool has been tested against.
+pub const TESTED_SDK_VERSIONS: &[&str] = &["0.46", "0.47", "0.50"];
+
+/// CometBFT/Tendermint release lines this tool has been tested against.
+pub const TESTED_COMET_VERSIONS: &[&str] = &["0.34", "0.37", "0.38"];
+
+/// Software versions reported by the node.
+#[derive(Debug, Clone, Default)]
+pub struct NodeVersions {
+    pub app_name: String,
+    pub app_version: String,
+    pub sdk_version: String,
+    pub comet_version: String,
+}
+
+/// Ask the node which application, SDK and CometBFT versions it runs.
+pub async fn node_versions(channel: GrpcChannel) -> Result<NodeVersions> {
+    let mut client = ServiceClient::new(channel);
+    let response = match client
+        .get_node_info(tonic::Request::new(GetNodeInfoRequest {}))
+        .await
+    {
+        Ok(response) => response.into_inner(),
+        Err(e) => {
+            log::error!("Failed to query node info: {}", e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to query node info: {}",
+                e
+            )));
+        }
+    };
+
+    let application = response.application_version.unwrap_or_default();
+    Ok(NodeVersions {
+        app_name: application.app_name,
+        app_version: application.version,
+        sdk_version: application.cosmos_sdk_version,
+        comet_version: response
+            .default_node_info
+            .map(|info| info.version)
+            .unwrap_or_default(),
+    })
+}
+
+/// Describe every component whose release line is outside the tested matrix.
+pub fn untested_components(versions: &NodeVersions) -> Vec<String> {
+    let mut untested = Vec::new();
+    if !is_tested(&versions.sdk_version, TESTED_SDK_VERSIONS) {
+        untested.push(format!(
+            "Cosmos SDK {:?} (tested: {})",
+            versions.sdk_version,
+            TESTED_SDK_VERSIONS.join(", ")
+        ));
+    }
+    if !is_tested(&versions.comet_version, TESTED_COMET_VERSIONS) {
+        untested.push(format!(
+            "CometBFT {:?} (tested: {})",
+            versions.comet_version,
+            TESTED_COMET_VERSIONS.join(", ")
+        ));
+    }
+
+    untested
+}
+
+/// Whether a version such as `v0.47.5` belongs to one of the tested `major.minor` lines.
+fn is_tested(version: &str, tested: &[&str]) -> bool {
+    let version = version.trim().trim_start_matches('v');
+    let mut parts = version.split(['.', '-', '+']);
+    let (Some(major), Some(minor)) = (parts.next(), parts.next()) else {
+        return false;
+    };
+    let line = format!("{}.{}", major, minor);
+
+    tested.contains(&line.as_str())
+}
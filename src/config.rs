@@ -0,0 +1,156 @@
+use eyre::Result;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Settings a config file can carry, either at the top level or per profile.
+/// Every field is optional; unset fields fall back to the flag defaults.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+    pub chain_id: Option<String>,
+    pub rpc_url: Option<String>,
+    pub grpc_url: Option<String>,
+    pub denom: Option<String>,
+    pub denom_exponent: Option<u32>,
+    pub fee_amount: Option<u64>,
+    pub timeout_height: Option<u64>,
+    pub signing_key_path: Option<String>,
+    pub authz_granter: Option<String>,
+    pub fee_granter: Option<String>,
+    pub min_interval: Option<u64>,
+    pub memo: Option<String>,
+    pub gas_limit: Option<u64>,
+}
+
+impl Settings {
+    /// Overlay `other` on top of these settings.
+    fn merge(self, other: Settings) -> Settings {
+        Settings {
+            chain_id: other.chain_id.or(self.chain_id),
+            rpc_url: other.rpc_url.or(self.rpc_url),
+            grpc_url: other.grpc_url.or(self.grpc_url),
+            denom: other.denom.or(self.denom),
+            denom_exponent: other.denom_exponent.or(self.denom_exponent),
+            fee_amount: other.fee_amount.or(self.fee_amount),
+            timeout_height: other.timeout_height.or(self.timeout_height),
+            signing_key_path: other.signing_key_path.or(self.signing_key_path),
+            authz_granter: other.authz_granter.or(self.authz_granter),
+            fee_granter: other.fee_granter.or(self.fee_granter),
+            min_interval: other.min_interval.or(self.min_interval),
+            memo: other.memo.or(self.memo),
+            gas_limit: other.gas_limit.or(self.gas_limit),
+        }
+    }
+}
+
+/// A config file: top-level defaults plus named `[profiles.<name>]` tables.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub defaults: Settings,
+    pub profiles: BTreeMap<String, Settings>,
+}
+
+impl Config {
+    /// Parse a TOML config file.
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut table: toml::Table = match input.parse() {
+            Ok(table) => table,
+            Err(e) => return Err(eyre::Report::msg(format!("Invalid config: {}", e))),
+        };
+        let profiles = match table.remove("profiles") {
+            Some(profiles) => match profiles.try_into() {
+                Ok(profiles) => profiles,
+                Err(e) => return Err(eyre::Report::msg(format!("Invalid config profiles: {}", e))),
+            },
+            None => BTreeMap::new(),
+        };
+        let defaults = match toml::Value::Table(table).try_into() {
+            Ok(defaults) => defaults,
+            Err(e) => return Err(eyre::Report::msg(format!("Invalid config: {}", e))),
+        };
+
+        Ok(Config { defaults, profiles })
+    }
+
+    /// Read and parse the config file at `path`.
+    pub fn load(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Config::parse(&contents),
+            Err(e) => {
+                log::error!("Failed to read config {}: {}", path.display(), e);
+                Err(eyre::Report::msg(format!(
+                    "Failed to read config {}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        }
+    }
+
+    /// The top-level settings with the named profile, if any, laid over them.
+    pub fn settings(&self, profile: Option<&str>) -> Result<Settings> {
+        let Some(profile) = profile else {
+            return Ok(self.defaults.clone());
+        };
+        match self.profiles.get(profile) {
+            Some(settings) => Ok(self.defaults.clone().merge(settings.clone())),
+            None => Err(eyre::Report::msg(format!(
+                "Unknown profile {:?} (available: {})",
+                profile,
+                self.profiles
+                    .keys()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))),
+        }
+    }
+}
+
+/// `~/.withdraw-commission/config.toml`, read when `--config` is not given.
+pub fn default_path() -> PathBuf {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .join(".withdraw-commission")
+        .join("config.toml")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+chain_id = "sommelier-3"
+denom = "usomm"
+
+[profiles.sommelier]
+rpc_url = "https://rpc.example.com:443"
+
+[profiles.testnet]
+chain_id = "sommelier-testnet"
+fee_amount = 5
+"#;
+
+    #[test]
+    fn profiles_override_top_level_settings() {
+        let config = Config::parse(CONFIG).unwrap();
+        let testnet = config.settings(Some("testnet")).unwrap();
+        assert_eq!(testnet.chain_id.as_deref(), Some("sommelier-testnet"));
+        assert_eq!(testnet.denom.as_deref(), Some("usomm"));
+        assert_eq!(testnet.fee_amount, Some(5));
+
+        let defaults = config.settings(None).unwrap();
+        assert_eq!(defaults.chain_id.as_deref(), Some("sommelier-3"));
+        assert_eq!(defaults.rpc_url, None);
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_profiles() {
+        assert!(Config::parse("chain-id = \"typo\"").is_err());
+        let config = Config::parse(CONFIG).unwrap();
+        assert!(config.settings(Some("mainnet")).is_err());
+    }
+}
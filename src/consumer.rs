@@ -0,0 +1,65 @@
+use crate::endpoint::{at_height, GrpcChannel};
+use cosmrs::proto::cosmos::distribution::v1beta1::{
+    query_client::QueryClient as DistributionQueryClient, QueryParamsRequest,
+};
+use cosmrs::proto::cosmos::staking::v1beta1::{
+    query_client::QueryClient as StakingQueryClient, QueryValidatorRequest,
+};
+use eyre::Result;
+use tonic::Code;
+
+const PROVIDER_HINT: &str = "On Interchain Security consumer chains (e.g. Neutron, Stride) the \
+validator set is provided by the Cosmos Hub and consumer rewards are paid to provider validators \
+there, so there is no commission to withdraw on the consumer chain. Run withdraw-commission \
+against the provider chain with your provider operator key instead \
+(--chain-preset cosmoshub --chain-id cosmoshub-4 --denom uatom --rpc-url ... --grpc-url ...).";
+
+/// Make sure the chain can pay commission to this validator before building a
+/// transaction, and explain Interchain Security consumer chains instead of
+/// failing with an opaque broadcast error.
+pub async fn ensure_commission_supported(
+    channel: GrpcChannel,
+    validator_operator_address: &str,
+    height: u64,
+) -> Result<()> {
+    let mut distribution_client = DistributionQueryClient::new(channel.clone());
+    if let Err(status) = distribution_client
+        .params(at_height(QueryParamsRequest {}, height))
+        .await
+    {
+        if status.code() == Code::Unimplemented {
+            log::error!("This chain has no distribution module");
+            return Err(eyre::Report::msg(format!(
+                "This chain has no distribution module, so validators do not earn commission on it. {}",
+                PROVIDER_HINT
+            )));
+        }
+        log::warn!("Failed to query distribution params: {}", status);
+    }
+
+    let mut staking_client = StakingQueryClient::new(channel);
+    let request = at_height(
+        QueryValidatorRequest {
+            validator_addr: validator_operator_address.to_string(),
+        },
+        height,
+    );
+    match staking_client.validator(request).await {
+        Ok(_) => Ok(()),
+        Err(status) if matches!(status.code(), Code::NotFound | Code::Unimplemented) => {
+            log::error!(
+                "Validator {} does not exist on this chain",
+                validator_operator_address
+            );
+            Err(eyre::Report::msg(format!(
+                "Validator {} does not exist in this chain's staking module. Check that the key \
+                 and address prefixes are right for this chain. {}",
+                validator_operator_address, PROVIDER_HINT
+            )))
+        }
+        Err(status) => {
+            log::warn!("Failed to query validator: {}", status);
+            Ok(())
+        }
+    }
+}
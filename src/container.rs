@@ -0,0 +1,36 @@
+use eyre::Result;
+use tokio::signal::unix::{signal, SignalKind};
+
+/// Wait for SIGTERM or SIGINT and return the conventional exit code for it.
+///
+/// When running as PID 1 in a container the kernel does not apply default
+/// signal dispositions, so without an explicit handler `docker stop` and pod
+/// termination would wait out the grace period and SIGKILL the process.
+pub async fn shutdown_signal() -> Result<i32> {
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut interrupt = signal(SignalKind::interrupt())?;
+
+    let (name, code) = tokio::select! {
+        _ = terminate.recv() => ("SIGTERM", 143),
+        _ = interrupt.recv() => ("SIGINT", 130),
+    };
+    log::warn!("Received {}, shutting down", name);
+
+    Ok(code)
+}
+
+/// Check that the configured gRPC and RPC endpoints are reachable, for use as
+/// a Docker HEALTHCHECK or Kubernetes exec probe.
+pub async fn healthcheck(
+    grpc_url: &str,
+    grpc_web: bool,
+    rpc_url: &str,
+    proxy: Option<&str>,
+) -> Result<()> {
+    let channel = crate::endpoint::connect_grpc(grpc_url, grpc_web, proxy).await?;
+    crate::endpoint::latest_height(channel).await?;
+    crate::endpoint::connect_rpc(rpc_url, proxy).await?;
+
+    log::info!("Endpoints are healthy");
+    Ok(())
+}
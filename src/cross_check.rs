@@ -0,0 +1,161 @@
+use crate::account;
+use crate::commission;
+use crate::decimal::{Decimal, Rounding};
+use crate::endpoint::GrpcChannel;
+use cosmrs::proto::cosmos::base::v1beta1::DecCoin;
+use eyre::Result;
+
+/// What to do when the second endpoint disagrees with the first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Mismatch {
+    /// Stop before signing
+    Abort,
+    /// Log a warning and continue
+    Warn,
+}
+
+/// Run the account and pending-commission queries against both endpoints at
+/// `height` and describe every disagreement. Commission amounts may differ by
+/// up to `tolerance_percent` of the larger amount.
+pub async fn cross_check(
+    primary: GrpcChannel,
+    secondary: GrpcChannel,
+    address: &str,
+    validator_operator_address: &str,
+    height: u64,
+    tolerance_percent: Decimal,
+) -> Result<Vec<String>> {
+    let mut disagreements = Vec::new();
+
+    let expected = account::account(primary.clone(), address, height).await?;
+    let actual = account::account(secondary.clone(), address, height).await?;
+    if expected != actual {
+        disagreements.push(format!(
+            "account number and sequence are {:?} on the first endpoint but {:?} on the second",
+            expected, actual
+        ));
+    }
+
+    let expected =
+        commission::pending_commission(primary, validator_operator_address, height).await?;
+    let actual =
+        commission::pending_commission(secondary, validator_operator_address, height).await?;
+    disagreements.extend(compare_commission(&expected, &actual, tolerance_percent));
+
+    Ok(disagreements)
+}
+
+/// Describe the denoms whose amounts differ by more than `tolerance_percent`
+/// of the larger amount, including denoms only one side reports. An amount
+/// that cannot be read counts as a disagreement.
+pub fn compare_commission(
+    expected: &[DecCoin],
+    actual: &[DecCoin],
+    tolerance_percent: Decimal,
+) -> Vec<String> {
+    let amount = |coins: &[DecCoin], denom: &str| -> Result<Decimal, String> {
+        match coins.iter().find(|coin| coin.denom == denom) {
+            Some(coin) => Decimal::from_dec(&coin.amount).map_err(|_| coin.amount.clone()),
+            None => Ok(Decimal::ZERO),
+        }
+    };
+    let mut denoms: Vec<&str> = expected
+        .iter()
+        .chain(actual)
+        .map(|coin| coin.denom.as_str())
+        .collect();
+    denoms.sort_unstable();
+    denoms.dedup();
+
+    denoms
+        .into_iter()
+        .filter_map(|denom| {
+            let (a, b) = match (amount(expected, denom), amount(actual, denom)) {
+                (Ok(a), Ok(b)) => (a, b),
+                (a, b) => {
+                    let shown = |amount: Result<Decimal, String>| match amount {
+                        Ok(amount) => format!("{}{}", amount, denom),
+                        Err(amount) => format!("the unreadable {:?}", amount),
+                    };
+                    return Some(format!(
+                        "pending commission of {} is {} on the first endpoint but {} on the second",
+                        denom,
+                        shown(a),
+                        shown(b)
+                    ));
+                }
+            };
+            let difference = a.atomics().abs_diff(b.atomics());
+            let allowed = tolerance_percent
+                .percent()
+                .mul_integer(a.max(b).atomics(), Rounding::Down);
+            if allowed.is_some_and(|allowed| difference <= allowed) {
+                return None;
+            }
+            Some(format!(
+                "pending commission is {}{} on the first endpoint but {}{} on the second",
+                a, denom, b, denom
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coin(denom: &str, amount: &str) -> DecCoin {
+        DecCoin {
+            denom: denom.to_string(),
+            amount: amount.to_string(),
+        }
+    }
+
+    fn percent(input: &str) -> Decimal {
+        commission::parse_percent(input).unwrap()
+    }
+
+    #[test]
+    fn tolerates_small_differences() {
+        let expected = [coin("usomm", "1000000000000000000000")];
+        let actual = [coin("usomm", "1005000000000000000000")];
+        assert!(compare_commission(&expected, &actual, percent("1")).is_empty());
+        assert!(compare_commission(&expected, &actual, percent("0.5")).is_empty());
+        assert_eq!(
+            compare_commission(&expected, &actual, percent("0.4")),
+            ["pending commission is 1000usomm on the first endpoint but 1005usomm on the second"]
+        );
+    }
+
+    #[test]
+    fn reports_denoms_missing_on_one_side() {
+        let expected = [coin("usomm", "1"), coin("uatom", "2000000000000000000")];
+        let actual = [coin("usomm", "1")];
+        assert_eq!(
+            compare_commission(&expected, &actual, percent("50")),
+            ["pending commission is 2uatom on the first endpoint but 0uatom on the second"]
+        );
+    }
+
+    #[test]
+    fn disagrees_on_amounts_it_cannot_read() {
+        // Beyond what a Decimal holds, on both sides
+        let huge = "9".repeat(40);
+        let expected = [coin("aevmos", &huge)];
+        let actual = [coin("aevmos", &huge)];
+        assert_eq!(
+            compare_commission(&expected, &actual, percent("100")),
+            [format!(
+                "pending commission of aevmos is the unreadable {:?} on the first endpoint but the unreadable {:?} on the second",
+                huge, huge
+            )]
+        );
+
+        // Amino JSON style amounts are read as decimals
+        let expected = [coin("usomm", "1.5")];
+        let actual = [coin("usomm", "1500000000000000000")];
+        assert!(compare_commission(&expected, &actual, percent("0")).is_empty());
+        assert!(commission::parse_percent("-1").is_err());
+        assert!(commission::parse_percent("NaN").is_err());
+    }
+}
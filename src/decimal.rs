@@ -0,0 +1,307 @@
+use eyre::Result;
+use std::fmt;
+use std::str::FromStr;
+
+/// Decimal places of a Cosmos SDK `Dec`, which gRPC sends as a scaled integer.
+pub const PRECISION: usize = 18;
+
+/// `10^PRECISION`, the scaled integer of one.
+const SCALE: u128 = 1_000_000_000_000_000_000;
+
+/// How a fractional amount becomes a whole one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    /// Toward zero, so shares never add up to more than the whole
+    Down,
+    /// Away from zero
+    Up,
+    /// To the nearest whole amount, ties to the even one
+    HalfEven,
+}
+
+/// A non-negative decimal with the precision of a Cosmos SDK `Dec`. It is
+/// stored as a scaled integer, so percentages and amounts stay exact and
+/// every rounding step is explicit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Decimal(u128);
+
+impl Decimal {
+    pub const ZERO: Decimal = Decimal(0);
+    pub const HUNDRED: Decimal = Decimal(100 * SCALE);
+
+    /// The decimal a gRPC `Dec` of `atomics` stands for.
+    pub const fn from_atomics(atomics: u128) -> Self {
+        Decimal(atomics)
+    }
+
+    /// The scaled integer this decimal is stored as.
+    pub const fn atomics(self) -> u128 {
+        self.0
+    }
+
+    /// Parse a `DecCoin` amount, either the scaled integer gRPC sends or an
+    /// amino JSON style decimal with a point.
+    pub fn from_dec(amount: &str) -> Result<Self> {
+        if amount.contains('.') {
+            return amount.parse();
+        }
+        match amount.parse::<u128>() {
+            Ok(atomics) => Ok(Decimal(atomics)),
+            Err(e) => Err(eyre::Report::msg(format!(
+                "Invalid decimal amount {:?}: {}",
+                amount, e
+            ))),
+        }
+    }
+
+    pub fn checked_add(self, other: Decimal) -> Option<Decimal> {
+        self.0.checked_add(other.0).map(Decimal)
+    }
+
+    pub fn checked_sub(self, other: Decimal) -> Option<Decimal> {
+        self.0.checked_sub(other.0).map(Decimal)
+    }
+
+    /// The fraction this percentage stands for. Exact for percentages with
+    /// at most 16 decimal places, which is all `parse_percent` accepts.
+    pub fn percent(self) -> Decimal {
+        Decimal(self.0 / 100)
+    }
+
+    /// The whole part, rounded by `rounding`.
+    pub fn to_integer(self, rounding: Rounding) -> u128 {
+        round(self.0 / SCALE, self.0 % SCALE, SCALE, rounding).unwrap_or(u128::MAX)
+    }
+
+    /// `amount` times this decimal, rounded to a whole amount by `rounding`,
+    /// or `None` if it does not fit.
+    pub fn mul_integer(self, amount: u128, rounding: Rounding) -> Option<u128> {
+        // Splitting off the whole multiples of the scale keeps the
+        // intermediate products within u128
+        let whole = (amount / SCALE).checked_mul(self.0)?;
+        let fraction = (amount % SCALE).checked_mul(self.0)?;
+        let rounded = round(fraction / SCALE, fraction % SCALE, SCALE, rounding)?;
+        whole.checked_add(rounded)
+    }
+}
+
+/// `quotient + remainder / divisor`, rounded by `rounding`.
+pub(crate) fn round(
+    quotient: u128,
+    remainder: u128,
+    divisor: u128,
+    rounding: Rounding,
+) -> Option<u128> {
+    let up = match rounding {
+        Rounding::Down => false,
+        Rounding::Up => remainder > 0,
+        Rounding::HalfEven => {
+            let rest = divisor - remainder;
+            remainder > rest || (remainder == rest && quotient % 2 == 1)
+        }
+    };
+    quotient.checked_add(u128::from(up))
+}
+
+/// SI prefixes of base denoms and the exponent each stands for.
+const DENOM_PREFIXES: [(char, u32); 3] = [('u', 6), ('n', 9), ('a', 18)];
+
+/// The exponent of a base denom, read from its SI prefix: 6 for `usomm`, 9
+/// for `n...` and 18 for `aevmos`. Other denoms, such as IBC ones, get 0.
+pub fn default_exponent(denom: &str) -> u32 {
+    prefixed(denom).map_or(0, |(_, exponent)| exponent)
+}
+
+fn prefixed(denom: &str) -> Option<(&str, u32)> {
+    let mut chars = denom.chars();
+    let prefix = chars.next()?;
+    let name = chars.as_str();
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    DENOM_PREFIXES
+        .iter()
+        .find(|(known, _)| *known == prefix)
+        .map(|(_, exponent)| (name, *exponent))
+}
+
+/// `amount` base units as whole tokens at `exponent`, exactly, e.g.
+/// `0.000617` for 617 at 6.
+pub fn format_units(amount: u128, exponent: u32) -> String {
+    let exponent = exponent as usize;
+    let padded = format!("{:0>width$}", amount, width = exponent + 1);
+    let (integer, fraction) = padded.split_at(padded.len() - exponent);
+    match fraction.trim_end_matches('0') {
+        "" => integer.to_string(),
+        fraction => format!("{}.{}", integer, fraction),
+    }
+}
+
+/// `amount` of the base `denom` with its whole-token value, e.g.
+/// `617usomm (0.000617 SOMM)`.
+pub fn display(amount: u128, denom: &str, exponent: u32) -> String {
+    if exponent == 0 {
+        return format!("{}{}", amount, denom);
+    }
+    let name = match prefixed(denom) {
+        Some((name, prefix_exponent)) if prefix_exponent == exponent => name.to_uppercase(),
+        _ => format!("{} x 10^{}", denom, exponent),
+    };
+    format!(
+        "{}{} ({} {})",
+        amount,
+        denom,
+        format_units(amount, exponent),
+        name
+    )
+}
+
+/// The whole base units of a `DecCoin` amount, rounded down. Unlike
+/// [`Decimal::from_dec`] this never overflows, so it also takes amounts of
+/// 18-exponent denoms far beyond what a scaled u128 holds.
+pub fn whole_dec(amount: &str) -> Option<u128> {
+    let integer = match amount.split_once('.') {
+        Some((integer, _)) => integer,
+        // The scaled integer gRPC sends, without its 18 decimal places
+        None => amount.get(..amount.len().saturating_sub(PRECISION))?,
+    };
+    if !amount.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return None;
+    }
+    match integer {
+        "" => Some(0),
+        integer => integer.parse().ok(),
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = eyre::Report;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let invalid = || eyre::Report::msg(format!("Invalid decimal {:?}", input));
+        let (integer, fraction) = input.split_once('.').unwrap_or((input, ""));
+        let digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+        if integer.is_empty() && fraction.is_empty()
+            || !digits(integer)
+            || !digits(fraction)
+            || fraction.len() > PRECISION
+        {
+            return Err(invalid());
+        }
+        let integer = match integer {
+            "" => 0,
+            integer => integer.parse::<u128>().map_err(|_| invalid())?,
+        };
+        let fraction = format!("{:0<width$}", fraction, width = PRECISION)
+            .parse::<u128>()
+            .map_err(|_| invalid())?;
+        integer
+            .checked_mul(SCALE)
+            .and_then(|scaled| scaled.checked_add(fraction))
+            .map(Decimal)
+            .ok_or_else(invalid)
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fraction = format!("{:0>width$}", self.0 % SCALE, width = PRECISION);
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.is_empty() {
+            write!(f, "{}", self.0 / SCALE)
+        } else {
+            write!(f, "{}.{}", self.0 / SCALE, fraction)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decimal(input: &str) -> Decimal {
+        input.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_and_prints_exactly() {
+        assert_eq!(decimal("12.5").to_string(), "12.5");
+        assert_eq!(decimal(".5").to_string(), "0.5");
+        assert_eq!(decimal("7").to_string(), "7");
+        assert_eq!(decimal("0.000000000000000001"), Decimal::from_atomics(1));
+        assert!("0.0000000000000000001".parse::<Decimal>().is_err());
+        assert!("-1".parse::<Decimal>().is_err());
+        assert!(".".parse::<Decimal>().is_err());
+        assert!("1e3".parse::<Decimal>().is_err());
+        assert_eq!(
+            Decimal::from_dec("1234500000000000000000").unwrap(),
+            decimal("1234.5")
+        );
+        assert_eq!(Decimal::from_dec("1234.5").unwrap(), decimal("1234.5"));
+    }
+
+    #[test]
+    fn rounds_as_asked() {
+        let half = decimal("0.5");
+        assert_eq!(half.mul_integer(5, Rounding::Down), Some(2));
+        assert_eq!(half.mul_integer(5, Rounding::Up), Some(3));
+        assert_eq!(half.mul_integer(5, Rounding::HalfEven), Some(2));
+        assert_eq!(half.mul_integer(7, Rounding::HalfEven), Some(4));
+        assert_eq!(decimal("2.5").to_integer(Rounding::HalfEven), 2);
+        assert_eq!(decimal("2.500001").to_integer(Rounding::HalfEven), 3);
+        assert_eq!(decimal("2.000001").to_integer(Rounding::Up), 3);
+        assert_eq!(decimal("2.999999").to_integer(Rounding::Down), 2);
+    }
+
+    #[test]
+    fn respects_denom_exponents() {
+        assert_eq!(default_exponent("usomm"), 6);
+        assert_eq!(default_exponent("aevmos"), 18);
+        assert_eq!(default_exponent("ibc/27394FB0"), 0);
+        assert_eq!(format_units(617, 6), "0.000617");
+        assert_eq!(format_units(1_500_000, 6), "1.5");
+        assert_eq!(format_units(42, 0), "42");
+        assert_eq!(display(617, "usomm", 6), "617usomm (0.000617 SOMM)");
+        assert_eq!(
+            display(2_500_000_000_000_000_000, "aevmos", 18),
+            "2500000000000000000aevmos (2.5 EVMOS)"
+        );
+        assert_eq!(display(7, "ibc/27394FB0", 0), "7ibc/27394FB0");
+        assert_eq!(
+            display(7, "ibc/27394FB0", 6),
+            "7ibc/27394FB0 (0.000007 ibc/27394FB0 x 10^6)"
+        );
+
+        // A million EVMOS of commission, which a scaled u128 cannot hold
+        let million = format!("1{}", "0".repeat(24 + PRECISION));
+        assert!(Decimal::from_dec(&million).is_err());
+        assert_eq!(whole_dec(&million), Some(10u128.pow(24)));
+        assert_eq!(whole_dec("1234567800000000000000"), Some(1234));
+        assert_eq!(whole_dec("999999999999999999"), Some(0));
+        assert_eq!(whole_dec("1234.9"), Some(1234));
+        assert_eq!(whole_dec("-1"), None);
+    }
+
+    #[test]
+    fn takes_percentages_without_losing_precision() {
+        // 12.345% of an 18-decimal denom far beyond what a u128 times a
+        // scaled percentage could hold
+        let amount = 123_456_789_000_000_000_000_000_000u128;
+        assert_eq!(
+            decimal("12.345")
+                .percent()
+                .mul_integer(amount, Rounding::Down),
+            Some(15_240_740_602_050_000_000_000_000)
+        );
+        let third = decimal("33.3333333333333333").percent();
+        assert_eq!(third.mul_integer(3, Rounding::Down), Some(0));
+        assert_eq!(third.mul_integer(3, Rounding::HalfEven), Some(1));
+        assert_eq!(
+            Decimal::HUNDRED
+                .percent()
+                .mul_integer(u128::MAX, Rounding::Down),
+            Some(u128::MAX)
+        );
+        assert_eq!(decimal("200").mul_integer(u128::MAX, Rounding::Down), None);
+    }
+}
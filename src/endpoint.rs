@@ -0,0 +1,386 @@
+use crate::unix_socket::{self, UnixRpcClient};
+use async_trait::async_trait;
+use bytes::Bytes;
+use cosmrs::proto::cosmos::base::tendermint::v1beta1::{
+    service_client::ServiceClient, GetLatestBlockRequest,
+};
+use cosmrs::rpc::{
+    client::CompatMode,
+    endpoint::{broadcast::tx_commit, tx},
+    Client, HttpClient, SimpleRequest,
+};
+use cosmrs::tendermint::Hash;
+use eyre::Result;
+use http_body_util::{BodyExt, Empty};
+use hyper_rustls::builderstates::WantsSchemes;
+use hyper_rustls::HttpsConnectorBuilder;
+use hyper_util::client::legacy::{connect::Connect, Client as HyperClient};
+use hyper_util::rt::TokioExecutor;
+use tonic::body::BoxBody;
+use tonic::transport::{Channel, ClientTlsConfig, Endpoint};
+use tonic_web::GrpcWebClientLayer;
+use tower::util::BoxCloneService;
+use tower::ServiceBuilder;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A gRPC connection, either native gRPC over HTTP/2 or gRPC-web over HTTP/1.1.
+pub type GrpcChannel = BoxCloneService<http::Request<BoxBody>, http::Response<BoxBody>, BoxError>;
+
+/// Connect to a gRPC endpoint, enabling rustls for `https://` URLs and
+/// connecting to a Unix socket for `unix:///path/to/socket`. With `grpc_web`
+/// set the endpoint is spoken to as a gRPC-web gateway instead.
+///
+/// Trust roots come from the OS certificate store, plus the bundled Mozilla
+/// roots when built with the `webpki-roots` feature (for scratch images that
+/// ship no CA bundle). TCP connections go through the SOCKS5 `proxy` if set.
+pub async fn connect_grpc(url: &str, grpc_web: bool, proxy: Option<&str>) -> Result<GrpcChannel> {
+    if grpc_web {
+        if unix_socket::socket_path(url).is_some() {
+            return Err(eyre::Report::msg(
+                "gRPC-web is not supported over Unix sockets",
+            ));
+        }
+        return connect_grpc_web(url, proxy);
+    }
+
+    let connected = match unix_socket::socket_path(url) {
+        Some(path) => unix_socket::connect_grpc(path).await,
+        None => {
+            let mut endpoint = match Endpoint::from_shared(url.to_string()) {
+                Ok(endpoint) => endpoint,
+                Err(e) => {
+                    log::error!("Invalid gRPC URL {}: {}", url, e);
+                    return Err(eyre::Report::msg(format!(
+                        "Invalid gRPC URL {}: {}",
+                        url, e
+                    )));
+                }
+            };
+            if endpoint.uri().scheme_str() == Some("https") {
+                endpoint = endpoint.tls_config(ClientTlsConfig::new().with_enabled_roots())?;
+            }
+            connect_endpoint(endpoint, proxy).await
+        }
+    };
+
+    match connected {
+        Ok(channel) => Ok(BoxCloneService::new(
+            ServiceBuilder::new()
+                .map_err(BoxError::from)
+                .service(channel),
+        )),
+        Err(e) => {
+            log::error!("Failed to connect to gRPC endpoint {}: {}", url, e);
+            Err(eyre::Report::msg(format!(
+                "Failed to connect to gRPC endpoint {}: {}",
+                url, e
+            )))
+        }
+    }
+}
+
+/// Open the connection directly, or through the SOCKS5 proxy when one is set.
+async fn connect_endpoint(
+    endpoint: Endpoint,
+    proxy: Option<&str>,
+) -> Result<Channel, tonic::transport::Error> {
+    match proxy {
+        #[cfg(feature = "socks")]
+        Some(proxy) => {
+            endpoint
+                .connect_with_connector(crate::socks::SocksConnector::new(proxy))
+                .await
+        }
+        _ => endpoint.connect().await,
+    }
+}
+
+/// Speak gRPC-web to `url`, for providers that only expose a gRPC-web gateway
+/// (often behind a CDN that does not proxy HTTP/2 trailers). Connections are
+/// opened lazily, on the first query.
+fn connect_grpc_web(url: &str, proxy: Option<&str>) -> Result<GrpcChannel> {
+    let origin = match url.parse::<http::Uri>() {
+        Ok(origin) if origin.scheme().is_some() && origin.authority().is_some() => origin,
+        Ok(_) => {
+            log::error!("Invalid gRPC-web URL {}: missing scheme or host", url);
+            return Err(eyre::Report::msg(format!(
+                "Invalid gRPC-web URL {}: missing scheme or host",
+                url
+            )));
+        }
+        Err(e) => {
+            log::error!("Invalid gRPC-web URL {}: {}", url, e);
+            return Err(eyre::Report::msg(format!(
+                "Invalid gRPC-web URL {}: {}",
+                url, e
+            )));
+        }
+    };
+
+    let builder = https_connector_builder()?.https_or_http().enable_http1();
+    match proxy {
+        #[cfg(feature = "socks")]
+        Some(proxy) => Ok(grpc_web_channel(
+            origin,
+            builder.wrap_connector(crate::socks::SocksConnector::new(proxy)),
+        )),
+        _ => Ok(grpc_web_channel(origin, builder.build())),
+    }
+}
+
+fn grpc_web_channel<C>(origin: http::Uri, connector: C) -> GrpcChannel
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    let client = HyperClient::builder(TokioExecutor::new()).build(connector);
+    let service = ServiceBuilder::new()
+        .map_err(BoxError::from)
+        .map_request(move |request| with_origin(request, &origin))
+        .map_response(|response: http::Response<_>| response.map(tonic::body::boxed))
+        .layer(GrpcWebClientLayer::new())
+        .service(client);
+
+    BoxCloneService::new(service)
+}
+
+/// HTTP/1.1 connector for gRPC-web, using the same trust roots as native gRPC.
+fn https_connector_builder() -> Result<HttpsConnectorBuilder<WantsSchemes>> {
+    let builder = match HttpsConnectorBuilder::new().with_native_roots() {
+        Ok(builder) => builder,
+        #[cfg(feature = "webpki-roots")]
+        Err(_) => HttpsConnectorBuilder::new().with_webpki_roots(),
+        #[cfg(not(feature = "webpki-roots"))]
+        Err(e) => {
+            log::error!("Failed to load native root certificates: {}", e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to load native root certificates: {}",
+                e
+            )));
+        }
+    };
+
+    Ok(builder)
+}
+
+/// Point a request at the gateway, keeping any path prefix of the gateway URL
+/// (e.g. `https://example.com/grpc-web`) in front of the gRPC method path.
+fn with_origin<B>(mut request: http::Request<B>, origin: &http::Uri) -> http::Request<B> {
+    let method = request
+        .uri()
+        .path_and_query()
+        .map(|path| path.as_str())
+        .unwrap_or("/");
+    let mut parts = origin.clone().into_parts();
+    parts.path_and_query = format!("{}{}", origin.path().trim_end_matches('/'), method)
+        .parse()
+        .ok();
+    if let Ok(uri) = http::Uri::from_parts(parts) {
+        *request.uri_mut() = uri;
+    }
+
+    request
+}
+
+/// gRPC metadata key the Cosmos SDK uses to serve a query at a given height.
+const BLOCK_HEIGHT_HEADER: &str = "x-cosmos-block-height";
+
+/// Build a query request pinned to `height`, so that every query of a run
+/// reads the same state.
+pub fn at_height<T>(message: T, height: u64) -> tonic::Request<T> {
+    let mut request = tonic::Request::new(message);
+    request
+        .metadata_mut()
+        .insert(BLOCK_HEIGHT_HEADER, height.into());
+
+    request
+}
+
+/// Height of the latest block known to the gRPC endpoint.
+pub async fn latest_height(channel: GrpcChannel) -> Result<u64> {
+    let mut client = ServiceClient::new(channel);
+    let response = match client
+        .get_latest_block(tonic::Request::new(GetLatestBlockRequest {}))
+        .await
+    {
+        Ok(response) => response.into_inner(),
+        Err(e) => {
+            log::error!("Failed to query latest block: {}", e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to query latest block: {}",
+                e
+            )));
+        }
+    };
+
+    let height = response
+        .sdk_block
+        .and_then(|block| block.header)
+        .map(|header| header.height)
+        .or_else(|| {
+            response
+                .block
+                .and_then(|block| block.header)
+                .map(|header| header.height)
+        })
+        .unwrap_or_default();
+    if height <= 0 {
+        return Err(eyre::Report::msg("Latest block has no height"));
+    }
+
+    Ok(height as u64)
+}
+
+/// GET `url` over HTTP(S) and return the body as text. TCP connections go
+/// through the SOCKS5 `proxy` if set, like the gRPC ones.
+pub async fn fetch(url: &str, proxy: Option<&str>) -> Result<String> {
+    let uri = match url.parse::<http::Uri>() {
+        Ok(uri) => uri,
+        Err(e) => {
+            log::error!("Invalid URL {}: {}", url, e);
+            return Err(eyre::Report::msg(format!("Invalid URL {}: {}", url, e)));
+        }
+    };
+
+    let builder = https_connector_builder()?.https_or_http().enable_http1();
+    let response = match proxy {
+        #[cfg(feature = "socks")]
+        Some(proxy) => {
+            get(
+                builder.wrap_connector(crate::socks::SocksConnector::new(proxy)),
+                uri,
+            )
+            .await
+        }
+        _ => get(builder.build(), uri).await,
+    };
+    match response {
+        Ok(body) => Ok(body),
+        Err(e) => {
+            log::error!("Failed to fetch {}: {}", url, e);
+            Err(eyre::Report::msg(format!("Failed to fetch {}: {}", url, e)))
+        }
+    }
+}
+
+async fn get<C>(connector: C, uri: http::Uri) -> Result<String, BoxError>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    let client = HyperClient::builder(TokioExecutor::new()).build(connector);
+    let response = client
+        .request(http::Request::get(uri).body(Empty::<Bytes>::new())?)
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("HTTP {}", status).into());
+    }
+    let body = response.into_body().collect().await?.to_bytes();
+
+    Ok(String::from_utf8(body.to_vec())?)
+}
+
+/// A CometBFT RPC client over HTTP(S) or a Unix socket.
+#[derive(Debug, Clone)]
+pub enum RpcClient {
+    Http(HttpClient),
+    Unix(UnixRpcClient),
+}
+
+impl RpcClient {
+    fn set_compat_mode(&mut self, compat: CompatMode) {
+        match self {
+            RpcClient::Http(client) => client.set_compat_mode(compat),
+            RpcClient::Unix(client) => client.set_compat_mode(compat),
+        }
+    }
+}
+
+#[async_trait]
+impl Client for RpcClient {
+    async fn perform<R>(&self, request: R) -> Result<R::Output, cosmrs::rpc::Error>
+    where
+        R: SimpleRequest,
+    {
+        match self {
+            RpcClient::Http(client) => client.perform(request).await,
+            RpcClient::Unix(client) => client.perform(request).await,
+        }
+    }
+
+    async fn tx(&self, hash: Hash, prove: bool) -> Result<tx::Response, cosmrs::rpc::Error> {
+        match self {
+            RpcClient::Http(client) => client.tx(hash, prove).await,
+            RpcClient::Unix(client) => client.tx(hash, prove).await,
+        }
+    }
+
+    async fn broadcast_tx_commit<T>(&self, tx: T) -> Result<tx_commit::Response, cosmrs::rpc::Error>
+    where
+        T: Into<Vec<u8>> + Send,
+    {
+        match self {
+            RpcClient::Http(client) => client.broadcast_tx_commit(tx).await,
+            RpcClient::Unix(client) => client.broadcast_tx_commit(tx).await,
+        }
+    }
+}
+
+fn http_client(url: &str, proxy: Option<&str>) -> Result<HttpClient> {
+    match proxy {
+        #[cfg(feature = "socks")]
+        Some(proxy) => {
+            let client = reqwest::Client::builder()
+                .proxy(crate::socks::reqwest_proxy(proxy)?)
+                .build()?;
+            Ok(HttpClient::new_from_parts(
+                client,
+                url.parse()?,
+                CompatMode::default(),
+            ))
+        }
+        _ => Ok(HttpClient::new(url)?),
+    }
+}
+
+/// Create an RPC client speaking the dialect of the node's CometBFT version.
+///
+/// Tendermint 0.34 nodes (Cosmos SDK 0.45/0.46) base64-encode event
+/// attributes while CometBFT 0.37+ sends plain strings, so the compatibility
+/// mode has to match or decoded events come out garbled.
+pub async fn connect_rpc(url: &str, proxy: Option<&str>) -> Result<RpcClient> {
+    let mut client = match unix_socket::socket_path(url) {
+        Some(path) => RpcClient::Unix(UnixRpcClient::new(path)),
+        None => {
+            let Ok(client) = http_client(url, proxy) else {
+                log::error!("Failed to create client");
+                return Err(eyre::Report::msg("Failed to create client"));
+            };
+            RpcClient::Http(client)
+        }
+    };
+
+    let status = match client.status().await {
+        Ok(status) => status,
+        Err(e) => {
+            log::error!("Failed to query node status: {}", e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to query node status: {}",
+                e
+            )));
+        }
+    };
+    match CompatMode::from_version(status.node_info.version.clone()) {
+        Ok(mode) => {
+            log::debug!("Using {} RPC dialect", mode);
+            client.set_compat_mode(mode);
+        }
+        Err(e) => log::warn!(
+            "Unrecognised CometBFT version {}, using the latest RPC dialect: {}",
+            status.node_info.version,
+            e
+        ),
+    }
+
+    Ok(client)
+}
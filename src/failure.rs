@@ -0,0 +1,170 @@
+/// Broad causes of a failed withdrawal, so whoever is paged knows where to
+/// look without knowing Cosmos SDK error codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureClass {
+    /// The RPC or gRPC endpoint could not be reached or misbehaved
+    Endpoint,
+    /// The fee was too low, or the fee payer cannot cover it or the gas
+    Fee,
+    /// The account sequence moved, usually because another transaction from
+    /// the same key landed first
+    Sequence,
+    /// The signature was rejected or the key could not be used
+    Key,
+    /// The transaction was accepted but no block committed it in time
+    ChainHalt,
+    /// The run was held for review before signing: the validator is jailed
+    /// or was slashed, or the endpoints disagree
+    Review,
+    /// Read-only mode refused to sign on this host
+    ReadOnly,
+    /// Anything else
+    Unknown,
+}
+
+// Cosmos SDK error codes in the `sdk` codespace
+const CODE_UNAUTHORIZED: u32 = 4;
+const CODE_INSUFFICIENT_FUNDS: u32 = 5;
+const CODE_INVALID_PUB_KEY: u32 = 8;
+const CODE_OUT_OF_GAS: u32 = 11;
+const CODE_INSUFFICIENT_FEE: u32 = 13;
+const CODE_WRONG_SEQUENCE: u32 = 32;
+
+impl FailureClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureClass::Endpoint => "endpoint",
+            FailureClass::Fee => "fee",
+            FailureClass::Sequence => "sequence",
+            FailureClass::Key => "key",
+            FailureClass::ChainHalt => "chain-halt",
+            FailureClass::Review => "review",
+            FailureClass::ReadOnly => "read-only",
+            FailureClass::Unknown => "unknown",
+        }
+    }
+
+    /// What the on-call engineer should try first.
+    pub fn remediation(&self) -> &'static str {
+        match self {
+            FailureClass::Endpoint => "Check that --rpc-url and --grpc-url are reachable and synced, or switch to another provider, then rerun.",
+            FailureClass::Fee => "Check the fee payer's balance and raise --fee-amount (or --fees) or the gas limit (--gas-limit or --gas-table), or size both with --gas-prices, then rerun.",
+            FailureClass::Sequence => "Another transaction from this key landed first; rerun, and make sure only one host signs with this key.",
+            FailureClass::Key => "Check that the signing key belongs to the validator and that --chain-id matches the chain.",
+            FailureClass::ChainHalt => "Check whether the chain is producing blocks; if it is, look the transaction up by hash before rerunning.",
+            FailureClass::Review => "Check the validator's status and both endpoints; once they are understood, rerun with --ignore-jailed or --on-cross-check-mismatch warn.",
+            FailureClass::ReadOnly => "This host must not broadcast; run the withdrawal on the designated broadcaster.",
+            FailureClass::Unknown => "Read the log for details and rerun with RUST_LOG=debug.",
+        }
+    }
+
+    /// Background reading for the failure.
+    pub fn doc_link(&self) -> &'static str {
+        match self {
+            FailureClass::Endpoint | FailureClass::ChainHalt | FailureClass::Unknown => {
+                "https://docs.cometbft.com/v0.38/rpc/"
+            }
+            FailureClass::Fee => "https://docs.cosmos.network/main/learn/beginner/gas-fees",
+            FailureClass::Sequence | FailureClass::Key | FailureClass::ReadOnly => {
+                "https://docs.cosmos.network/main/learn/beginner/accounts"
+            }
+            FailureClass::Review => "https://docs.cosmos.network/main/build/modules/slashing",
+        }
+    }
+}
+
+/// Classify a failure from the result code (0 when the transaction never got
+/// a code) and the log or error message.
+pub fn classify(code: u32, log: &str) -> FailureClass {
+    match code {
+        CODE_WRONG_SEQUENCE => return FailureClass::Sequence,
+        CODE_INSUFFICIENT_FEE | CODE_INSUFFICIENT_FUNDS | CODE_OUT_OF_GAS => {
+            return FailureClass::Fee
+        }
+        CODE_UNAUTHORIZED | CODE_INVALID_PUB_KEY => return FailureClass::Key,
+        _ => {}
+    }
+
+    let log = log.to_lowercase();
+    let mentions = |needles: &[&str]| needles.iter().any(|needle| log.contains(needle));
+    if mentions(&["read-only mode"]) {
+        FailureClass::ReadOnly
+    } else if mentions(&["paused for review", "endpoints disagree"]) {
+        FailureClass::Review
+    } else if mentions(&["account sequence mismatch", "incorrect account sequence"]) {
+        FailureClass::Sequence
+    } else if mentions(&["insufficient fee", "insufficient funds", "out of gas"]) {
+        FailureClass::Fee
+    } else if mentions(&[
+        "signature verification failed",
+        "private key",
+        "signing key",
+    ]) {
+        FailureClass::Key
+    } else if mentions(&["timed out waiting for tx", "was not committed within"]) {
+        FailureClass::ChainHalt
+    } else if mentions(&[
+        "failed to connect",
+        "failed to broadcast",
+        "connection refused",
+        "transport error",
+        "dns error",
+        "timed out",
+        "unavailable",
+    ]) {
+        FailureClass::Endpoint
+    } else {
+        FailureClass::Unknown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_result_codes() {
+        assert_eq!(
+            classify(32, "account sequence mismatch, expected 7, got 6"),
+            FailureClass::Sequence
+        );
+        assert_eq!(
+            classify(13, "insufficient fees; got: 1usomm"),
+            FailureClass::Fee
+        );
+        assert_eq!(classify(4, ""), FailureClass::Key);
+    }
+
+    #[test]
+    fn classifies_error_messages() {
+        assert_eq!(
+            classify(
+                0,
+                "Failed to broadcast transaction: timed out waiting for tx to be included in a block"
+            ),
+            FailureClass::ChainHalt
+        );
+        assert_eq!(
+            classify(
+                0,
+                "Failed to broadcast transaction: error trying to connect: Connection refused"
+            ),
+            FailureClass::Endpoint
+        );
+        assert_eq!(
+            classify(
+                0,
+                "Withdrawal paused for review (validator is jailed); rerun with --ignore-jailed to override"
+            ),
+            FailureClass::Review
+        );
+        assert_eq!(
+            classify(
+                0,
+                "Read-only mode is set (--read-only or WITHDRAW_COMMISSION_READ_ONLY), refusing to sign and broadcast"
+            ),
+            FailureClass::ReadOnly
+        );
+        assert_eq!(classify(1, "internal error"), FailureClass::Unknown);
+    }
+}
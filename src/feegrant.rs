@@ -0,0 +1,158 @@
+use cosmrs::proto::cosmos::feegrant::v1beta1::{
+    BasicAllowance, MsgGrantAllowance, MsgRevokeAllowance, PeriodicAllowance,
+};
+use cosmrs::proto::prost::Message;
+use cosmrs::proto::tendermint::google::protobuf::Duration;
+use cosmrs::tendermint::Time;
+use cosmrs::{Any, Coin};
+use eyre::Result;
+
+/// Type URL of the allowance with an optional total limit and expiration.
+const BASIC_ALLOWANCE: &str = "/cosmos.feegrant.v1beta1.BasicAllowance";
+
+/// Type URL of the allowance that also limits spending per period.
+const PERIODIC_ALLOWANCE: &str = "/cosmos.feegrant.v1beta1.PeriodicAllowance";
+
+/// Type URL of the message that creates an allowance.
+const MSG_GRANT_ALLOWANCE: &str = "/cosmos.feegrant.v1beta1.MsgGrantAllowance";
+
+/// Type URL of the message that revokes an allowance.
+const MSG_REVOKE_ALLOWANCE: &str = "/cosmos.feegrant.v1beta1.MsgRevokeAllowance";
+
+/// Limits on the fees a grantee may spend from the granter's account.
+#[derive(Debug, Clone, Default)]
+pub struct Allowance {
+    /// Total the grantee may spend, `None` for no limit
+    pub spend_limit: Option<Coin>,
+    /// When the allowance expires, `None` for never
+    pub expiration: Option<Time>,
+    /// Length of a period in seconds and what may be spent in each
+    pub period: Option<(u64, Coin)>,
+}
+
+/// The message that lets `grantee` pay fees from `granter`'s account within
+/// `allowance`. `now` starts the first period of a periodic allowance.
+pub fn grant_allowance_msg(
+    granter: &str,
+    grantee: &str,
+    allowance: &Allowance,
+    now: Time,
+) -> Result<Any> {
+    let basic = BasicAllowance {
+        spend_limit: allowance
+            .spend_limit
+            .iter()
+            .cloned()
+            .map(Into::into)
+            .collect(),
+        expiration: allowance.expiration.map(Into::into),
+    };
+    let granted = match &allowance.period {
+        None => Any {
+            type_url: BASIC_ALLOWANCE.to_string(),
+            value: basic.encode_to_vec(),
+        },
+        Some((seconds, limit)) => {
+            if let Some(spend_limit) = &allowance.spend_limit {
+                if spend_limit.denom != limit.denom || spend_limit.amount < limit.amount {
+                    return Err(eyre::Report::msg(
+                        "The period spend limit must not exceed the spend limit",
+                    ));
+                }
+            }
+            let period = std::time::Duration::from_secs(*seconds);
+            let period_reset = match now + period {
+                Ok(period_reset) => period_reset,
+                Err(e) => {
+                    log::error!("Failed to compute the period reset: {}", e);
+                    return Err(eyre::Report::msg(format!(
+                        "Failed to compute the period reset: {}",
+                        e
+                    )));
+                }
+            };
+            let periodic = PeriodicAllowance {
+                basic: Some(basic),
+                period: Some(Duration {
+                    seconds: *seconds as i64,
+                    nanos: 0,
+                }),
+                period_spend_limit: vec![limit.clone().into()],
+                period_can_spend: vec![limit.clone().into()],
+                period_reset: Some(period_reset.into()),
+            };
+            Any {
+                type_url: PERIODIC_ALLOWANCE.to_string(),
+                value: periodic.encode_to_vec(),
+            }
+        }
+    };
+    let msg = MsgGrantAllowance {
+        granter: granter.to_string(),
+        grantee: grantee.to_string(),
+        allowance: Some(granted),
+    };
+
+    Ok(Any {
+        type_url: MSG_GRANT_ALLOWANCE.to_string(),
+        value: msg.encode_to_vec(),
+    })
+}
+
+/// The message that revokes the allowance `granter` gave `grantee`.
+pub fn revoke_allowance_msg(granter: &str, grantee: &str) -> Any {
+    let msg = MsgRevokeAllowance {
+        granter: granter.to_string(),
+        grantee: grantee.to_string(),
+    };
+
+    Any {
+        type_url: MSG_REVOKE_ALLOWANCE.to_string(),
+        value: msg.encode_to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usomm(amount: u128) -> Coin {
+        Coin::new(amount, "usomm").unwrap()
+    }
+
+    #[test]
+    fn grants_periodic_allowance_from_now() {
+        let now = Time::parse_from_rfc3339("2026-10-15T00:00:00Z").unwrap();
+        let allowance = Allowance {
+            spend_limit: Some(usomm(1_000_000)),
+            expiration: None,
+            period: Some((86_400, usomm(10_000))),
+        };
+        let msg = grant_allowance_msg("somm1granter", "somm1grantee", &allowance, now).unwrap();
+        assert_eq!(msg.type_url, MSG_GRANT_ALLOWANCE);
+        let msg = MsgGrantAllowance::decode(msg.value.as_slice()).unwrap();
+        let allowance = msg.allowance.unwrap();
+        assert_eq!(allowance.type_url, PERIODIC_ALLOWANCE);
+        let periodic = PeriodicAllowance::decode(allowance.value.as_slice()).unwrap();
+        assert_eq!(periodic.basic.unwrap().spend_limit[0].amount, "1000000");
+        assert_eq!(periodic.period_can_spend[0].amount, "10000");
+        let period_reset = Time::try_from(periodic.period_reset.unwrap()).unwrap();
+        assert_eq!(period_reset.to_rfc3339(), "2026-10-16T00:00:00Z");
+    }
+
+    #[test]
+    fn rejects_period_limit_above_spend_limit() {
+        let allowance = Allowance {
+            spend_limit: Some(usomm(1_000)),
+            expiration: None,
+            period: Some((3_600, usomm(10_000))),
+        };
+        assert!(grant_allowance_msg(
+            "somm1granter",
+            "somm1grantee",
+            &allowance,
+            Time::unix_epoch()
+        )
+        .is_err());
+    }
+}
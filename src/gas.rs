@@ -0,0 +1,75 @@
+use eyre::Result;
+use std::collections::BTreeMap;
+
+/// Gas charged for every transaction regardless of its messages (signature
+/// verification, fee deduction, tx size).
+pub const DEFAULT_BASE_GAS: u64 = 80_000;
+
+/// Gas for message types missing from the table.
+pub const DEFAULT_MESSAGE_GAS: u64 = 150_000;
+
+/// Default gas per message type URL. Together with the base gas a lone
+/// commission withdrawal gets 200000, the limit this tool has always used.
+pub const DEFAULT_GAS_TABLE: &[(&str, u64)] = &[(
+    "/cosmos.distribution.v1beta1.MsgWithdrawValidatorCommission",
+    120_000,
+)];
+
+/// Static gas estimates per message type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GasTable {
+    base: u64,
+    messages: BTreeMap<String, u64>,
+}
+
+impl Default for GasTable {
+    fn default() -> Self {
+        GasTable {
+            base: DEFAULT_BASE_GAS,
+            messages: DEFAULT_GAS_TABLE
+                .iter()
+                .map(|(type_url, gas)| (type_url.to_string(), *gas))
+                .collect(),
+        }
+    }
+}
+
+impl GasTable {
+    /// Apply an override; the key `base` sets the per-transaction base gas.
+    pub fn set(&mut self, key: &str, gas: u64) {
+        if key == "base" {
+            self.base = gas;
+        } else {
+            self.messages.insert(key.to_string(), gas);
+        }
+    }
+
+    /// Gas limit for a transaction carrying messages of the given types.
+    pub fn estimate<'a>(&self, type_urls: impl IntoIterator<Item = &'a str>) -> u64 {
+        type_urls.into_iter().fold(self.base, |total, type_url| {
+            total.saturating_add(
+                self.messages
+                    .get(type_url)
+                    .copied()
+                    .unwrap_or(DEFAULT_MESSAGE_GAS),
+            )
+        })
+    }
+}
+
+/// Parse a `<type url or base>=<gas>` override.
+pub fn parse_entry(input: &str) -> Result<(String, u64)> {
+    let Some((key, gas)) = input.split_once('=') else {
+        return Err(eyre::Report::msg(format!(
+            "Invalid gas table entry {:?}, expected <type url>=<gas> or base=<gas>",
+            input
+        )));
+    };
+    match gas.trim().parse() {
+        Ok(gas) => Ok((key.trim().to_string(), gas)),
+        Err(e) => Err(eyre::Report::msg(format!(
+            "Invalid gas amount in {:?}: {}",
+            input, e
+        ))),
+    }
+}
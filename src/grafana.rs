@@ -0,0 +1,60 @@
+use crate::outcome::RunOutcome;
+use eyre::Result;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A Grafana instance that receives an annotation for each successful withdrawal.
+#[derive(Debug, Clone)]
+pub struct Grafana {
+    pub url: String,
+    pub token: Option<String>,
+}
+
+/// Post an annotation tagged with the chain, validator and withdrawn amount.
+pub async fn annotate(grafana: &Grafana, outcome: &RunOutcome) -> Result<()> {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default();
+    let body = serde_json::json!({
+        "time": time,
+        "tags": [
+            "withdraw-commission",
+            format!("chain:{}", outcome.chain_id),
+            format!("validator:{}", outcome.validator),
+            format!("amount:{}", outcome.amount),
+        ],
+        "text": format!(
+            "Withdrew {} commission for {} (tx {})",
+            outcome.amount, outcome.validator, outcome.tx_hash
+        ),
+    });
+
+    let mut request = reqwest::Client::new()
+        .post(format!(
+            "{}/api/annotations",
+            grafana.url.trim_end_matches('/')
+        ))
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body.to_string());
+    if let Some(token) = &grafana.token {
+        request = request.bearer_auth(token);
+    }
+
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            return Err(eyre::Report::msg(format!(
+                "Failed to post Grafana annotation: {}",
+                e
+            )));
+        }
+    };
+    if !response.status().is_success() {
+        return Err(eyre::Report::msg(format!(
+            "Grafana annotation returned {}",
+            response.status()
+        )));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,238 @@
+use crate::endpoint::GrpcChannel;
+use cosmrs::proto::cosmos::base::query::v1beta1::PageRequest;
+use cosmrs::proto::cosmos::tx::v1beta1::{
+    service_client::ServiceClient as TxServiceClient, GetTxsEventRequest, OrderBy,
+};
+use cosmrs::tendermint::Time;
+use cosmrs::AccountId;
+use eyre::Result;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+/// A commission withdrawal found in the node's transaction index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Withdrawal {
+    pub tx_hash: String,
+    pub height: u64,
+    pub time: Time,
+}
+
+/// Type URL of the commission withdrawal message, which Cosmos SDK 0.46 and
+/// later use as its `message.action`.
+const MSG_WITHDRAW_COMMISSION: &str = "/cosmos.distribution.v1beta1.MsgWithdrawValidatorCommission";
+
+/// Type URL of an authz exec.
+const MSG_EXEC: &str = "/cosmos.authz.v1beta1.MsgExec";
+
+/// The account of the distribution module, which pays out commission, under
+/// the account `prefix`.
+fn distribution_module_address(prefix: &str) -> Result<String> {
+    match AccountId::new(prefix, &Sha256::digest("distribution")[..20]) {
+        Ok(address) => Ok(address.to_string()),
+        Err(e) => Err(eyre::Report::msg(format!(
+            "Failed to derive the distribution module address: {}",
+            e
+        ))),
+    }
+}
+
+/// Searches that find a commission withdrawal of the validator with
+/// `operator_address` and account `account_address`, whose commission goes to
+/// `withdraw_address`, whoever signed it. The `withdraw_commission` event only
+/// carries the amount, and tx search has no OR, so each event layout gets its
+/// own search.
+fn searches(
+    operator_address: &str,
+    account_address: &str,
+    withdraw_address: &str,
+    module_address: &str,
+) -> Vec<Vec<String>> {
+    vec![
+        // Cosmos SDK 0.45 names the action, and the distribution module sends
+        // from the operator address
+        vec![
+            "message.action='withdraw_validator_commission'".to_string(),
+            format!("message.sender='{}'", operator_address),
+        ],
+        // 0.46 and later use the type URL with the signer, the validator's
+        // account, as the sender
+        vec![
+            format!("message.action='{}'", MSG_WITHDRAW_COMMISSION),
+            format!("message.sender='{}'", account_address),
+        ],
+        // Up to 0.47 the distribution module's own message event names the
+        // operator address, also for withdrawals inside an authz exec
+        vec![
+            "message.module='distribution'".to_string(),
+            format!("message.sender='{}'", operator_address),
+        ],
+        // From 0.50 messages inside an exec emit no message events, so look
+        // for the payout from the distribution module to the withdraw address
+        vec![
+            format!("message.action='{}'", MSG_EXEC),
+            format!("transfer.sender='{}'", module_address),
+            format!("transfer.recipient='{}'", withdraw_address),
+        ],
+    ]
+}
+
+/// The latest commission withdrawal of the validator with `operator_address`
+/// and account `account_address`, whose commission goes to
+/// `withdraw_address`, signed by its own key or through authz, or `None` if
+/// the node's transaction index has none.
+pub async fn last_withdrawal(
+    channel: GrpcChannel,
+    operator_address: &str,
+    account_address: &AccountId,
+    withdraw_address: &str,
+) -> Result<Option<Withdrawal>> {
+    let module_address = distribution_module_address(account_address.prefix())?;
+    let mut last: Option<Withdrawal> = None;
+    for events in searches(
+        operator_address,
+        account_address.as_ref(),
+        withdraw_address,
+        &module_address,
+    ) {
+        if let Some(withdrawal) = search(channel.clone(), events).await? {
+            if last
+                .as_ref()
+                .is_none_or(|last| withdrawal.height > last.height)
+            {
+                last = Some(withdrawal);
+            }
+        }
+    }
+
+    Ok(last)
+}
+
+/// The latest transaction matching all of `events`.
+async fn search(channel: GrpcChannel, events: Vec<String>) -> Result<Option<Withdrawal>> {
+    let mut client = TxServiceClient::new(channel);
+    // Nodes before Cosmos SDK 0.50 read `events` and `pagination`, later
+    // ones `query`, `page` and `limit`
+    #[allow(deprecated)]
+    let request = GetTxsEventRequest {
+        query: events.join(" AND "),
+        events,
+        pagination: Some(PageRequest {
+            limit: 1,
+            ..Default::default()
+        }),
+        order_by: OrderBy::Desc as i32,
+        page: 1,
+        limit: 1,
+    };
+    let response = match client.get_txs_event(request).await {
+        Ok(response) => response.into_inner(),
+        Err(e) => {
+            log::error!("Failed to search past withdrawals: {}", e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to search past withdrawals: {}",
+                e
+            )));
+        }
+    };
+
+    let Some(tx) = response.tx_responses.into_iter().next() else {
+        return Ok(None);
+    };
+    match Time::parse_from_rfc3339(&tx.timestamp) {
+        Ok(time) => Ok(Some(Withdrawal {
+            tx_hash: tx.txhash,
+            height: tx.height as u64,
+            time,
+        })),
+        Err(e) => {
+            log::error!("Failed to parse withdrawal time: {}", e);
+            Err(eyre::Report::msg(format!(
+                "Failed to parse withdrawal time {}: {}",
+                tx.timestamp, e
+            )))
+        }
+    }
+}
+
+/// How long until `min_interval` has passed since `last`, or `None` if it
+/// already has at `now`.
+pub fn remaining(last: Time, now: Time, min_interval: Duration) -> Option<Duration> {
+    let elapsed = now.duration_since(last).unwrap_or_default();
+    min_interval
+        .checked_sub(elapsed)
+        .filter(|remaining| !remaining.is_zero())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waits_out_the_min_interval() {
+        let last = Time::parse_from_rfc3339("2026-10-15T00:00:00Z").unwrap();
+        let now = Time::parse_from_rfc3339("2026-10-15T18:00:00Z").unwrap();
+        let day = Duration::from_secs(86_400);
+        assert_eq!(
+            remaining(last, now, day),
+            Some(Duration::from_secs(6 * 3_600))
+        );
+        assert_eq!(remaining(last, now, Duration::from_secs(18 * 3_600)), None);
+        assert_eq!(remaining(now, last, Duration::ZERO), None);
+    }
+
+    /// Whether a transaction with `events` matches every condition of
+    /// `search`, as the node's transaction index does.
+    fn matches(events: &serde_json::Value, search: &[String]) -> bool {
+        search.iter().all(|condition| {
+            let (key, value) = condition.split_once('=').unwrap();
+            let (kind, key) = key.split_once('.').unwrap();
+            let value = value.trim_matches('\'');
+            events.as_array().unwrap().iter().any(|event| {
+                event["type"] == kind
+                    && event["attributes"]
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .any(|attribute| attribute["key"] == key && attribute["value"] == value)
+            })
+        })
+    }
+
+    #[test]
+    fn finds_withdrawals_in_indexed_events() {
+        let validator = "sommvaloper18pll7w4dwhp38sq79xjerth0dw6jh2tnhmvzw8";
+        let account = "somm18pll7w4dwhp38sq79xjerth0dw6jh2tnz9zlfg";
+        let module_address = distribution_module_address("somm").unwrap();
+        assert_eq!(
+            module_address,
+            "somm1jv65s3grqf6v6jl3dp4t6c9t9rk99cd8trtsc4"
+        );
+        let ours = searches(validator, account, account, &module_address);
+        let other = searches(
+            "sommvaloper15pq7xy8m3r945w78wx0d6hngm43p5kencvmjja",
+            "somm15pq7xy8m3r945w78wx0d6hngm43p5kendj404j",
+            "somm15pq7xy8m3r945w78wx0d6hngm43p5kendj404j",
+            &module_address,
+        );
+
+        for fixture in [
+            include_str!("../tests/fixtures/history/sdk-0.47.json"),
+            include_str!("../tests/fixtures/history/sdk-0.50.json"),
+        ] {
+            let fixture: serde_json::Value = serde_json::from_str(fixture).unwrap();
+            for tx in fixture["tx_responses"].as_array().unwrap() {
+                let events = &tx["events"];
+                assert!(
+                    ours.iter().any(|search| matches(events, search)),
+                    "no search finds {}",
+                    tx["txhash"]
+                );
+                assert!(
+                    !other.iter().any(|search| matches(events, search)),
+                    "another validator's search finds {}",
+                    tx["txhash"]
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use crate::endpoint::{at_height, GrpcChannel};
+use cosmrs::proto::prost::Message;
+use cosmrs::tendermint::Time;
+use cosmrs::{AccountId, Any, Coin};
+use eyre::Result;
+use std::time::Duration;
+
+/// Type URL of the ICS-20 fungible token transfer message.
+const MSG_TRANSFER: &str = "/ibc.applications.transfer.v1.MsgTransfer";
+
+/// Port of the ICS-20 transfer application.
+const TRANSFER_PORT: &str = "transfer";
+
+/// Counterparty blocks until a transfer times out, as `<daemon> tx ibc-transfer` defaults to.
+pub const TIMEOUT_BLOCKS: u64 = 1000;
+
+/// Time until a transfer times out, as `<daemon> tx ibc-transfer` defaults to.
+pub const TIMEOUT: Duration = Duration::from_secs(600);
+
+// The ibc-go messages this tool needs, declared here since cosmrs does not
+// ship the IBC protos. Response fields it does not read are left out.
+
+/// `ibc.core.client.v1.Height`
+#[derive(Clone, Copy, PartialEq, Eq, Message)]
+pub struct Height {
+    #[prost(uint64, tag = "1")]
+    pub revision_number: u64,
+    #[prost(uint64, tag = "2")]
+    pub revision_height: u64,
+}
+
+/// `ibc.applications.transfer.v1.MsgTransfer`
+#[derive(Clone, PartialEq, Message)]
+struct MsgTransfer {
+    #[prost(string, tag = "1")]
+    source_port: String,
+    #[prost(string, tag = "2")]
+    source_channel: String,
+    #[prost(message, optional, tag = "3")]
+    token: Option<cosmrs::proto::cosmos::base::v1beta1::Coin>,
+    #[prost(string, tag = "4")]
+    sender: String,
+    #[prost(string, tag = "5")]
+    receiver: String,
+    #[prost(message, optional, tag = "6")]
+    timeout_height: Option<Height>,
+    #[prost(uint64, tag = "7")]
+    timeout_timestamp: u64,
+    #[prost(string, tag = "8")]
+    memo: String,
+}
+
+/// `ibc.core.channel.v1.QueryChannelClientStateRequest`
+#[derive(Clone, PartialEq, Message)]
+struct QueryChannelClientStateRequest {
+    #[prost(string, tag = "1")]
+    port_id: String,
+    #[prost(string, tag = "2")]
+    channel_id: String,
+}
+
+/// `ibc.core.channel.v1.QueryChannelClientStateResponse`
+#[derive(Clone, PartialEq, Message)]
+struct QueryChannelClientStateResponse {
+    #[prost(message, optional, tag = "1")]
+    identified_client_state: Option<IdentifiedClientState>,
+}
+
+/// `ibc.core.client.v1.IdentifiedClientState`
+#[derive(Clone, PartialEq, Message)]
+struct IdentifiedClientState {
+    #[prost(message, optional, tag = "2")]
+    client_state: Option<Any>,
+}
+
+/// `ibc.lightclients.tendermint.v1.ClientState`
+#[derive(Clone, PartialEq, Message)]
+struct TendermintClientState {
+    #[prost(message, optional, tag = "7")]
+    latest_height: Option<Height>,
+}
+
+/// Type URL of the Tendermint light client state.
+const TENDERMINT_CLIENT_STATE: &str = "/ibc.lightclients.tendermint.v1.ClientState";
+
+/// Parse a `<channel>:<receiver>` transfer destination, such as
+/// `channel-0:cosmos1...`.
+pub fn parse_destination(input: &str) -> Result<(String, String)> {
+    match input.split_once(':') {
+        Some((channel, receiver))
+            if channel.starts_with("channel-") && !receiver.trim().is_empty() =>
+        {
+            Ok((channel.to_string(), receiver.trim().to_string()))
+        }
+        _ => Err(eyre::Report::msg(format!(
+            "Invalid IBC destination {:?}, expected \"channel-<n>:<receiver>\"",
+            input
+        ))),
+    }
+}
+
+/// The latest counterparty height known to the light client behind
+/// `channel_id`, at `height`.
+pub async fn counterparty_height(
+    channel: GrpcChannel,
+    channel_id: &str,
+    height: u64,
+) -> Result<Height> {
+    let mut grpc = tonic::client::Grpc::new(channel);
+    if let Err(e) = grpc.ready().await {
+        log::error!("Failed to query IBC client state: {}", e);
+        return Err(eyre::Report::msg(format!(
+            "Failed to query IBC client state: {}",
+            e
+        )));
+    }
+    let request = at_height(
+        QueryChannelClientStateRequest {
+            port_id: TRANSFER_PORT.to_string(),
+            channel_id: channel_id.to_string(),
+        },
+        height,
+    );
+    let response: QueryChannelClientStateResponse = match grpc
+        .unary(
+            request,
+            http::uri::PathAndQuery::from_static("/ibc.core.channel.v1.Query/ChannelClientState"),
+            tonic::codec::ProstCodec::default(),
+        )
+        .await
+    {
+        Ok(response) => response.into_inner(),
+        Err(e) => {
+            log::error!("Failed to query IBC client state: {}", e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to query IBC client state of {}: {}",
+                channel_id, e
+            )));
+        }
+    };
+
+    let client_state = response
+        .identified_client_state
+        .and_then(|identified| identified.client_state)
+        .unwrap_or_default();
+    latest_height(&client_state).ok_or_else(|| {
+        eyre::Report::msg(format!(
+            "{} is not backed by a Tendermint light client ({})",
+            channel_id, client_state.type_url
+        ))
+    })
+}
+
+fn latest_height(client_state: &Any) -> Option<Height> {
+    if client_state.type_url != TENDERMINT_CLIENT_STATE {
+        return None;
+    }
+    TendermintClientState::decode(client_state.value.as_slice())
+        .ok()?
+        .latest_height
+}
+
+/// The message that sends `token` from `sender` to `receiver` over
+/// `channel_id`, timing out [`TIMEOUT_BLOCKS`] after `counterparty_height`
+/// or [`TIMEOUT`] after `now`, whichever comes first.
+pub fn transfer_msg(
+    channel_id: &str,
+    sender: &AccountId,
+    receiver: &str,
+    token: Coin,
+    counterparty_height: Height,
+    now: Time,
+) -> Any {
+    let timeout_timestamp = (now + TIMEOUT)
+        .map(|timeout| timeout.unix_timestamp_nanos() as u64)
+        .unwrap_or_default();
+    let msg = MsgTransfer {
+        source_port: TRANSFER_PORT.to_string(),
+        source_channel: channel_id.to_string(),
+        token: Some(token.into()),
+        sender: sender.to_string(),
+        receiver: receiver.to_string(),
+        timeout_height: Some(Height {
+            revision_number: counterparty_height.revision_number,
+            revision_height: counterparty_height.revision_height + TIMEOUT_BLOCKS,
+        }),
+        timeout_timestamp,
+        memo: String::new(),
+    };
+
+    Any {
+        type_url: MSG_TRANSFER.to_string(),
+        value: msg.encode_to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_tendermint_client_height() {
+        let height = Height {
+            revision_number: 4,
+            revision_height: 19_000_000,
+        };
+        let client_state = Any {
+            type_url: TENDERMINT_CLIENT_STATE.to_string(),
+            value: TendermintClientState {
+                latest_height: Some(height),
+            }
+            .encode_to_vec(),
+        };
+        assert_eq!(latest_height(&client_state), Some(height));
+
+        let sender: AccountId = "somm18pll7w4dwhp38sq79xjerth0dw6jh2tnz9zlfg"
+            .parse()
+            .unwrap();
+        let token = Coin::new(1500, "usomm").unwrap();
+        let now = Time::parse_from_rfc3339("2026-10-15T00:00:00Z").unwrap();
+        let msg = transfer_msg("channel-0", &sender, "cosmos1xyz", token, height, now);
+        let msg = MsgTransfer::decode(msg.value.as_slice()).unwrap();
+        assert_eq!(msg.timeout_height.unwrap().revision_height, 19_001_000);
+        assert_eq!(msg.timeout_timestamp, 1_792_023_000_000_000_000);
+    }
+
+    #[test]
+    fn parses_destination() {
+        assert_eq!(
+            parse_destination("channel-0:cosmos1xyz").unwrap(),
+            ("channel-0".to_string(), "cosmos1xyz".to_string())
+        );
+        assert!(parse_destination("cosmos1xyz").is_err());
+    }
+}
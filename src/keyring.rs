@@ -0,0 +1,225 @@
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use aes_kw::KekAes128;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use cosmrs::crypto::secp256k1::SigningKey;
+use cosmrs::proto::cosmos::crypto::secp256k1::PrivKey;
+use cosmrs::proto::prost::Message;
+use cosmrs::Any;
+use eyre::Result;
+use serde::Deserialize;
+use sha2::Sha256;
+use std::fs;
+use std::path::Path;
+
+/// The only key management algorithm the file keyring backend uses.
+const ALG: &str = "PBES2-HS256+A128KW";
+
+/// Type URL of the private keys this tool can sign with.
+const SECP256K1_PRIV_KEY: &str = "/cosmos.crypto.secp256k1.PrivKey";
+
+/// The subset of `cosmos.crypto.keyring.v1.Record` needed to get at a local key.
+#[derive(Clone, PartialEq, prost::Message)]
+struct Record {
+    #[prost(string, tag = "1")]
+    name: String,
+    #[prost(message, optional, tag = "3")]
+    local: Option<Local>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Local {
+    #[prost(message, optional, tag = "1")]
+    priv_key: Option<Any>,
+}
+
+/// The JSON item the keyring stores encrypted in each file.
+#[derive(Deserialize)]
+struct Item {
+    #[serde(rename = "Data")]
+    data: String,
+}
+
+/// The protected JWE header of a keyring file.
+#[derive(Deserialize)]
+struct Header {
+    alg: String,
+    enc: String,
+    p2s: String,
+    p2c: u32,
+}
+
+/// Load the key `name` from a Cosmos SDK `file` keyring backend directory,
+/// such as `~/.sommelier/keyring-file`.
+pub fn load_keyring_key(dir: impl AsRef<Path>, name: &str, passphrase: &str) -> Result<SigningKey> {
+    let path = dir.as_ref().join(format!("{}.info", name));
+    let token = match fs::read_to_string(&path) {
+        Ok(token) => token,
+        Err(e) => {
+            log::error!("Failed to read keyring file {}: {}", path.display(), e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to read keyring file {}: {}",
+                path.display(),
+                e
+            )));
+        }
+    };
+
+    let item = decrypt(token.trim(), passphrase)?;
+    let item: Item = match serde_json::from_slice(&item) {
+        Ok(item) => item,
+        Err(e) => {
+            log::error!("Failed to parse keyring item: {}", e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to parse keyring item: {}",
+                e
+            )));
+        }
+    };
+
+    record_key(&decode(&STANDARD, &item.data)?)
+}
+
+/// The secp256k1 signing key held by a protobuf keyring record.
+fn record_key(record: &[u8]) -> Result<SigningKey> {
+    let record = match Record::decode(record) {
+        Ok(record) => record,
+        Err(e) => {
+            log::error!("Failed to decode keyring record: {}", e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to decode keyring record (only records written by Cosmos SDK 0.46 or later are supported): {}",
+                e
+            )));
+        }
+    };
+    let Some(priv_key) = record.local.and_then(|local| local.priv_key) else {
+        return Err(eyre::Report::msg(format!(
+            "Key {} is not a local key (ledger, multisig and offline keys cannot sign here)",
+            record.name
+        )));
+    };
+    if priv_key.type_url != SECP256K1_PRIV_KEY {
+        return Err(eyre::Report::msg(format!(
+            "Key {} is a {} key, expected {}",
+            record.name, priv_key.type_url, SECP256K1_PRIV_KEY
+        )));
+    }
+    let priv_key = match PrivKey::decode(priv_key.value.as_slice()) {
+        Ok(priv_key) => priv_key,
+        Err(e) => {
+            log::error!("Failed to decode private key: {}", e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to decode private key: {}",
+                e
+            )));
+        }
+    };
+    match SigningKey::from_slice(&priv_key.key) {
+        Ok(key) => Ok(key),
+        Err(e) => {
+            log::error!("Failed to create signing key: {}", e);
+            Err(eyre::Report::msg(format!(
+                "Failed to create signing key: {}",
+                e
+            )))
+        }
+    }
+}
+
+/// Decrypt a compact JWE encrypted with PBES2-HS256+A128KW and A256GCM, as
+/// written by the `file` keyring backend.
+fn decrypt(token: &str, passphrase: &str) -> Result<Vec<u8>> {
+    let parts: Vec<&str> = token.split('.').collect();
+    let [protected, encrypted_key, iv, ciphertext, tag] = parts[..] else {
+        return Err(eyre::Report::msg("Keyring file is not a compact JWE"));
+    };
+    let header: Header = match serde_json::from_slice(&decode(&URL_SAFE_NO_PAD, protected)?) {
+        Ok(header) => header,
+        Err(e) => {
+            log::error!("Failed to parse keyring file header: {}", e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to parse keyring file header: {}",
+                e
+            )));
+        }
+    };
+    if header.alg != ALG || header.enc != "A256GCM" {
+        return Err(eyre::Report::msg(format!(
+            "Unsupported keyring encryption {} with {}",
+            header.alg, header.enc
+        )));
+    }
+
+    // Derive the key encryption key from the passphrase and unwrap the content key
+    let mut salt = ALG.as_bytes().to_vec();
+    salt.push(0);
+    salt.extend(decode(&URL_SAFE_NO_PAD, &header.p2s)?);
+    let mut kek = [0u8; 16];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), &salt, header.p2c, &mut kek);
+    let encrypted_key = decode(&URL_SAFE_NO_PAD, encrypted_key)?;
+    let mut cek = [0u8; 32];
+    if encrypted_key.len() != cek.len() + 8
+        || KekAes128::from(kek)
+            .unwrap(&encrypted_key, &mut cek)
+            .is_err()
+    {
+        return Err(eyre::Report::msg(
+            "Failed to decrypt keyring file: wrong passphrase?",
+        ));
+    }
+
+    let iv = decode(&URL_SAFE_NO_PAD, iv)?;
+    if iv.len() != 12 {
+        return Err(eyre::Report::msg("Keyring file has an invalid IV"));
+    }
+    let mut sealed = decode(&URL_SAFE_NO_PAD, ciphertext)?;
+    sealed.extend(decode(&URL_SAFE_NO_PAD, tag)?);
+    let cipher = Aes256Gcm::new(&cek.into());
+    match cipher.decrypt(
+        Nonce::from_slice(&iv),
+        Payload {
+            msg: &sealed,
+            aad: protected.as_bytes(),
+        },
+    ) {
+        Ok(plaintext) => Ok(plaintext),
+        Err(_) => Err(eyre::Report::msg("Failed to decrypt keyring file")),
+    }
+}
+
+fn decode(engine: &impl Engine, input: &str) -> Result<Vec<u8>> {
+    match engine.decode(input) {
+        Ok(bytes) => Ok(bytes),
+        Err(e) => {
+            log::error!("Failed to decode keyring file: {}", e);
+            Err(eyre::Report::msg(format!(
+                "Failed to decode keyring file: {}",
+                e
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/keyring-file");
+
+    #[test]
+    fn decrypts_file_keyring_key() {
+        let key = load_keyring_key(DIR, "validator", "correct horse battery").unwrap();
+        let expected = crate::keys::load_signing_key(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/key.hex"
+        ))
+        .unwrap();
+        assert_eq!(key.public_key(), expected.public_key());
+    }
+
+    #[test]
+    fn rejects_wrong_passphrase() {
+        assert!(load_keyring_key(DIR, "validator", "wrong").is_err());
+    }
+}
@@ -0,0 +1,361 @@
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use bip32::{DerivationPath, Language, Mnemonic, XPrv};
+use cosmrs::crypto::secp256k1::SigningKey;
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+/// The Cosmos Hub HD path used by `gaiad keys add` and most wallets.
+pub const DEFAULT_HD_PATH: &str = "m/44'/118'/0'/0/0";
+
+/// The BIP-44 coin type of [`DEFAULT_HD_PATH`].
+pub const DEFAULT_COIN_TYPE: u32 = 118;
+
+/// The first account's HD path for `coin_type`, as Cosmos SDK wallets derive it.
+pub fn hd_path(coin_type: u32) -> String {
+    format!("m/44'/{}'/0'/0/0", coin_type)
+}
+
+/// scrypt cost (log2 of N) for newly encrypted key files.
+const SCRYPT_LOG_N: u8 = 15;
+
+/// A private key encrypted with a passphrase: scrypt derives an AES-256-GCM
+/// key that seals the raw key bytes.
+#[derive(Debug, Serialize, Deserialize)]
+struct EncryptedKey {
+    kdf: String,
+    log_n: u8,
+    r: u32,
+    p: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Load a secp256k1 signing key from a file holding the hex-encoded private key.
+pub fn load_signing_key(path: impl AsRef<Path>) -> Result<SigningKey> {
+    load_key_file(path, || {
+        Err(eyre::Report::msg(
+            "The key file is encrypted, a passphrase is required",
+        ))
+    })
+}
+
+/// Load a secp256k1 signing key from a file holding either the hex-encoded
+/// private key or one encrypted by `keys encrypt`, asking `passphrase` for
+/// the passphrase only if it is needed.
+pub fn load_key_file(
+    path: impl AsRef<Path>,
+    passphrase: impl FnOnce() -> Result<String>,
+) -> Result<SigningKey> {
+    // Read private key from file
+    let private_key = match fs::read_to_string(path) {
+        Ok(key) => key.trim().to_string(),
+        Err(e) => {
+            log::error!("Failed to read private key from file: {}", e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to read private key from file: {}",
+                e
+            )));
+        }
+    };
+
+    parse_key(&private_key, passphrase)
+}
+
+/// Parse a hex-encoded or encrypted private key, such as one read from stdin
+/// or an environment variable.
+pub fn parse_key(
+    private_key: &str,
+    passphrase: impl FnOnce() -> Result<String>,
+) -> Result<SigningKey> {
+    let private_key = private_key.trim();
+    if private_key.starts_with('{') {
+        return signing_key(&decrypt_key(private_key, &passphrase()?)?);
+    }
+
+    // Create the signing key from the private key
+    let decoded_private_key = match hex::decode(private_key) {
+        Ok(decoded) => decoded,
+        Err(e) => {
+            log::error!("Failed to decode private key: {}", e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to decode private key: {}",
+                e
+            )));
+        }
+    };
+    signing_key(&decoded_private_key)
+}
+
+fn signing_key(private_key: &[u8]) -> Result<SigningKey> {
+    match SigningKey::from_slice(private_key) {
+        Ok(key) => Ok(key),
+        Err(e) => {
+            log::error!("Failed to create signing key: {}", e);
+            Err(eyre::Report::msg(format!(
+                "Failed to create signing key: {}",
+                e
+            )))
+        }
+    }
+}
+
+/// Derive a signing key from the BIP-39 mnemonic in `path` (`-` reads it from
+/// stdin) at the BIP-32 derivation path `hd_path`.
+pub fn load_mnemonic_key(path: &str, hd_path: &str) -> Result<SigningKey> {
+    let mut phrase = String::new();
+    let read = if path == "-" {
+        std::io::stdin().read_to_string(&mut phrase).map(|_| ())
+    } else {
+        fs::read_to_string(path).map(|contents| phrase = contents)
+    };
+    if let Err(e) = read {
+        log::error!("Failed to read mnemonic: {}", e);
+        return Err(eyre::Report::msg(format!("Failed to read mnemonic: {}", e)));
+    }
+
+    mnemonic_key(&phrase, hd_path)
+}
+
+/// Derive a signing key from a 24-word BIP-39 mnemonic phrase at `hd_path`.
+pub fn mnemonic_key(phrase: &str, hd_path: &str) -> Result<SigningKey> {
+    // Normalize whitespace so phrases split across lines are accepted
+    let phrase = phrase.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mnemonic = match Mnemonic::new(phrase, Language::English) {
+        Ok(mnemonic) => mnemonic,
+        Err(e) => {
+            log::error!("Failed to parse mnemonic: {}", e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to parse mnemonic: {}",
+                e
+            )));
+        }
+    };
+    let hd_path = match hd_path.parse::<DerivationPath>() {
+        Ok(hd_path) => hd_path,
+        Err(e) => {
+            log::error!("Failed to parse HD path {}: {}", hd_path, e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to parse HD path {}: {}",
+                hd_path, e
+            )));
+        }
+    };
+    match XPrv::derive_from_path(mnemonic.to_seed(""), &hd_path) {
+        Ok(xprv) => Ok(SigningKey::from(xprv)),
+        Err(e) => {
+            log::error!("Failed to derive signing key: {}", e);
+            Err(eyre::Report::msg(format!(
+                "Failed to derive signing key: {}",
+                e
+            )))
+        }
+    }
+}
+
+/// Encrypt the hex key file at `path` with `passphrase`, returning the
+/// contents of the encrypted key file.
+pub fn encrypt_key_file(path: impl AsRef<Path>, passphrase: &str) -> Result<String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents.trim().to_string(),
+        Err(e) => {
+            log::error!("Failed to read private key from file: {}", e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to read private key from file: {}",
+                e
+            )));
+        }
+    };
+    if contents.starts_with('{') {
+        return Err(eyre::Report::msg("The key file is already encrypted"));
+    }
+    let private_key = match hex::decode(&contents) {
+        Ok(decoded) => decoded,
+        Err(e) => {
+            log::error!("Failed to decode private key: {}", e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to decode private key: {}",
+                e
+            )));
+        }
+    };
+    signing_key(&private_key)?;
+
+    encrypt_key(&private_key, passphrase)
+}
+
+/// Encrypt raw private key bytes with `passphrase`, returning the contents
+/// of an encrypted key file.
+pub fn encrypt_key(private_key: &[u8], passphrase: &str) -> Result<String> {
+    encrypt_key_with(private_key, passphrase, SCRYPT_LOG_N)
+}
+
+/// Generate a new random signing key, returning it with the contents of its
+/// encrypted key file.
+pub fn generate_key(passphrase: &str) -> Result<(SigningKey, String)> {
+    let mut private_key = [0u8; 32];
+    // Draw again in the vanishingly rare case the bytes are not a valid scalar
+    let signing_key = loop {
+        OsRng.fill_bytes(&mut private_key);
+        if let Ok(key) = SigningKey::from_slice(&private_key) {
+            break key;
+        }
+    };
+    let contents = encrypt_key(&private_key, passphrase)?;
+
+    Ok((signing_key, contents))
+}
+
+fn encrypt_key_with(private_key: &[u8], passphrase: &str, log_n: u8) -> Result<String> {
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let (r, p) = (8, 1);
+    let cipher = Aes256Gcm::new(&derive_key(passphrase, &salt, log_n, r, p)?.into());
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = match cipher.encrypt(&nonce, private_key) {
+        Ok(ciphertext) => ciphertext,
+        Err(e) => {
+            log::error!("Failed to encrypt private key: {}", e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to encrypt private key: {}",
+                e
+            )));
+        }
+    };
+
+    let encrypted = EncryptedKey {
+        kdf: "scrypt".to_string(),
+        log_n,
+        r,
+        p,
+        salt: STANDARD.encode(salt),
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(ciphertext),
+    };
+    Ok(serde_json::to_string_pretty(&encrypted)? + "\n")
+}
+
+/// Decrypt the contents of an encrypted key file into raw private key bytes.
+fn decrypt_key(contents: &str, passphrase: &str) -> Result<Vec<u8>> {
+    let encrypted: EncryptedKey = match serde_json::from_str(contents) {
+        Ok(encrypted) => encrypted,
+        Err(e) => {
+            log::error!("Failed to parse encrypted key file: {}", e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to parse encrypted key file: {}",
+                e
+            )));
+        }
+    };
+    if encrypted.kdf != "scrypt" {
+        return Err(eyre::Report::msg(format!(
+            "Unsupported key derivation {}",
+            encrypted.kdf
+        )));
+    }
+    let decode = |field: &str| match STANDARD.decode(field) {
+        Ok(bytes) => Ok(bytes),
+        Err(e) => Err(eyre::Report::msg(format!(
+            "Failed to decode encrypted key file: {}",
+            e
+        ))),
+    };
+    let nonce = decode(&encrypted.nonce)?;
+    if nonce.len() != 12 {
+        return Err(eyre::Report::msg("Encrypted key file has an invalid nonce"));
+    }
+
+    let key = derive_key(
+        passphrase,
+        &decode(&encrypted.salt)?,
+        encrypted.log_n,
+        encrypted.r,
+        encrypted.p,
+    )?;
+    match Aes256Gcm::new(&key.into()).decrypt(
+        Nonce::from_slice(&nonce),
+        decode(&encrypted.ciphertext)?.as_slice(),
+    ) {
+        Ok(private_key) => Ok(private_key),
+        Err(_) => Err(eyre::Report::msg(
+            "Failed to decrypt key file: wrong passphrase?",
+        )),
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], log_n: u8, r: u32, p: u32) -> Result<[u8; 32]> {
+    let mut key = [0u8; 32];
+    let params = match scrypt::Params::new(log_n, r, p, key.len()) {
+        Ok(params) => params,
+        Err(e) => {
+            return Err(eyre::Report::msg(format!(
+                "Invalid scrypt parameters: {}",
+                e
+            )))
+        }
+    };
+    if let Err(e) = scrypt::scrypt(passphrase.as_bytes(), salt, &params, &mut key) {
+        return Err(eyre::Report::msg(format!(
+            "Failed to derive key from passphrase: {}",
+            e
+        )));
+    }
+
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon \
+        abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+        abandon abandon abandon abandon art";
+
+    #[test]
+    fn derives_cosmos_hub_address_from_mnemonic() {
+        let key = mnemonic_key(MNEMONIC, DEFAULT_HD_PATH).unwrap();
+        assert_eq!(
+            key.public_key().account_id("cosmos").unwrap().to_string(),
+            "cosmos1r5v5srda7xfth3hn2s26txvrcrntldjumt8mhl"
+        );
+    }
+
+    #[test]
+    fn derives_at_the_coin_type_of_the_chain_preset() {
+        let terra = crate::chain::parse_preset("terra").unwrap();
+        let hd_path = crate::chain::hd_path(Some(&terra), None);
+        assert_eq!(hd_path, "m/44'/330'/0'/0/0");
+        let key = mnemonic_key(MNEMONIC, &hd_path).unwrap();
+        assert_eq!(
+            key.public_key().account_id("terra").unwrap().to_string(),
+            "terra1uag78r7hn6we00ddudd9juv2f5nvapf0qtuskg"
+        );
+        // An explicit path wins over the preset
+        assert_eq!(
+            crate::chain::hd_path(Some(&terra), Some(DEFAULT_HD_PATH)),
+            DEFAULT_HD_PATH
+        );
+    }
+
+    #[test]
+    fn encrypted_key_round_trips() {
+        let private_key = [7u8; 32];
+        let contents = encrypt_key_with(&private_key, "hunter2", 10).unwrap();
+        assert_eq!(decrypt_key(&contents, "hunter2").unwrap(), private_key);
+        assert!(decrypt_key(&contents, "hunter3").is_err());
+    }
+
+    #[test]
+    fn rejects_bad_checksum() {
+        let phrase = MNEMONIC.replace("art", "abandon");
+        assert!(mnemonic_key(&phrase, DEFAULT_HD_PATH).is_err());
+    }
+}
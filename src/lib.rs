@@ -0,0 +1,59 @@
+//! Withdraw validator commission on Cosmos SDK chains.
+//!
+//! The `withdraw-commission` binary is a thin command line over this crate.
+//! Other tools can drive the same flow with [`WithdrawClient`] and
+//! [`withdraw_commission`] instead of shelling out to the binary.
+
+pub mod abci;
+pub mod account;
+pub mod authz;
+pub mod balance_sheet;
+pub mod broadcast;
+pub mod build_info;
+#[cfg(feature = "callback")]
+pub mod callback;
+pub mod chain;
+mod client;
+pub mod commission;
+pub mod compat;
+pub mod config;
+pub mod consumer;
+pub mod container;
+pub mod cross_check;
+pub mod decimal;
+pub mod endpoint;
+pub mod failure;
+pub mod feegrant;
+pub mod gas;
+#[cfg(feature = "grafana")]
+pub mod grafana;
+pub mod history;
+pub mod ibc;
+pub mod keyring;
+pub mod keys;
+pub mod local_node;
+pub mod logging;
+pub mod migrate;
+pub mod multisig;
+#[cfg(feature = "os-keychain")]
+pub mod os_keychain;
+pub mod outcome;
+pub mod plan;
+#[cfg(feature = "pushgateway")]
+pub mod pushgateway;
+pub mod slashing;
+#[cfg(feature = "socks")]
+pub mod socks;
+pub mod split;
+#[cfg(feature = "statsd")]
+pub mod statsd;
+pub mod tx;
+pub mod unit;
+pub mod unix_socket;
+pub mod validator_set;
+
+pub use client::{
+    read_only_env, withdraw_commission, CrossCheck, WithdrawClient, DEFAULT_SLASH_LOOKBACK_BLOCKS,
+    READ_ONLY_ENV,
+};
+pub use tx::WithdrawTx;
@@ -0,0 +1,114 @@
+use eyre::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Default CometBFT/gRPC listen addresses used when the node home has no
+/// config files.
+const DEFAULT_RPC_LADDR: &str = "tcp://127.0.0.1:26657";
+const DEFAULT_GRPC_ADDRESS: &str = "localhost:9090";
+
+/// Endpoints and chain id of a node running on this host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalNode {
+    pub rpc_url: String,
+    pub grpc_url: String,
+    pub chain_id: Option<String>,
+}
+
+/// The node home directory: the given one, or `~/.sommelier`.
+pub fn home(home: Option<&str>) -> PathBuf {
+    match home {
+        Some(home) => PathBuf::from(home),
+        None => std::env::var_os("HOME")
+            .map(PathBuf::from)
+            .unwrap_or_default()
+            .join(".sommelier"),
+    }
+}
+
+/// Read `config/config.toml`, `config/app.toml` and `config/client.toml` under
+/// the node home to find its RPC and gRPC listen addresses and chain id.
+/// Missing files fall back to the default localhost ports.
+pub fn discover(home: &Path) -> Result<LocalNode> {
+    let config = read_config(&home.join("config").join("config.toml"))?;
+    let app = read_config(&home.join("config").join("app.toml"))?;
+    let client = read_config(&home.join("config").join("client.toml"))?;
+
+    let rpc_laddr = lookup(&config, &["rpc", "laddr"]).unwrap_or(DEFAULT_RPC_LADDR);
+    let rpc_url = if rpc_laddr.starts_with("unix://") {
+        rpc_laddr.to_string()
+    } else if let Some(rpc_address) = rpc_laddr.strip_prefix("tcp://") {
+        format!("http://{}", connectable(rpc_address))
+    } else {
+        return Err(eyre::Report::msg(format!(
+            "Unsupported RPC listen address {:?} in config.toml",
+            rpc_laddr
+        )));
+    };
+
+    if app
+        .get("grpc")
+        .and_then(|grpc| grpc.get("enable"))
+        .and_then(toml::Value::as_bool)
+        == Some(false)
+    {
+        return Err(eyre::Report::msg(
+            "gRPC is disabled in app.toml ([grpc] enable = false)",
+        ));
+    }
+    let grpc_address = lookup(&app, &["grpc", "address"]).unwrap_or(DEFAULT_GRPC_ADDRESS);
+
+    Ok(LocalNode {
+        rpc_url,
+        grpc_url: format!("http://{}", connectable(grpc_address)),
+        chain_id: lookup(&client, &["chain-id"])
+            .filter(|chain_id| !chain_id.is_empty())
+            .map(str::to_string),
+    })
+}
+
+fn read_config(path: &Path) -> Result<toml::Table> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(toml::Table::new()),
+        Err(e) => {
+            log::error!("Failed to read {}: {}", path.display(), e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to read {}: {}",
+                path.display(),
+                e
+            )));
+        }
+    };
+
+    match contents.parse() {
+        Ok(table) => Ok(table),
+        Err(e) => {
+            log::error!("Failed to parse {}: {}", path.display(), e);
+            Err(eyre::Report::msg(format!(
+                "Failed to parse {}: {}",
+                path.display(),
+                e
+            )))
+        }
+    }
+}
+
+fn lookup<'a>(table: &'a toml::Table, path: &[&str]) -> Option<&'a str> {
+    let (last, sections) = path.split_last()?;
+    let mut table = table;
+    for section in sections {
+        table = table.get(*section)?.as_table()?;
+    }
+
+    table.get(*last)?.as_str()
+}
+
+/// Turn a listen address into one we can connect to: services bound to all
+/// interfaces are reached over loopback.
+fn connectable(address: &str) -> String {
+    match address.rsplit_once(':') {
+        Some(("0.0.0.0" | "" | "[::]", port)) => format!("127.0.0.1:{}", port),
+        _ => address.to_string(),
+    }
+}
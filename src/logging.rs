@@ -0,0 +1,47 @@
+use clap::ValueEnum;
+use std::io::Write;
+
+/// How log lines are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// JSON when running in a container, text otherwise
+    Auto,
+    Text,
+    Json,
+}
+
+/// Whether the process is running in a container, signalled by a non-empty
+/// `RUNNING_IN_CONTAINER` environment variable that is not `0` or `false`.
+pub fn in_container() -> bool {
+    match std::env::var("RUNNING_IN_CONTAINER") {
+        Ok(value) => !matches!(value.trim(), "" | "0" | "false"),
+        Err(_) => false,
+    }
+}
+
+/// Configure logging. Both formats go to stderr, which container log
+/// collectors capture too, so stdout only carries command output such as
+/// `--output json` and `--plan`.
+pub fn init(format: LogFormat) {
+    let json = match format {
+        LogFormat::Auto => in_container(),
+        LogFormat::Text => false,
+        LogFormat::Json => true,
+    };
+
+    let mut builder = env_logger::Builder::from_default_env();
+    builder.filter_level(log::LevelFilter::Info);
+    if json {
+        builder.format(|buf, record| {
+            let line = serde_json::json!({
+                "timestamp": buf.timestamp_millis().to_string(),
+                "level": record.level().as_str(),
+                "message": record.args().to_string(),
+            });
+            writeln!(buf, "{}", line)
+        });
+    } else {
+        builder.format_timestamp(None).format_module_path(false);
+    }
+    builder.init();
+}
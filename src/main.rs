@@ -1,88 +1,1420 @@
-use clap::Parser;
-use cosmrs::distribution::MsgWithdrawValidatorCommission;
-use cosmrs::proto::prost::Message;
-use cosmrs::tx::Msg;
-use cosmrs::{
-    crypto::secp256k1::SigningKey,
-    rpc::Client,
-    tendermint::{block::Height, chain::Id},
-    tx::{AuthInfo, Body, Fee, SignDoc, SignerInfo},
-    Coin,
-};
+use base64::Engine;
+use clap::{parser::ValueSource, ArgMatches, CommandFactory, FromArgMatches, Parser, ValueEnum};
+use cosmrs::tendermint::{block::Height, Time};
+use cosmrs::tx::Body;
 use eyre::Result;
-use std::{fs, str::FromStr};
+use std::io::{Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::{fs, time::Duration};
+#[cfg(feature = "callback")]
+use withdraw_commission::callback;
+#[cfg(feature = "grafana")]
+use withdraw_commission::grafana;
+#[cfg(feature = "os-keychain")]
+use withdraw_commission::os_keychain;
+#[cfg(feature = "pushgateway")]
+use withdraw_commission::pushgateway;
+#[cfg(feature = "socks")]
+use withdraw_commission::socks;
+#[cfg(feature = "statsd")]
+use withdraw_commission::statsd;
+use withdraw_commission::{
+    account, authz, balance_sheet, broadcast, build_info, chain, commission, compat, config,
+    container, cross_check,
+    decimal::{self, Decimal},
+    endpoint, feegrant, gas, history, ibc, keyring, keys, local_node, logging, migrate, multisig,
+    outcome, plan, read_only_env, split, tx, unit, validator_set, CrossCheck, WithdrawClient,
+    WithdrawTx, READ_ONLY_ENV,
+};
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
-struct Args {
-    #[arg(long, default_value = "sommelier-3")]
-    chain_id: String,
+#[command(
+    author,
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true,
+    disable_version_flag = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    // Without a subcommand the flags run `withdraw`
+    #[command(flatten)]
+    withdraw: WithdrawArgs,
+
+    /// Output format for results
+    #[arg(
+        long,
+        env = "WITHDRAW_COMMISSION_OUTPUT",
+        value_enum,
+        default_value = "text",
+        global = true
+    )]
+    output: OutputFormat,
+
+    /// Log format; `auto` logs JSON when RUNNING_IN_CONTAINER is set
+    #[arg(
+        long,
+        env = "WITHDRAW_COMMISSION_LOG_FORMAT",
+        value_enum,
+        default_value = "auto",
+        global = true
+    )]
+    log_format: logging::LogFormat,
+
+    /// Config file with defaults and named profiles [default: ~/.withdraw-commission/config.toml if it exists]
+    #[arg(long, env = "WITHDRAW_COMMISSION_CONFIG", global = true)]
+    config: Option<String>,
+
+    /// Use the settings of [profiles.<PROFILE>] from the config file
+    #[arg(long, env = "WITHDRAW_COMMISSION_PROFILE", global = true)]
+    profile: Option<String>,
+
+    /// Print version
+    #[arg(short = 'V', long)]
+    version: bool,
+
+    /// With --version, also print the commit, build date, features and dependency versions
+    #[arg(long, requires = "version")]
+    verbose: bool,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Withdraw validator commission (the default without a subcommand)
+    Withdraw(Box<WithdrawArgs>),
+    /// Query chain state without signing anything
+    #[command(subcommand)]
+    Query(QueryCommand),
+    /// Estimate the gas the withdrawal would use, without signing it
+    Simulate(SimulateArgs),
+    /// Inspect the signing key
+    #[command(subcommand)]
+    Keys(KeysCommand),
+    /// Inspect authz grants
+    #[command(subcommand)]
+    Authz(AuthzCommand),
+    /// Let a hot key withdraw commission through authz
+    #[command(subcommand)]
+    Grant(GrantCommand),
+    /// Let the signing key's account pay another key's fees through feegrant
+    #[command(subcommand)]
+    Feegrant(FeegrantCommand),
+    /// Withdraw for a validator whose operator account is a multisig
+    #[command(subcommand)]
+    Multisig(MultisigCommand),
+    /// Print the subcommand form of a legacy flat-flag command line
+    MigrateCmdline(MigrateArgs),
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum QueryCommand {
+    /// Show the validator's pending commission
+    Commission(CommissionArgs),
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum AuthzCommand {
+    /// List the grants where the signing key's account is granter or grantee
+    List(Box<AuthzListArgs>),
+    /// Revoke every grant from the signing key's account in one transaction
+    RevokeAll(Box<RevokeAllArgs>),
+    /// Withdraw the commission of many validators through their grants to the signing key, in one transaction
+    Withdraw(Box<AuthzWithdrawArgs>),
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum GrantCommand {
+    /// Grant the grantee permission to withdraw the signing key's commission
+    Create(GrantCreateArgs),
+    /// Revoke the grantee's permission to withdraw commission
+    Revoke(GrantArgs),
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum FeegrantCommand {
+    /// Grant the grantee an allowance to pay fees from the signing key's account
+    Create(FeegrantCreateArgs),
+    /// Revoke the grantee's fee allowance
+    Revoke(FeegrantArgs),
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum MultisigCommand {
+    /// Sign the withdrawal as one member of the multisig
+    Sign(Box<MultisigSignArgs>),
+    /// Assemble members' signatures into a transaction
+    Combine(MultisigCombineArgs),
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum KeysCommand {
+    /// Show the addresses and public key of the signing key
+    Show(Box<KeyArgs>),
+    /// Encrypt a hex key file with a passphrase
+    Encrypt(EncryptArgs),
+    /// Generate a new signing key and write it encrypted
+    Generate(GenerateArgs),
+    /// Check that the signing key belongs to the expected validator
+    Verify(Box<VerifyArgs>),
+    #[cfg(feature = "os-keychain")]
+    /// Store a hex private key in the OS keychain for --key-backend os
+    Import(KeysImportArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct EncryptArgs {
+    /// Hex key file to encrypt
+    #[arg(long)]
+    signing_key_path: String,
+
+    /// Where to write the encrypted key file; it must not exist yet
+    #[arg(long)]
+    out: String,
+
+    /// Read the new passphrase from this environment variable instead of prompting
+    #[arg(long, value_name = "VAR")]
+    passphrase_env: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct GenerateArgs {
+    /// Where to write the encrypted key file; it must not exist yet
+    #[arg(long)]
+    out: String,
+
+    /// Read the new passphrase from this environment variable instead of prompting
+    #[arg(long, value_name = "VAR")]
+    passphrase_env: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct VerifyArgs {
+    #[command(flatten)]
+    key: KeyArgs,
+
+    /// Validator operator address the signing key must derive
+    #[arg(long)]
+    expected_valoper: String,
+}
+
+#[cfg(feature = "os-keychain")]
+#[derive(clap::Args, Debug)]
+struct KeysImportArgs {
+    /// Name to store the key under, as passed to --key-name
+    name: String,
 
+    /// File holding the hex-encoded private key (`-` reads it from stdin)
     #[arg(long)]
     signing_key_path: String,
+}
+
+// Where the signing key comes from and how its addresses are encoded
+#[derive(clap::Args, Debug)]
+struct KeyArgs {
+    #[arg(long, env = "WITHDRAW_COMMISSION_SIGNING_KEY_PATH")]
+    signing_key_path: Option<String>,
+
+    /// Read the hex (or encrypted) private key from stdin
+    #[arg(long, conflicts_with = "signing_key_path")]
+    signing_key_stdin: bool,
+
+    /// Read the hex (or encrypted) private key from this environment variable
+    #[arg(
+        long,
+        env = "WITHDRAW_COMMISSION_SIGNING_KEY_ENV",
+        value_name = "VAR",
+        conflicts_with_all = ["signing_key_path", "signing_key_stdin"]
+    )]
+    signing_key_env: Option<String>,
+
+    /// Derive the signing key from a 24-word BIP-39 mnemonic in this file (`-` reads it from stdin)
+    #[arg(
+        long,
+        env = "WITHDRAW_COMMISSION_MNEMONIC_PATH",
+        conflicts_with_all = ["signing_key_path", "signing_key_stdin", "signing_key_env"]
+    )]
+    mnemonic_path: Option<String>,
+
+    /// HD derivation path for --mnemonic-path [default: m/44'/<coin type of --chain-preset, or 118>'/0'/0/0]
+    #[arg(long, env = "WITHDRAW_COMMISSION_HD_PATH", requires = "mnemonic_path")]
+    hd_path: Option<String>,
 
-    #[arg(long, default_value = "https://sommelier-rpc.polkachu.com:443")]
+    /// Read the passphrase of an encrypted private key from this environment variable instead of prompting
+    #[arg(long, env = "WITHDRAW_COMMISSION_PASSPHRASE_ENV", value_name = "VAR")]
+    passphrase_env: Option<String>,
+
+    /// Name of a key in a Cosmos SDK `file` keyring backend
+    #[arg(
+        long,
+        env = "WITHDRAW_COMMISSION_KEY_NAME",
+        conflicts_with_all = [
+            "signing_key_path",
+            "signing_key_stdin",
+            "signing_key_env",
+            "mnemonic_path"
+        ]
+    )]
+    key_name: Option<String>,
+
+    /// Directory of the `file` keyring backend [default: ~/.sommelier/keyring-file]
+    #[arg(long, env = "WITHDRAW_COMMISSION_KEYRING_DIR", requires = "key_name")]
+    keyring_dir: Option<String>,
+
+    /// File holding the keyring passphrase; prompted for when not given
+    #[arg(
+        long,
+        env = "WITHDRAW_COMMISSION_KEYRING_PASSPHRASE_PATH",
+        requires = "key_name"
+    )]
+    keyring_passphrase_path: Option<String>,
+
+    #[cfg(feature = "os-keychain")]
+    /// Where the signing key is read from; `os` reads the --key-name entry from the OS keychain
+    #[arg(
+        long,
+        env = "WITHDRAW_COMMISSION_KEY_BACKEND",
+        value_enum,
+        default_value = "file",
+        requires = "key_name"
+    )]
+    key_backend: os_keychain::KeyBackend,
+
+    /// Use the address prefixes of a known chain (sommelier, cosmoshub, osmosis, terra, secret, crypto-org)
+    #[arg(long, env = "WITHDRAW_COMMISSION_CHAIN_PRESET", value_parser = chain::parse_preset)]
+    chain_preset: Option<chain::ChainPreset>,
+
+    /// Bech32 prefix for account addresses [default: somm]
+    #[arg(long, env = "WITHDRAW_COMMISSION_ACCOUNT_PREFIX")]
+    account_prefix: Option<String>,
+
+    /// Bech32 prefix for validator operator addresses [default: <account prefix>valoper]
+    #[arg(long, env = "WITHDRAW_COMMISSION_VALOPER_PREFIX")]
+    valoper_prefix: Option<String>,
+}
+
+// Which chain to talk to, and how to reach its node
+#[derive(clap::Args, Debug)]
+struct NodeArgs {
+    #[arg(
+        long,
+        env = "WITHDRAW_COMMISSION_CHAIN_ID",
+        default_value = "sommelier-3"
+    )]
+    chain_id: String,
+
+    #[arg(
+        long,
+        env = "WITHDRAW_COMMISSION_RPC_URL",
+        default_value = "https://sommelier-rpc.polkachu.com:443"
+    )]
     rpc_url: String,
 
-    #[arg(long, default_value = "https://sommelier-grpc.polkachu.com:14190")]
+    #[arg(
+        long,
+        env = "WITHDRAW_COMMISSION_GRPC_URL",
+        default_value = "https://sommelier-grpc.polkachu.com:14190"
+    )]
     grpc_url: String,
 
-    #[arg(long, default_value = "usomm")]
+    /// Speak gRPC-web (HTTP/1.1) to --grpc-url, for providers that only expose a gRPC-web gateway
+    #[arg(long, env = "WITHDRAW_COMMISSION_GRPC_WEB")]
+    grpc_web: bool,
+
+    /// Discover the RPC and gRPC endpoints and chain id of a node on this host from its home directory [default: ~/.sommelier]
+    #[arg(
+        long,
+        env = "WITHDRAW_COMMISSION_LOCAL_NODE",
+        value_name = "HOME",
+        num_args = 0..=1
+    )]
+    local_node: Option<Option<String>>,
+
+    #[cfg(feature = "socks")]
+    /// Route RPC and gRPC connections through a SOCKS5 proxy such as Tor, e.g. socks5h://127.0.0.1:9050
+    #[arg(long, env = "WITHDRAW_COMMISSION_PROXY", value_parser = socks::parse_proxy)]
+    proxy: Option<String>,
+
+    /// Height to run all queries at [default: latest block]
+    #[arg(long, env = "WITHDRAW_COMMISSION_QUERY_HEIGHT")]
+    query_height: Option<u64>,
+}
+
+// How the transaction is built
+#[derive(clap::Args, Debug)]
+struct TxArgs {
+    #[arg(long, env = "WITHDRAW_COMMISSION_DENOM", default_value = "usomm")]
     denom: String,
 
-    #[arg(long, default_value = "0")]
+    /// Decimal places of the whole token of --denom, for showing amounts [default: from its prefix, e.g. 6 for u..., 18 for a...]
+    #[arg(long, env = "WITHDRAW_COMMISSION_DENOM_EXPONENT")]
+    denom_exponent: Option<u32>,
+
+    #[arg(long, env = "WITHDRAW_COMMISSION_TIMEOUT_HEIGHT", default_value = "0")]
     timeout_height: u64,
+
+    /// Override the static gas table, as <type url>=<gas> or base=<gas> (repeatable)
+    #[arg(
+        long = "gas-table",
+        env = "WITHDRAW_COMMISSION_GAS_TABLE",
+        value_parser = gas::parse_entry,
+        value_delimiter = ','
+    )]
+    gas_table: Vec<(String, u64)>,
+
+    /// Fee amount paid in --denom [default: 1000]
+    #[arg(long, env = "WITHDRAW_COMMISSION_FEE_AMOUNT")]
+    fee_amount: Option<u128>,
+
+    /// Fee amount and denom together, e.g. 2500usomm; the denom may differ from --denom
+    #[arg(long, env = "WITHDRAW_COMMISSION_FEES", value_parser = tx::parse_fees, conflicts_with = "fee_amount")]
+    fees: Option<(u128, String)>,
+
+    /// Gas limit, replacing the gas table's estimate
+    #[arg(long, env = "WITHDRAW_COMMISSION_GAS_LIMIT")]
+    gas_limit: Option<u64>,
+
+    /// Simulate the transaction before signing and pay this price per unit of gas, e.g. 0.025usomm
+    #[arg(
+        long,
+        env = "WITHDRAW_COMMISSION_GAS_PRICES",
+        value_parser = tx::parse_gas_prices,
+        conflicts_with_all = ["fee_amount", "fees", "gas_limit"]
+    )]
+    gas_prices: Option<(Decimal, String)>,
+
+    /// With --gas-prices, multiply the simulated gas by this factor for the gas limit
+    #[arg(
+        long,
+        env = "WITHDRAW_COMMISSION_GAS_ADJUSTMENT",
+        value_parser = str::parse::<Decimal>,
+        default_value = "1.3"
+    )]
+    gas_adjustment: Decimal,
+
+    /// Withdraw for this validator (operator or account address) through its authz grant to the signing key
+    #[arg(long, env = "WITHDRAW_COMMISSION_AUTHZ_GRANTER")]
+    authz_granter: Option<String>,
+
+    /// Pay the fee from this account's feegrant allowance to the signing key
+    #[arg(long, env = "WITHDRAW_COMMISSION_FEE_GRANTER")]
+    fee_granter: Option<String>,
+
+    /// Transaction memo, with {date}, {version}, {validator} and {chain_id} placeholders [default: Withdraw validator commission]
+    #[arg(long, env = "WITHDRAW_COMMISSION_MEMO")]
+    memo: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct MigrateArgs {
+    /// The legacy command line, optionally starting with the binary; put it after `--`
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    args: Vec<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct CommissionArgs {
+    /// Validator operator address to query [default: derived from the signing key]
+    #[arg(long)]
+    validator: Option<String>,
+
+    #[command(flatten)]
+    key: KeyArgs,
+
+    #[command(flatten)]
+    node: NodeArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct AuthzListArgs {
+    #[command(flatten)]
+    key: KeyArgs,
+
+    #[command(flatten)]
+    node: NodeArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct RevokeAllArgs {
+    /// Sign and broadcast the revocation; without it the grants are only listed
+    #[arg(long)]
+    yes: bool,
+
+    #[command(flatten)]
+    key: KeyArgs,
+
+    #[command(flatten)]
+    node: NodeArgs,
+
+    #[command(flatten)]
+    tx: TxArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct AuthzWithdrawArgs {
+    /// Validators to withdraw for, as an operator address, moniker:<pattern> with * and ? wildcards, or an http(s) URL listing operator addresses (repeatable)
+    #[arg(
+        long = "validators",
+        env = "WITHDRAW_COMMISSION_VALIDATORS",
+        value_parser = validator_set::parse_selector,
+        value_delimiter = ',',
+        required = true
+    )]
+    validators: Vec<validator_set::Selector>,
+
+    /// Sign and broadcast the withdrawal; without it the validators are only listed
+    #[arg(long)]
+    yes: bool,
+
+    #[command(flatten)]
+    key: KeyArgs,
+
+    #[command(flatten)]
+    node: NodeArgs,
+
+    #[command(flatten)]
+    tx: TxArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct GrantArgs {
+    /// Address of the hot key
+    #[arg(long)]
+    grantee: String,
+
+    #[command(flatten)]
+    key: KeyArgs,
+
+    #[command(flatten)]
+    node: NodeArgs,
+
+    #[command(flatten)]
+    tx: TxArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct GrantCreateArgs {
+    /// When the grant expires, in RFC 3339 (e.g. 2027-01-01T00:00:00Z) [default: never]
+    #[arg(long)]
+    expiration: Option<String>,
+
+    #[command(flatten)]
+    grant: GrantArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct FeegrantArgs {
+    /// Address of the key whose fees are paid
+    #[arg(long)]
+    grantee: String,
+
+    #[command(flatten)]
+    key: KeyArgs,
+
+    #[command(flatten)]
+    node: NodeArgs,
+
+    #[command(flatten)]
+    tx: TxArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct FeegrantCreateArgs {
+    /// Total the grantee may spend on fees, in --denom [default: no limit]
+    #[arg(long)]
+    spend_limit: Option<u128>,
+
+    /// When the allowance expires, in RFC 3339 (e.g. 2027-01-01T00:00:00Z) [default: never]
+    #[arg(long)]
+    expiration: Option<String>,
+
+    /// Make the allowance periodic, with periods of this many seconds
+    #[arg(long, requires = "period_spend_limit")]
+    period: Option<u64>,
+
+    /// What the grantee may spend on fees in each period, in --denom
+    #[arg(long, requires = "period")]
+    period_spend_limit: Option<u128>,
+
+    #[command(flatten)]
+    grant: FeegrantArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct MultisigSignArgs {
+    /// Multisig public key, as printed by `<daemon> keys show <name> --pubkey`
+    #[arg(long)]
+    multisig_pubkey: String,
+
+    /// Account number of the multisig account, to sign offline [default: queried from the node]
+    #[arg(long, requires = "sequence")]
+    account_number: Option<u64>,
+
+    /// Sequence of the multisig account, to sign offline [default: queried from the node]
+    #[arg(long, requires = "account_number")]
+    sequence: Option<u64>,
+
+    /// Where to write the partial signature
+    #[arg(long)]
+    out: String,
+
+    #[command(flatten)]
+    key: KeyArgs,
+
+    #[command(flatten)]
+    node: NodeArgs,
+
+    #[command(flatten)]
+    tx: TxArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct MultisigCombineArgs {
+    /// Multisig public key, as printed by `<daemon> keys show <name> --pubkey`
+    #[arg(long)]
+    multisig_pubkey: String,
+
+    /// Partial signatures written by `multisig sign`
+    #[arg(required = true)]
+    partials: Vec<String>,
+
+    /// Broadcast the transaction instead of printing it
+    #[arg(long)]
+    broadcast: bool,
+
+    #[command(flatten)]
+    node: NodeArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct SimulateArgs {
+    #[command(flatten)]
+    key: KeyArgs,
+
+    #[command(flatten)]
+    node: NodeArgs,
+
+    #[command(flatten)]
+    tx: TxArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct WithdrawArgs {
+    #[command(flatten)]
+    key: KeyArgs,
+
+    #[command(flatten)]
+    node: NodeArgs,
+
+    #[command(flatten)]
+    tx: TxArgs,
+
+    /// Print the transaction that would be broadcast instead of broadcasting it
+    #[arg(long, env = "WITHDRAW_COMMISSION_PLAN")]
+    plan: bool,
+
+    /// Diff the plan against a previously saved plan file
+    #[arg(long, requires = "plan")]
+    plan_baseline: Option<String>,
+
+    /// Write the plan to a file so it can be used as a future baseline
+    #[arg(long, requires = "plan")]
+    plan_out: Option<String>,
+
+    /// File that keeps the plan of the last successful withdrawal; --plan diffs against it unless --plan-baseline is given
+    #[arg(long, env = "WITHDRAW_COMMISSION_PLAN_STATE")]
+    plan_state: Option<String>,
+
+    /// Withdraw even if the validator is jailed or was recently slashed
+    #[arg(long, env = "WITHDRAW_COMMISSION_IGNORE_JAILED")]
+    ignore_jailed: bool,
+
+    /// Also withdraw the validator account's delegation rewards from every validator, in the same transaction
+    #[arg(long, env = "WITHDRAW_COMMISSION_INCLUDE_REWARDS")]
+    include_rewards: bool,
+
+    /// Delegate the claimed rewards across validators by weight, as <valoper>:<weight> (repeatable)
+    #[arg(
+        long = "delegate-rewards",
+        env = "WITHDRAW_COMMISSION_DELEGATE_REWARDS",
+        value_parser = split::parse_weight,
+        value_delimiter = ',',
+        requires = "include_rewards"
+    )]
+    delegate_rewards: Vec<(String, u64)>,
+
+    /// Delegate this percentage of the pending commission back to the validator, in the same transaction
+    #[arg(long, env = "WITHDRAW_COMMISSION_COMPOUND", value_parser = commission::parse_percent)]
+    compound: Option<Decimal>,
+
+    /// Send this percentage of the pending commission to an address, as <address>:<percent>% (repeatable)
+    #[arg(
+        long,
+        env = "WITHDRAW_COMMISSION_PAYOUT",
+        value_parser = split::parse_percent_share,
+        value_delimiter = ','
+    )]
+    payout: Vec<(String, Decimal)>,
+
+    /// Send the commission left after --compound and --payout to one of these addresses, taking turns by weight, as <address>[:<weight>] (repeatable)
+    #[arg(
+        long = "rotate-payout",
+        env = "WITHDRAW_COMMISSION_ROTATE_PAYOUT",
+        value_parser = split::parse_rotation,
+        value_delimiter = ','
+    )]
+    rotate_payout: Vec<(String, u64)>,
+
+    /// IBC-transfer the commission left after --compound and --payout to another chain, as <channel>:<receiver>
+    #[arg(
+        long,
+        env = "WITHDRAW_COMMISSION_IBC_TRANSFER",
+        value_parser = ibc::parse_destination,
+        conflicts_with = "rotate_payout"
+    )]
+    ibc_transfer: Option<(String, String)>,
+
+    /// What to do with the base units left over when shares are rounded down
+    #[arg(
+        long,
+        env = "WITHDRAW_COMMISSION_DUST",
+        value_enum,
+        default_value = "leave"
+    )]
+    dust: split::DustPolicy,
+
+    /// With --dust accumulate, add the leftover to the first share once it reaches this many base units
+    #[arg(
+        long,
+        env = "WITHDRAW_COMMISSION_DUST_THRESHOLD",
+        default_value = "1000"
+    )]
+    dust_threshold: u128,
+
+    /// With --dust accumulate, file that carries the leftover between runs
+    #[arg(
+        long,
+        env = "WITHDRAW_COMMISSION_DUST_FILE",
+        required_if_eq("dust", "accumulate")
+    )]
+    dust_file: Option<String>,
+
+    /// Skip the run if the signing key withdrew commission less than this many seconds ago
+    #[arg(long, env = "WITHDRAW_COMMISSION_MIN_INTERVAL")]
+    min_interval: Option<u64>,
+
+    /// How many blocks back to look for slashing events
+    #[arg(
+        long,
+        env = "WITHDRAW_COMMISSION_SLASH_LOOKBACK_BLOCKS",
+        default_value = "100000"
+    )]
+    slash_lookback_blocks: u64,
+
+    #[cfg(feature = "callback")]
+    /// URL that receives the result of the run
+    #[arg(long, env = "WITHDRAW_COMMISSION_CALLBACK_URL")]
+    callback_url: Option<String>,
+
+    #[cfg(feature = "callback")]
+    /// HTTP method used for the callback
+    #[arg(long, env = "WITHDRAW_COMMISSION_CALLBACK_METHOD", default_value = "POST", value_parser = callback::parse_method)]
+    callback_method: reqwest::Method,
+
+    #[cfg(feature = "callback")]
+    /// Extra callback header as "Name: value" (repeatable)
+    #[arg(long = "callback-header", env = "WITHDRAW_COMMISSION_CALLBACK_HEADER", value_parser = callback::parse_header)]
+    callback_headers: Vec<(String, String)>,
+
+    #[cfg(feature = "callback")]
+    /// Callback body template with {chain_id}, {validator}, {status}, {tx_hash}, {height}, {code}, {amount}, {log}, {failure_class}, {remediation} and {doc_link} placeholders; defaults to a JSON object
+    #[arg(long, env = "WITHDRAW_COMMISSION_CALLBACK_BODY")]
+    callback_body: Option<String>,
+
+    #[cfg(feature = "grafana")]
+    /// Grafana base URL to post an annotation to when a withdrawal lands
+    #[arg(long, env = "WITHDRAW_COMMISSION_GRAFANA_URL")]
+    grafana_url: Option<String>,
+
+    #[cfg(feature = "grafana")]
+    /// Path to a file containing a Grafana API token
+    #[arg(
+        long,
+        env = "WITHDRAW_COMMISSION_GRAFANA_TOKEN_PATH",
+        requires = "grafana_url"
+    )]
+    grafana_token_path: Option<String>,
+
+    #[cfg(feature = "pushgateway")]
+    /// Prometheus Pushgateway base URL to push the run's metrics to
+    #[arg(long, env = "WITHDRAW_COMMISSION_PUSHGATEWAY_URL")]
+    pushgateway_url: Option<String>,
+
+    #[cfg(feature = "statsd")]
+    /// StatsD or DogStatsD agent (host:port) to send the run's metrics to
+    #[arg(long, env = "WITHDRAW_COMMISSION_STATSD_ADDR")]
+    statsd_addr: Option<String>,
+
+    #[cfg(feature = "statsd")]
+    /// Extra tag sent with every StatsD metric, as key:value (repeatable)
+    #[arg(
+        long = "statsd-tag",
+        env = "WITHDRAW_COMMISSION_STATSD_TAG",
+        value_delimiter = ',',
+        requires = "statsd_addr"
+    )]
+    statsd_tags: Vec<String>,
+
+    /// Repeat the account and pending-commission queries against this second gRPC endpoint and compare the results
+    #[arg(long, env = "WITHDRAW_COMMISSION_CROSS_CHECK_GRPC_URL")]
+    cross_check_grpc_url: Option<String>,
+
+    /// How far pending commission may differ between the endpoints, in percent
+    #[arg(
+        long,
+        env = "WITHDRAW_COMMISSION_CROSS_CHECK_TOLERANCE",
+        default_value = "0",
+        value_parser = commission::parse_percent,
+        requires = "cross_check_grpc_url"
+    )]
+    cross_check_tolerance: Decimal,
+
+    /// What to do when the endpoints disagree
+    #[arg(
+        long,
+        env = "WITHDRAW_COMMISSION_ON_CROSS_CHECK_MISMATCH",
+        value_enum,
+        default_value = "abort"
+    )]
+    on_cross_check_mismatch: cross_check::Mismatch,
+
+    /// Do everything except signing and broadcasting
+    // Read the break-glass variable the way read_only_env does, so any value
+    // but "", "0" or "false" turns it on
+    #[arg(
+        long,
+        env = "WITHDRAW_COMMISSION_READ_ONLY",
+        value_parser = clap::builder::FalseyValueParser::new()
+    )]
+    read_only: bool,
+
+    /// Skip checking the node's SDK and CometBFT versions against the tested matrix
+    #[arg(long, env = "WITHDRAW_COMMISSION_SKIP_VERSION_CHECK")]
+    skip_version_check: bool,
+
+    /// How to treat "tx already exists in cache" broadcast errors
+    #[arg(
+        long,
+        env = "WITHDRAW_COMMISSION_ON_ALREADY_IN_CACHE",
+        value_enum,
+        default_value = "confirm"
+    )]
+    on_already_in_cache: broadcast::AlreadyInCache,
+
+    /// Seconds to wait for an already-submitted transaction to be committed
+    #[arg(
+        long,
+        env = "WITHDRAW_COMMISSION_CONFIRM_TIMEOUT",
+        default_value = "60"
+    )]
+    confirm_timeout: u64,
+
+    /// Only check that the endpoints are reachable and exit with 0 (healthy) or 1
+    #[arg(long, env = "WITHDRAW_COMMISSION_HEALTHCHECK")]
+    healthcheck: bool,
+
+    /// Print a scheduler definition that runs this command line and exit
+    #[arg(long, value_enum)]
+    generate_unit: Option<unit::UnitFormat>,
+
+    /// Schedule for --generate-unit (systemd OnCalendar or Nomad cron syntax)
+    #[arg(long, requires = "generate_unit")]
+    unit_schedule: Option<String>,
+}
+
+/// How results are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum OutputFormat {
+    /// Human-readable text; for withdrawals, the raw broadcast response
+    Text,
+    /// A JSON object; for withdrawals, the run outcome including decoded events
+    Json,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Configure logging for stdout
-    env_logger::Builder::from_default_env()
-        .filter_level(log::LevelFilter::Info)
-        .format_timestamp(None)
-        .format_module_path(false)
-        .init();
+    let raw_args: Vec<String> = std::env::args().collect();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if cli.version {
+        if cli.verbose {
+            println!("{}", build_info::verbose_version());
+        } else {
+            print!("{}", Cli::command().render_version());
+        }
+        return Ok(());
+    }
+    logging::init(cli.log_format);
+    let output = cli.output;
+    let settings = config_settings(cli.config.as_deref(), cli.profile.as_deref())?;
+    let matches = subcommand_matches(&matches);
+
+    match cli.command {
+        None => {
+            log::warn!(
+                "Running without a subcommand is deprecated; run `withdraw-commission migrate-cmdline -- <flags>` for the `withdraw` form"
+            );
+            withdraw(cli.withdraw, matches, &settings, output, &raw_args).await
+        }
+        Some(Command::Withdraw(args)) => {
+            withdraw(*args, matches, &settings, output, &raw_args).await
+        }
+        Some(Command::Query(QueryCommand::Commission(mut args))) => {
+            apply_key_config(&mut args.key, &settings);
+            apply_node_config(&mut args.node, &settings, matches);
+            apply_local_node(&mut args.node, matches)?;
+            query_commission(args, output).await
+        }
+        Some(Command::Simulate(mut args)) => {
+            apply_key_config(&mut args.key, &settings);
+            apply_node_config(&mut args.node, &settings, matches);
+            apply_tx_config(&mut args.tx, &settings, matches);
+            apply_local_node(&mut args.node, matches)?;
+            simulate(args, output).await
+        }
+        Some(Command::Keys(KeysCommand::Show(mut args))) => {
+            apply_key_config(&mut args, &settings);
+            show_key(*args, output)
+        }
+        Some(Command::Keys(KeysCommand::Encrypt(args))) => encrypt_key(args),
+        Some(Command::Keys(KeysCommand::Generate(args))) => generate_key(args, output),
+        Some(Command::Keys(KeysCommand::Verify(mut args))) => {
+            apply_key_config(&mut args.key, &settings);
+            verify_key(*args, output)
+        }
+        #[cfg(feature = "os-keychain")]
+        Some(Command::Keys(KeysCommand::Import(args))) => import_key(args),
+        Some(Command::Authz(AuthzCommand::List(mut args))) => {
+            apply_key_config(&mut args.key, &settings);
+            apply_node_config(&mut args.node, &settings, matches);
+            apply_local_node(&mut args.node, matches)?;
+            list_grants(*args, output).await
+        }
+        Some(Command::Authz(AuthzCommand::RevokeAll(mut args))) => {
+            apply_key_config(&mut args.key, &settings);
+            apply_node_config(&mut args.node, &settings, matches);
+            apply_tx_config(&mut args.tx, &settings, matches);
+            apply_local_node(&mut args.node, matches)?;
+            revoke_all(*args, output).await
+        }
+        Some(Command::Authz(AuthzCommand::Withdraw(mut args))) => {
+            apply_key_config(&mut args.key, &settings);
+            apply_node_config(&mut args.node, &settings, matches);
+            apply_tx_config(&mut args.tx, &settings, matches);
+            apply_local_node(&mut args.node, matches)?;
+            authz_withdraw(*args, output).await
+        }
+        Some(Command::Grant(mut command)) => {
+            let args = match &mut command {
+                GrantCommand::Create(args) => &mut args.grant,
+                GrantCommand::Revoke(args) => args,
+            };
+            apply_key_config(&mut args.key, &settings);
+            apply_node_config(&mut args.node, &settings, matches);
+            apply_tx_config(&mut args.tx, &settings, matches);
+            apply_local_node(&mut args.node, matches)?;
+            grant(command, output).await
+        }
+        Some(Command::Feegrant(mut command)) => {
+            let args = match &mut command {
+                FeegrantCommand::Create(args) => &mut args.grant,
+                FeegrantCommand::Revoke(args) => args,
+            };
+            apply_key_config(&mut args.key, &settings);
+            apply_node_config(&mut args.node, &settings, matches);
+            apply_tx_config(&mut args.tx, &settings, matches);
+            apply_local_node(&mut args.node, matches)?;
+            feegrant(command, output).await
+        }
+        Some(Command::Multisig(MultisigCommand::Sign(mut args))) => {
+            apply_key_config(&mut args.key, &settings);
+            apply_node_config(&mut args.node, &settings, matches);
+            apply_tx_config(&mut args.tx, &settings, matches);
+            apply_local_node(&mut args.node, matches)?;
+            multisig_sign(*args).await
+        }
+        Some(Command::Multisig(MultisigCommand::Combine(mut args))) => {
+            apply_node_config(&mut args.node, &settings, matches);
+            apply_local_node(&mut args.node, matches)?;
+            multisig_combine(args, output).await
+        }
+        Some(Command::MigrateCmdline(args)) => migrate_cmdline(args),
+    }
+}
+
+/// Names of the subcommands, including clap's `help`.
+fn subcommand_names() -> Vec<String> {
+    Cli::command()
+        .get_subcommands()
+        .map(|subcommand| subcommand.get_name().to_string())
+        .chain(std::iter::once("help".to_string()))
+        .collect()
+}
+
+/// Map a command line onto its subcommand form, leaving it unchanged if it
+/// already has one.
+fn to_subcommand(args: &[String]) -> Vec<String> {
+    let names = subcommand_names();
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    migrate::to_subcommand(args, &names)
+}
+
+fn migrate_cmdline(args: MigrateArgs) -> Result<()> {
+    let (binary, args) = migrate::split_binary(&args.args);
+    let binary = binary.map(String::as_str).unwrap_or("withdraw-commission");
+    let migrated = to_subcommand(args);
+
+    // Only print command lines that still parse
+    let command_line = std::iter::once(binary.to_string()).chain(migrated.iter().cloned());
+    if let Err(e) = Cli::try_parse_from(command_line) {
+        e.exit();
+    }
+
+    println!(
+        "{}",
+        std::iter::once(binary)
+            .chain(migrated.iter().map(String::as_str))
+            .map(migrate::shell_quote)
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+
+    Ok(())
+}
+
+/// Load the settings from `--config`, or from the default config file if it
+/// exists, with `--profile` applied.
+fn config_settings(path: Option<&str>, profile: Option<&str>) -> Result<config::Settings> {
+    let config = match path {
+        Some(path) => config::Config::load(path.as_ref())?,
+        None => {
+            let path = config::default_path();
+            if path.exists() {
+                config::Config::load(&path)?
+            } else {
+                config::Config::default()
+            }
+        }
+    };
+
+    config.settings(profile)
+}
+
+/// Whether the flag `id` was given on the command line or in its
+/// `WITHDRAW_COMMISSION_*` environment variable.
+fn explicitly_set(matches: &ArgMatches, id: &str) -> bool {
+    matches!(
+        matches.value_source(id),
+        Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+    )
+}
+
+/// Fill in key settings from the config file unless set explicitly.
+fn apply_key_config(key: &mut KeyArgs, settings: &config::Settings) {
+    if key.signing_key_path.is_none()
+        && !key.signing_key_stdin
+        && key.signing_key_env.is_none()
+        && key.mnemonic_path.is_none()
+        && key.key_name.is_none()
+    {
+        key.signing_key_path = settings.signing_key_path.clone();
+    }
+}
+
+/// Fill in node settings from the config file unless set explicitly.
+fn apply_node_config(node: &mut NodeArgs, settings: &config::Settings, matches: &ArgMatches) {
+    if let Some(chain_id) = &settings.chain_id {
+        if !explicitly_set(matches, "chain_id") {
+            node.chain_id = chain_id.clone();
+        }
+    }
+    if let Some(rpc_url) = &settings.rpc_url {
+        if !explicitly_set(matches, "rpc_url") {
+            node.rpc_url = rpc_url.clone();
+        }
+    }
+    if let Some(grpc_url) = &settings.grpc_url {
+        if !explicitly_set(matches, "grpc_url") {
+            node.grpc_url = grpc_url.clone();
+        }
+    }
+}
+
+/// Fill in transaction settings from the config file unless set explicitly.
+fn apply_tx_config(tx: &mut TxArgs, settings: &config::Settings, matches: &ArgMatches) {
+    if let Some(denom) = &settings.denom {
+        if !explicitly_set(matches, "denom") {
+            tx.denom = denom.clone();
+        }
+    }
+    if tx.denom_exponent.is_none() {
+        tx.denom_exponent = settings.denom_exponent;
+    }
+    if let Some(timeout_height) = settings.timeout_height {
+        if !explicitly_set(matches, "timeout_height") {
+            tx.timeout_height = timeout_height;
+        }
+    }
+    if tx.fee_amount.is_none() && tx.fees.is_none() && tx.gas_prices.is_none() {
+        tx.fee_amount = settings.fee_amount.map(u128::from);
+    }
+    if tx.gas_limit.is_none() && tx.gas_prices.is_none() {
+        tx.gas_limit = settings.gas_limit;
+    }
+    if tx.authz_granter.is_none() {
+        tx.authz_granter = settings.authz_granter.clone();
+    }
+    if tx.fee_granter.is_none() {
+        tx.fee_granter = settings.fee_granter.clone();
+    }
+    if tx.memo.is_none() {
+        tx.memo = settings.memo.clone();
+    }
+}
+
+/// The matches of the innermost subcommand, where its flags live.
+fn subcommand_matches(matches: &ArgMatches) -> &ArgMatches {
+    match matches.subcommand() {
+        Some((_, matches)) => subcommand_matches(matches),
+        None => matches,
+    }
+}
+
+/// Fill in endpoints and chain id discovered by `--local-node`. Values given
+/// on the command line or in the environment win over the discovered ones.
+fn apply_local_node(node: &mut NodeArgs, matches: &ArgMatches) -> Result<()> {
+    let Some(home) = node.local_node.clone() else {
+        return Ok(());
+    };
+    let home = local_node::home(home.as_deref());
+    let discovered = local_node::discover(&home)?;
+    log::info!("Using local node at {}", home.display());
+
+    if !explicitly_set(matches, "rpc_url") {
+        node.rpc_url = discovered.rpc_url;
+    }
+    if !explicitly_set(matches, "grpc_url") {
+        node.grpc_url = discovered.grpc_url;
+    }
+    if let Some(chain_id) = discovered
+        .chain_id
+        .filter(|_| !explicitly_set(matches, "chain_id"))
+    {
+        node.chain_id = chain_id;
+    }
+
+    Ok(())
+}
+
+async fn withdraw(
+    mut args: WithdrawArgs,
+    matches: &ArgMatches,
+    settings: &config::Settings,
+    output: OutputFormat,
+    raw_args: &[String],
+) -> Result<()> {
+    apply_key_config(&mut args.key, settings);
+    apply_node_config(&mut args.node, settings, matches);
+    apply_tx_config(&mut args.tx, settings, matches);
+    apply_local_node(&mut args.node, matches)?;
+    args.min_interval = args.min_interval.or(settings.min_interval);
+
+    if let Some(format) = args.generate_unit {
+        let binary = std::env::current_exe()?;
+        let schedule = args
+            .unit_schedule
+            .as_deref()
+            .unwrap_or(format.default_schedule());
+        print!(
+            "{}",
+            unit::render(
+                format,
+                &binary.to_string_lossy(),
+                &unit::command_args(&to_subcommand(&raw_args[1..])),
+                schedule
+            )
+        );
+        return Ok(());
+    }
+
+    if args.healthcheck {
+        return container::healthcheck(
+            &args.node.grpc_url,
+            args.node.grpc_web,
+            &args.node.rpc_url,
+            proxy(&args.node),
+        )
+        .await;
+    }
 
     log::info!("Starting withdraw-commission");
-    let args = Args::parse();
 
-    // Read private key from file
-    let private_key = match fs::read_to_string(&args.signing_key_path) {
-        Ok(key) => key.trim().to_string(),
-        Err(e) => {
-            log::error!("Failed to read private key from file: {}", e);
+    // Stop promptly on SIGTERM/SIGINT, which matters when running as PID 1
+    tokio::select! {
+        result = run(args, output) => result,
+        code = container::shutdown_signal() => std::process::exit(code?),
+    }
+}
+
+/// Load the signing key and describe the withdrawal transaction.
+fn withdraw_tx(key: &KeyArgs, node: &NodeArgs, tx: &TxArgs) -> Result<WithdrawTx> {
+    let mut withdraw_tx = WithdrawTx::new(signing_key(key)?, &node.chain_id);
+    (withdraw_tx.account_prefix, withdraw_tx.valoper_prefix) = prefixes(key);
+    withdraw_tx.denom = tx.denom.clone();
+    withdraw_tx.denom_exponent = tx
+        .denom_exponent
+        .unwrap_or_else(|| decimal::default_exponent(&tx.denom));
+    if let Some(fee_amount) = tx.fee_amount {
+        withdraw_tx.fee_amount = fee_amount;
+    }
+    if let Some((fee_amount, fee_denom)) = &tx.fees {
+        withdraw_tx.fee_amount = *fee_amount;
+        withdraw_tx.fee_denom = Some(fee_denom.clone());
+    }
+    withdraw_tx.gas_limit = tx.gas_limit;
+    withdraw_tx.gas_price = tx.gas_prices.clone();
+    withdraw_tx.gas_adjustment = tx.gas_adjustment;
+    withdraw_tx.timeout_height = tx.timeout_height;
+    for (key, gas) in &tx.gas_table {
+        withdraw_tx.gas_table.set(key, *gas);
+    }
+    if let Some(granter) = &tx.authz_granter {
+        withdraw_tx.authz_granter = match granter.parse() {
+            Ok(granter) => Some(granter),
+            Err(e) => {
+                log::error!("Failed to parse authz granter: {}", e);
+                return Err(eyre::Report::msg(format!(
+                    "Failed to parse authz granter {}: {}",
+                    granter, e
+                )));
+            }
+        };
+    }
+    if let Some(granter) = &tx.fee_granter {
+        withdraw_tx.fee_granter = match granter.parse() {
+            Ok(granter) => Some(granter),
+            Err(e) => {
+                log::error!("Failed to parse fee granter: {}", e);
+                return Err(eyre::Report::msg(format!(
+                    "Failed to parse fee granter {}: {}",
+                    granter, e
+                )));
+            }
+        };
+    }
+    if let Some(memo) = &tx.memo {
+        withdraw_tx.memo = tx::render_memo(
+            memo,
+            withdraw_tx.operator_address()?.as_ref(),
+            &node.chain_id,
+            Time::now(),
+        );
+    }
+
+    Ok(withdraw_tx)
+}
+
+fn signing_key(key: &KeyArgs) -> Result<cosmrs::crypto::secp256k1::SigningKey> {
+    #[cfg(feature = "os-keychain")]
+    if let (os_keychain::KeyBackend::Os, Some(key_name)) = (key.key_backend, &key.key_name) {
+        return os_keychain::load_key(key_name);
+    }
+    if let Some(mnemonic_path) = &key.mnemonic_path {
+        let hd_path = chain::hd_path(key.chain_preset.as_ref(), key.hd_path.as_deref());
+        return keys::load_mnemonic_key(mnemonic_path, &hd_path);
+    }
+    if let Some(key_name) = &key.key_name {
+        let keyring_dir = match &key.keyring_dir {
+            Some(dir) => dir.into(),
+            None => local_node::home(None).join("keyring-file"),
+        };
+        return keyring::load_keyring_key(keyring_dir, key_name, &keyring_passphrase(key)?);
+    }
+    let ask_passphrase = || passphrase(key.passphrase_env.as_deref(), "Enter key passphrase: ");
+    if key.signing_key_stdin {
+        let mut private_key = String::new();
+        if let Err(e) = std::io::stdin().read_to_string(&mut private_key) {
+            log::error!("Failed to read private key from stdin: {}", e);
             return Err(eyre::Report::msg(format!(
-                "Failed to read private key from file: {}",
+                "Failed to read private key from stdin: {}",
                 e
             )));
         }
+        return keys::parse_key(&private_key, ask_passphrase);
+    }
+    if let Some(env) = &key.signing_key_env {
+        return match std::env::var(env) {
+            Ok(private_key) => keys::parse_key(&private_key, ask_passphrase),
+            Err(e) => Err(eyre::Report::msg(format!(
+                "Failed to read private key from {}: {}",
+                env, e
+            ))),
+        };
+    }
+    let Some(signing_key_path) = &key.signing_key_path else {
+        return Err(eyre::Report::msg(
+            "--signing-key-path, --signing-key-stdin, --signing-key-env, --mnemonic-path or --key-name is required",
+        ));
     };
 
-    // Create the signing key from the private key
-    let decoded_private_key = match hex::decode(&private_key) {
-        Ok(decoded) => decoded,
+    keys::load_key_file(signing_key_path, ask_passphrase)
+}
+
+/// A passphrase from the environment variable `env`, or prompted for on the
+/// terminal.
+fn passphrase(env: Option<&str>, prompt: &str) -> Result<String> {
+    if let Some(env) = env {
+        return match std::env::var(env) {
+            Ok(passphrase) => Ok(passphrase),
+            Err(e) => Err(eyre::Report::msg(format!(
+                "Failed to read passphrase from {}: {}",
+                env, e
+            ))),
+        };
+    }
+    match rpassword::prompt_password(prompt) {
+        Ok(passphrase) => Ok(passphrase),
         Err(e) => {
-            log::error!("Failed to decode private key: {}", e);
-            return Err(eyre::Report::msg(format!(
-                "Failed to decode private key: {}",
+            log::error!("Failed to read passphrase: {}", e);
+            Err(eyre::Report::msg(format!(
+                "Failed to read passphrase: {}",
                 e
-            )));
+            )))
         }
+    }
+}
+
+/// Store a hex private key in the OS keychain.
+#[cfg(feature = "os-keychain")]
+fn import_key(args: KeysImportArgs) -> Result<()> {
+    let mut private_key = String::new();
+    let read = if args.signing_key_path == "-" {
+        std::io::Read::read_to_string(&mut std::io::stdin(), &mut private_key).map(|_| ())
+    } else {
+        fs::read_to_string(&args.signing_key_path).map(|contents| private_key = contents)
+    };
+    if let Err(e) = read {
+        log::error!("Failed to read private key: {}", e);
+        return Err(eyre::Report::msg(format!(
+            "Failed to read private key: {}",
+            e
+        )));
+    }
+    os_keychain::store_key(&args.name, &private_key)?;
+    log::info!(
+        "Stored the key as {}; sign with --key-backend os --key-name {}",
+        args.name,
+        args.name
+    );
+
+    Ok(())
+}
+
+/// The keyring passphrase, from --keyring-passphrase-path or the terminal.
+fn keyring_passphrase(key: &KeyArgs) -> Result<String> {
+    let passphrase = match &key.keyring_passphrase_path {
+        Some(path) => fs::read_to_string(path).map(|passphrase| passphrase.trim_end().to_string()),
+        None => rpassword::prompt_password("Enter keyring passphrase: "),
     };
-    let signing_key = match SigningKey::from_slice(&decoded_private_key) {
-        Ok(key) => key,
+    match passphrase {
+        Ok(passphrase) => Ok(passphrase),
         Err(e) => {
-            log::error!("Failed to create signing key: {}", e);
-            return Err(eyre::Report::msg(format!(
-                "Failed to create signing key: {}",
+            log::error!("Failed to read keyring passphrase: {}", e);
+            Err(eyre::Report::msg(format!(
+                "Failed to read keyring passphrase: {}",
                 e
-            )));
+            )))
+        }
+    }
+}
+
+fn prefixes(key: &KeyArgs) -> (String, String) {
+    chain::prefixes(
+        key.chain_preset.as_ref(),
+        key.account_prefix.as_deref(),
+        key.valoper_prefix.as_deref(),
+    )
+}
+
+async fn query_commission(args: CommissionArgs, output: OutputFormat) -> Result<()> {
+    let validator = match args.validator {
+        Some(validator) => validator,
+        None => {
+            let (_, valoper_prefix) = prefixes(&args.key);
+            match signing_key(&args.key)?
+                .public_key()
+                .account_id(&valoper_prefix)
+            {
+                Ok(address) => address.to_string(),
+                Err(e) => {
+                    log::error!("Failed to get validator operator address: {}", e);
+                    return Err(eyre::Report::msg(format!(
+                        "Failed to get validator operator address: {}",
+                        e
+                    )));
+                }
+            }
         }
     };
 
-    // Derive the validator address from the private key
-    let validator_address = match signing_key.public_key().account_id("somm") {
-        Ok(validator_address) => validator_address,
+    let channel =
+        endpoint::connect_grpc(&args.node.grpc_url, args.node.grpc_web, proxy(&args.node)).await?;
+    let height = match args.node.query_height {
+        Some(height) => height,
+        None => endpoint::latest_height(channel.clone()).await?,
+    };
+    let coins = commission::pending_commission(channel, &validator, height).await?;
+
+    match output {
+        OutputFormat::Text => {
+            println!("Pending commission of {} at height {}:", validator, height);
+            for coin in &coins {
+                println!(
+                    "  {}{}",
+                    commission::format_amount(&coin.amount),
+                    coin.denom
+                );
+            }
+        }
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::json!({
+                "validator": validator,
+                "height": height,
+                "commission": coins
+                    .iter()
+                    .map(|coin| serde_json::json!({
+                        "denom": coin.denom,
+                        "amount": commission::format_amount(&coin.amount),
+                    }))
+                    .collect::<Vec<_>>(),
+            })
+        ),
+    }
+
+    Ok(())
+}
+
+async fn list_grants(args: AuthzListArgs, output: OutputFormat) -> Result<()> {
+    let (account_prefix, _) = prefixes(&args.key);
+    let address = match signing_key(&args.key)?
+        .public_key()
+        .account_id(&account_prefix)
+    {
+        Ok(address) => address.to_string(),
         Err(e) => {
             log::error!("Failed to get validator address: {}", e);
             return Err(eyre::Report::msg(format!(
@@ -91,159 +1423,1539 @@ async fn main() -> Result<()> {
             )));
         }
     };
-    let validator_operator_address = match signing_key.public_key().account_id("sommvaloper") {
-        Ok(validator_operator_address) => validator_operator_address,
-        Err(e) => {
-            log::error!("Failed to get validator operator address: {}", e);
-            return Err(eyre::Report::msg(format!(
-                "Failed to get validator operator address: {}",
-                e
-            )));
-        }
+
+    let channel =
+        endpoint::connect_grpc(&args.node.grpc_url, args.node.grpc_web, proxy(&args.node)).await?;
+    let height = match args.node.query_height {
+        Some(height) => height,
+        None => endpoint::latest_height(channel.clone()).await?,
     };
+    let grants = authz::grants(channel, &address, height).await?;
+    for grant in grants.iter().filter(|grant| grant.broad) {
+        log::warn!(
+            "{} may execute any {} on behalf of {}",
+            grant.grantee,
+            grant.msg.as_deref().unwrap_or_default(),
+            grant.granter
+        );
+    }
 
-    // log addresses
-    log::info!("Validator address: {}", validator_address);
-    log::info!("Validator operator address: {}", validator_operator_address);
+    match output {
+        OutputFormat::Text => {
+            println!("Authz grants of {} at height {}:", address, height);
+            for grant in &grants {
+                println!(
+                    "  {} -> {}: {}, expires {}{}",
+                    grant.granter,
+                    grant.grantee,
+                    grant.msg.as_deref().unwrap_or(&grant.authorization),
+                    grant.expiration.as_deref().unwrap_or("never"),
+                    if grant.broad { " (broad)" } else { "" }
+                );
+            }
+        }
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::json!({
+                "address": address,
+                "height": height,
+                "grants": grants,
+            })
+        ),
+    }
+
+    Ok(())
+}
 
-    // Create the message
-    let msg = MsgWithdrawValidatorCommission {
-        validator_address: validator_operator_address,
+async fn revoke_all(args: RevokeAllArgs, output: OutputFormat) -> Result<()> {
+    let withdraw_tx = withdraw_tx(&args.key, &args.node, &args.tx)?;
+    let address = withdraw_tx.account_address()?.to_string();
+    let channel =
+        endpoint::connect_grpc(&args.node.grpc_url, args.node.grpc_web, proxy(&args.node)).await?;
+    let height = match args.node.query_height {
+        Some(height) => height,
+        None => endpoint::latest_height(channel.clone()).await?,
     };
 
-    // Create the transaction body
-    let any = match msg.to_any() {
-        Ok(any) => any,
-        Err(e) => {
-            log::error!("Failed to create any: {}", e);
-            return Err(eyre::Report::msg(format!("Failed to create any: {}", e)));
+    let grants: Vec<_> = authz::grants(channel.clone(), &address, height)
+        .await?
+        .into_iter()
+        .filter(|grant| grant.granter == address)
+        .collect();
+    let mut msgs = Vec::new();
+    let mut revoking = Vec::new();
+    for grant in &grants {
+        match authz::revoke_msg(grant) {
+            Some(msg) => {
+                log::info!(
+                    "Revoking {} from {}",
+                    grant.msg.as_deref().unwrap_or_default(),
+                    grant.grantee
+                );
+                msgs.push(msg);
+                revoking.push(grant);
+            }
+            None => log::error!(
+                "Cannot revoke {} granted to {}: unknown message type; revoke it with the chain's CLI",
+                grant.authorization,
+                grant.grantee
+            ),
         }
+    }
+    if msgs.is_empty() {
+        log::info!("No grants from {} to revoke", address);
+        return Ok(());
+    }
+    // Without --yes this is a dry run: show what would be revoked and stop
+    if !args.yes {
+        match output {
+            OutputFormat::Text => {
+                println!("Would revoke at height {}:", height);
+                for grant in &revoking {
+                    println!(
+                        "  {} -> {}: {}",
+                        grant.granter,
+                        grant.grantee,
+                        grant.msg.as_deref().unwrap_or_default()
+                    );
+                }
+            }
+            OutputFormat::Json => println!(
+                "{}",
+                serde_json::json!({
+                    "address": address,
+                    "height": height,
+                    "revoke": revoking,
+                })
+            ),
+        }
+        log::info!(
+            "Would revoke {} grants from {}; rerun with --yes to sign and broadcast",
+            msgs.len(),
+            address
+        );
+        return Ok(());
+    }
+    let revoked = msgs.len();
+    let tx_body = Body::new(
+        msgs,
+        memo_or(&args.tx, &withdraw_tx, "Revoke authz grants"),
+        Height::try_from(args.tx.timeout_height)?,
+    );
+    let outcome = sign_and_broadcast(
+        withdraw_tx,
+        channel,
+        &args.node,
+        &tx_body,
+        "Revocation",
+        output,
+    )
+    .await?;
+    log::info!(
+        "Revoked {} grants in tx {} at height {}",
+        revoked,
+        outcome.tx_hash,
+        outcome.height
+    );
+
+    Ok(())
+}
+
+async fn authz_withdraw(args: AuthzWithdrawArgs, output: OutputFormat) -> Result<()> {
+    let withdraw_tx = withdraw_tx(&args.key, &args.node, &args.tx)?;
+    let grantee = withdraw_tx.account_address()?;
+    let channel =
+        endpoint::connect_grpc(&args.node.grpc_url, args.node.grpc_web, proxy(&args.node)).await?;
+    let height = match args.node.query_height {
+        Some(height) => height,
+        None => endpoint::latest_height(channel.clone()).await?,
     };
+
+    let validators = validator_set::validators(channel.clone(), height).await?;
+    let selected = validator_set::resolve(&args.validators, &validators, proxy(&args.node)).await?;
+    let grants = authz::grants(channel.clone(), grantee.as_ref(), height).await?;
+    let mut operators = Vec::new();
+    let mut withdrawing = Vec::new();
+    for validator in &selected {
+        let operator: cosmrs::AccountId = match validator.parse() {
+            Ok(operator) => operator,
+            Err(e) => {
+                log::error!("Failed to parse validator address: {}", e);
+                return Err(eyre::Report::msg(format!(
+                    "Failed to parse validator address {}: {}",
+                    validator, e
+                )));
+            }
+        };
+        let granter =
+            match cosmrs::AccountId::new(&withdraw_tx.account_prefix, &operator.to_bytes()) {
+                Ok(granter) => granter.to_string(),
+                Err(e) => {
+                    log::error!("Failed to get granter address: {}", e);
+                    return Err(eyre::Report::msg(format!(
+                        "Failed to get granter address: {}",
+                        e
+                    )));
+                }
+            };
+        // Executing a message without a grant fails the whole transaction,
+        // and so does withdrawing commission that is not there
+        if !grants.iter().any(|grant| {
+            grant.granter == granter
+                && grant.grantee == grantee.as_ref()
+                && authz::allows_commission_withdrawal(grant)
+        }) {
+            log::warn!("Skipping {}: no withdrawal grant to {}", validator, grantee);
+            continue;
+        }
+        if commission::pending_commission(channel.clone(), validator, height)
+            .await?
+            .is_empty()
+        {
+            log::info!("Skipping {}: no pending commission", validator);
+            continue;
+        }
+        log::info!("Withdrawing the commission of {}", validator);
+        operators.push(operator);
+        withdrawing.push(validator);
+    }
+    if operators.is_empty() {
+        log::info!(
+            "None of the {} selected validators has commission to withdraw",
+            selected.len()
+        );
+        return Ok(());
+    }
+    // Without --yes this is a dry run: show what would be withdrawn and stop
+    if !args.yes {
+        match output {
+            OutputFormat::Text => {
+                println!(
+                    "Would withdraw the commission of these validators at height {} as {}:",
+                    height, grantee
+                );
+                for validator in &withdrawing {
+                    println!("  {}", validator);
+                }
+            }
+            OutputFormat::Json => println!(
+                "{}",
+                serde_json::json!({
+                    "grantee": grantee.to_string(),
+                    "height": height,
+                    "validators": withdrawing,
+                })
+            ),
+        }
+        log::info!(
+            "Would withdraw the commission of {} validators; rerun with --yes to sign and broadcast",
+            operators.len()
+        );
+        return Ok(());
+    }
+    let withdrawn = operators.len();
     let tx_body = Body::new(
-        vec![any],
-        "Withdraw validator commission",
-        Height::try_from(args.timeout_height)?,
+        vec![validator_set::withdraw_msg(&grantee, &operators)?],
+        withdraw_tx.memo.clone(),
+        Height::try_from(args.tx.timeout_height)?,
+    );
+    let outcome = sign_and_broadcast(
+        withdraw_tx,
+        channel,
+        &args.node,
+        &tx_body,
+        "Withdrawal",
+        output,
+    )
+    .await?;
+    log::info!(
+        "Withdrew the commission of {} validators in tx {} at height {}",
+        withdrawn,
+        outcome.tx_hash,
+        outcome.height
     );
 
-    // Set up the fee (adjust as needed)
-    let coin = match Coin::new(1000, &args.denom) {
-        Ok(coin) => coin,
-        Err(e) => {
-            log::error!("Failed to create coin: {}", e);
-            return Err(eyre::Report::msg(format!("Failed to create coin: {}", e)));
+    Ok(())
+}
+
+async fn grant(command: GrantCommand, output: OutputFormat) -> Result<()> {
+    let (args, expiration) = match command {
+        GrantCommand::Create(args) => {
+            let expiration = parse_expiration(&args.expiration)?;
+            if expiration.is_none() {
+                log::warn!("The grant never expires; pass --expiration to limit it");
+            }
+            (args.grant, Some(expiration))
         }
+        GrantCommand::Revoke(args) => (args, None),
     };
-    let fee = Fee::from_amount_and_gas(coin, 200000u64);
 
-    // Create a client
-    let channel = tonic::transport::Channel::from_shared(args.grpc_url.clone())?
-        .connect()
-        .await?;
-    let mut query_client =
-        cosmrs::proto::cosmos::auth::v1beta1::query_client::QueryClient::new(channel);
-    let request = tonic::Request::new(cosmrs::proto::cosmos::auth::v1beta1::QueryAccountRequest {
-        address: validator_address.to_string(),
-    });
-    let account_info = match query_client.account(request).await {
-        Ok(account_info) => account_info,
-        Err(e) => {
-            log::error!("Failed to query account info: {}", e);
-            return Err(eyre::Report::msg(format!(
-                "Failed to query account info: {}",
-                e
-            )));
+    let withdraw_tx = withdraw_tx(&args.key, &args.node, &args.tx)?;
+    let granter = withdraw_tx.account_address()?.to_string();
+    let (msg, memo, kind) = match expiration {
+        Some(expiration) => (
+            authz::grant_withdraw_msg(&granter, &args.grantee, expiration),
+            "Grant commission withdrawal",
+            "Grant",
+        ),
+        None => (
+            authz::revoke_withdraw_msg(&granter, &args.grantee),
+            "Revoke commission withdrawal grant",
+            "Revocation",
+        ),
+    };
+    let memo = memo_or(&args.tx, &withdraw_tx, memo);
+    let tx_body = Body::new(vec![msg], memo, Height::try_from(args.tx.timeout_height)?);
+    let channel =
+        endpoint::connect_grpc(&args.node.grpc_url, args.node.grpc_web, proxy(&args.node)).await?;
+    let outcome =
+        sign_and_broadcast(withdraw_tx, channel, &args.node, &tx_body, kind, output).await?;
+    log::info!(
+        "{} for {} committed in tx {} at height {}",
+        kind,
+        args.grantee,
+        outcome.tx_hash,
+        outcome.height
+    );
+
+    Ok(())
+}
+
+async fn feegrant(command: FeegrantCommand, output: OutputFormat) -> Result<()> {
+    let (args, allowance) = match command {
+        FeegrantCommand::Create(args) => {
+            let denom = &args.grant.tx.denom;
+            let allowance = feegrant::Allowance {
+                spend_limit: args
+                    .spend_limit
+                    .map(|amount| coin(amount, denom))
+                    .transpose()?,
+                expiration: parse_expiration(&args.expiration)?,
+                period: match (args.period, args.period_spend_limit) {
+                    (Some(period), Some(limit)) => Some((period, coin(limit, denom)?)),
+                    _ => None,
+                },
+            };
+            if allowance.spend_limit.is_none() && allowance.expiration.is_none() {
+                log::warn!(
+                    "The allowance has no spend limit and never expires; pass --spend-limit or --expiration to limit it"
+                );
+            }
+            (args.grant, Some(allowance))
         }
+        FeegrantCommand::Revoke(args) => (args, None),
     };
 
-    // Query the account information
-    let account_any = account_info.into_inner().account.unwrap();
-    let base_account = match cosmrs::proto::cosmos::auth::v1beta1::BaseAccount::decode(
-        account_any.value.as_slice(),
-    ) {
-        Ok(base_account) => base_account,
+    let withdraw_tx = withdraw_tx(&args.key, &args.node, &args.tx)?;
+    let granter = withdraw_tx.account_address()?.to_string();
+    let (msg, memo, kind) = match allowance {
+        Some(allowance) => (
+            feegrant::grant_allowance_msg(&granter, &args.grantee, &allowance, Time::now())?,
+            "Grant fee allowance",
+            "Fee allowance",
+        ),
+        None => (
+            feegrant::revoke_allowance_msg(&granter, &args.grantee),
+            "Revoke fee allowance",
+            "Fee allowance revocation",
+        ),
+    };
+    let memo = memo_or(&args.tx, &withdraw_tx, memo);
+    let tx_body = Body::new(vec![msg], memo, Height::try_from(args.tx.timeout_height)?);
+    let channel =
+        endpoint::connect_grpc(&args.node.grpc_url, args.node.grpc_web, proxy(&args.node)).await?;
+    let outcome =
+        sign_and_broadcast(withdraw_tx, channel, &args.node, &tx_body, kind, output).await?;
+    log::info!(
+        "{} for {} committed in tx {} at height {}",
+        kind,
+        args.grantee,
+        outcome.tx_hash,
+        outcome.height
+    );
+
+    Ok(())
+}
+
+/// `amount` of `denom` as a coin.
+fn coin(amount: u128, denom: &str) -> Result<cosmrs::Coin> {
+    match cosmrs::Coin::new(amount, denom) {
+        Ok(coin) => Ok(coin),
         Err(e) => {
-            log::error!("Failed to decode BaseAccount: {}", e);
-            return Err(eyre::Report::msg(format!(
-                "Failed to decode BaseAccount: {}",
-                e
-            )));
+            log::error!("Failed to create coin: {}", e);
+            Err(eyre::Report::msg(format!("Failed to create coin: {}", e)))
         }
-    };
-    let account_number = base_account.account_number;
-    let sequence_number = base_account.sequence;
+    }
+}
 
-    // Create the sign doc
-    let chain_id = match Id::from_str(&args.chain_id) {
-        Ok(chain_id) => chain_id,
+/// Parse an RFC 3339 `--expiration`.
+fn parse_expiration(expiration: &Option<String>) -> Result<Option<Time>> {
+    let Some(expiration) = expiration else {
+        return Ok(None);
+    };
+    match Time::parse_from_rfc3339(expiration) {
+        Ok(expiration) => Ok(Some(expiration)),
         Err(e) => {
-            log::error!("Failed to parse chain ID: {}", e);
-            return Err(eyre::Report::msg(format!(
-                "Failed to parse chain ID: {}",
-                e
-            )));
+            log::error!("Failed to parse expiration: {}", e);
+            Err(eyre::Report::msg(format!(
+                "Failed to parse expiration {}: {}",
+                expiration, e
+            )))
+        }
+    }
+}
+
+/// The memo set with --memo, or `default` for transactions other than withdrawals.
+fn memo_or(tx: &TxArgs, withdraw_tx: &WithdrawTx, default: &str) -> String {
+    match tx.memo {
+        Some(_) => withdraw_tx.memo.clone(),
+        None => default.to_string(),
+    }
+}
+
+/// Sign `tx_body` with the key of `withdraw_tx` at the latest height,
+/// broadcast it and print the result. `kind` names the transaction in errors.
+async fn sign_and_broadcast(
+    withdraw_tx: WithdrawTx,
+    channel: endpoint::GrpcChannel,
+    node: &NodeArgs,
+    tx_body: &Body,
+    kind: &str,
+    output: OutputFormat,
+) -> Result<outcome::RunOutcome> {
+    refuse_read_only()?;
+
+    let rpc_client = endpoint::connect_rpc(&node.rpc_url, proxy(node)).await?;
+    let height = endpoint::latest_height(channel.clone()).await?;
+    let mut outcome = outcome::RunOutcome {
+        chain_id: node.chain_id.clone(),
+        validator: withdraw_tx.operator_address()?.to_string(),
+        ..Default::default()
+    };
+    let client = WithdrawClient::new(withdraw_tx, channel, rpc_client);
+    let tx_bytes = client.sign(tx_body, height).await?;
+    let response = client.broadcast(tx_bytes).await?;
+    outcome.record(&response);
+
+    match output {
+        OutputFormat::Text => println!("Response: {:?}", response),
+        OutputFormat::Json => println!("{}", outcome.to_json()),
+    }
+    if !outcome.success {
+        log::error!(
+            "{} transaction {} failed with code {}: {}",
+            kind,
+            outcome.tx_hash,
+            outcome.code,
+            outcome.log
+        );
+        return Err(eyre::Report::msg(format!(
+            "{} transaction {} failed with code {}: {}",
+            kind, outcome.tx_hash, outcome.code, outcome.log
+        )));
+    }
+
+    Ok(outcome)
+}
+
+async fn multisig_sign(args: MultisigSignArgs) -> Result<()> {
+    // Every member must sign the same fee, possibly offline, so it cannot
+    // come from a simulation at signing time
+    if args.tx.gas_prices.is_some() {
+        return Err(eyre::Report::msg(
+            "--gas-prices is not supported by multisig sign; agree on a fee and pass --fees and --gas-limit",
+        ));
+    }
+    let withdraw_tx = withdraw_tx(&args.key, &args.node, &args.tx)?;
+    let multisig_key = multisig::load_multisig(&args.multisig_pubkey)?;
+    let address = multisig::address(&multisig_key, &withdraw_tx.account_prefix)?;
+    let operator_address = multisig::address(&multisig_key, &withdraw_tx.valoper_prefix)?;
+    log::info!("Multisig validator operator address: {}", operator_address);
+    let tx_body = withdraw_tx.body_for(operator_address)?;
+    let fee = withdraw_tx.fee(&tx_body)?;
+
+    let (account_number, sequence) = match (args.account_number, args.sequence) {
+        (Some(account_number), Some(sequence)) => (account_number, sequence),
+        _ => {
+            let channel =
+                endpoint::connect_grpc(&args.node.grpc_url, args.node.grpc_web, proxy(&args.node))
+                    .await?;
+            let height = match args.node.query_height {
+                Some(height) => height,
+                None => endpoint::latest_height(channel.clone()).await?,
+            };
+            account::account(channel, address.as_ref(), height).await?
         }
     };
 
-    // Set up the signer info
-    let signer_info = SignerInfo::single_direct(Some(signing_key.public_key()), sequence_number);
-    let sign_doc = match SignDoc::new(
+    let sign_doc = multisig::amino_sign_doc(
+        &args.node.chain_id,
+        account_number,
+        sequence,
+        &fee,
         &tx_body,
-        &AuthInfo {
-            fee,
-            signer_infos: vec![signer_info],
-        },
-        &chain_id,
+    )?;
+    let partial = multisig::sign(&withdraw_tx.signing_key, &multisig_key, sign_doc, &tx_body)?;
+    if let Err(e) = fs::write(&args.out, serde_json::to_string_pretty(&partial)? + "\n") {
+        log::error!("Failed to write partial signature: {}", e);
+        return Err(eyre::Report::msg(format!(
+            "Failed to write partial signature: {}",
+            e
+        )));
+    }
+    log::info!(
+        "Partial signature written to {} (account {}, sequence {})",
+        args.out,
         account_number,
+        sequence
+    );
+
+    Ok(())
+}
+
+async fn multisig_combine(args: MultisigCombineArgs, output: OutputFormat) -> Result<()> {
+    let multisig_key = multisig::load_multisig(&args.multisig_pubkey)?;
+    let mut partials = Vec::new();
+    for path in &args.partials {
+        let partial = match fs::read_to_string(path)
+            .map_err(eyre::Report::from)
+            .and_then(|contents| Ok(serde_json::from_str(&contents)?))
+        {
+            Ok(partial) => partial,
+            Err(e) => {
+                log::error!("Failed to read partial signature {}: {}", path, e);
+                return Err(eyre::Report::msg(format!(
+                    "Failed to read partial signature {}: {}",
+                    path, e
+                )));
+            }
+        };
+        partials.push(partial);
+    }
+    let tx_bytes = multisig::combine(&multisig_key, &partials)?;
+    let tx_hash = broadcast::tx_hash(&tx_bytes).to_string();
+
+    if !args.broadcast {
+        let tx = base64::engine::general_purpose::STANDARD.encode(&tx_bytes);
+        match output {
+            OutputFormat::Text => println!("{}", tx),
+            OutputFormat::Json => {
+                println!("{}", serde_json::json!({ "tx_hash": tx_hash, "tx": tx }))
+            }
+        }
+        return Ok(());
+    }
+
+    refuse_read_only()?;
+    let rpc_client = endpoint::connect_rpc(&args.node.rpc_url, proxy(&args.node)).await?;
+    let mut outcome = outcome::RunOutcome {
+        chain_id: args.node.chain_id.clone(),
+        validator: multisig::validator(&partials[0]).unwrap_or_default(),
+        tx_hash,
+        ..Default::default()
+    };
+    let response = broadcast::broadcast(
+        &rpc_client,
+        tx_bytes,
+        broadcast::AlreadyInCache::Confirm,
+        Duration::from_secs(60),
+    )
+    .await?;
+    outcome.record(&response);
+
+    match output {
+        OutputFormat::Text => println!("Response: {:?}", response),
+        OutputFormat::Json => println!("{}", outcome.to_json()),
+    }
+    if !outcome.success {
+        log::error!(
+            "Withdrawal transaction {} failed with code {}: {}",
+            outcome.tx_hash,
+            outcome.code,
+            outcome.log
+        );
+        return Err(eyre::Report::msg(format!(
+            "Withdrawal transaction {} failed with code {}: {}",
+            outcome.tx_hash, outcome.code, outcome.log
+        )));
+    }
+
+    Ok(())
+}
+
+async fn simulate(args: SimulateArgs, output: OutputFormat) -> Result<()> {
+    let withdraw_tx = withdraw_tx(&args.key, &args.node, &args.tx)?;
+    let tx_body = withdraw_tx.body()?;
+    let fee = withdraw_tx.fee(&tx_body)?;
+    let mut gas_limit = fee.gas_limit;
+
+    let channel =
+        endpoint::connect_grpc(&args.node.grpc_url, args.node.grpc_web, proxy(&args.node)).await?;
+    let rpc_client = endpoint::connect_rpc(&args.node.rpc_url, proxy(&args.node)).await?;
+    let height = match args.node.query_height {
+        Some(height) => height,
+        None => endpoint::latest_height(channel.clone()).await?,
+    };
+    let mut fee_coins: Vec<_> = fee.amount.iter().cloned().map(Into::into).collect();
+    let client = WithdrawClient::new(withdraw_tx, channel.clone(), rpc_client);
+    let (_, sequence) = client.account(height).await?;
+    let tx_bytes = client.tx.unsigned(&tx_body, fee, sequence)?;
+    let gas_info = client.simulate(tx_bytes).await?;
+
+    // Show the fee --gas-prices would pay for the simulated gas
+    if client.tx.gas_price.is_some() {
+        let fee = client.tx.simulated_fee(gas_info.gas_used)?;
+        gas_limit = fee.gas_limit;
+        fee_coins = fee.amount.iter().cloned().map(Into::into).collect();
+    }
+
+    // Work out the balances the withdrawal would leave behind
+    let fee_payer = match &client.tx.fee_granter {
+        Some(granter) => granter.to_string(),
+        None => client.tx.account_address()?.to_string(),
+    };
+    let withdraw_address = commission::withdraw_address(
+        channel.clone(),
+        client.tx.validator_account_address()?.as_ref(),
+        height,
+    )
+    .await?;
+    let commission = commission::pending_commission(
+        channel.clone(),
+        client.tx.operator_address()?.as_ref(),
+        height,
+    )
+    .await?;
+    let mut lines = balance_sheet::lines(&withdraw_address, &fee_payer, &commission, &fee_coins)?;
+    for line in &mut lines {
+        let before =
+            balance_sheet::balance(channel.clone(), &line.address, &line.denom, height).await?;
+        line.set_before(before)?;
+    }
+
+    match output {
+        OutputFormat::Text => {
+            println!("Gas used: {}", gas_info.gas_used);
+            println!("Gas limit: {}", gas_limit);
+            println!("Balances at height {} and after the withdrawal:", height);
+            for line in &lines {
+                println!(
+                    "  {} {}: {} + {} commission - {} fee = {}",
+                    line.address, line.denom, line.before, line.commission, line.fee, line.after
+                );
+            }
+        }
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::json!({
+                "gas_used": gas_info.gas_used,
+                "gas_limit": gas_limit,
+                "height": height,
+                "balance_sheet": lines,
+            })
+        ),
+    }
+    // The fee is deducted before the commission is credited
+    for line in lines.iter().filter(|line| line.fee > line.before) {
+        log::warn!(
+            "{} holds {}{} but the fee is {}{}; the transaction would fail",
+            line.address,
+            line.before,
+            line.denom,
+            line.fee,
+            line.denom
+        );
+    }
+    if gas_info.gas_used > gas_limit {
+        log::warn!(
+            "Simulation used {} gas, more than the gas limit {}; raise it with --gas-table or use --gas-prices",
+            gas_info.gas_used,
+            gas_limit
+        );
+    }
+
+    Ok(())
+}
+
+fn show_key(args: KeyArgs, output: OutputFormat) -> Result<()> {
+    let (account_prefix, valoper_prefix) = prefixes(&args);
+    print_key(
+        &signing_key(&args)?,
+        &account_prefix,
+        &valoper_prefix,
+        output,
+    )
+}
+
+fn print_key(
+    signing_key: &cosmrs::crypto::secp256k1::SigningKey,
+    account_prefix: &str,
+    valoper_prefix: &str,
+    output: OutputFormat,
+) -> Result<()> {
+    let public_key = signing_key.public_key();
+    let (address, operator_address) = match (
+        public_key.account_id(account_prefix),
+        public_key.account_id(valoper_prefix),
     ) {
-        Ok(sign_doc) => sign_doc,
-        Err(e) => {
-            log::error!("Failed to create sign doc: {}", e);
+        (Ok(address), Ok(operator_address)) => (address, operator_address),
+        (Err(e), _) | (_, Err(e)) => {
+            log::error!("Failed to derive addresses: {}", e);
             return Err(eyre::Report::msg(format!(
-                "Failed to create sign doc: {}",
+                "Failed to derive addresses: {}",
                 e
             )));
         }
     };
 
-    // Sign the transaction
-    let tx_raw = match sign_doc.sign(&signing_key) {
-        Ok(tx_raw) => tx_raw,
+    match output {
+        OutputFormat::Text => {
+            println!("address: {}", address);
+            println!("operator_address: {}", operator_address);
+            println!("pubkey: {}", public_key.to_json());
+        }
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::json!({
+                "address": address.to_string(),
+                "operator_address": operator_address.to_string(),
+                "pubkey": serde_json::from_str::<serde_json::Value>(&public_key.to_json())?,
+            })
+        ),
+    }
+
+    Ok(())
+}
+
+fn verify_key(args: VerifyArgs, output: OutputFormat) -> Result<()> {
+    let (_, valoper_prefix) = prefixes(&args.key);
+    let operator_address = match signing_key(&args.key)?
+        .public_key()
+        .account_id(&valoper_prefix)
+    {
+        Ok(address) => address.to_string(),
         Err(e) => {
-            log::error!("Failed to sign transaction: {}", e);
+            log::error!("Failed to get validator operator address: {}", e);
             return Err(eyre::Report::msg(format!(
-                "Failed to sign transaction: {}",
+                "Failed to get validator operator address: {}",
                 e
             )));
         }
     };
+    let matches = operator_address == args.expected_valoper;
+
+    match output {
+        OutputFormat::Text if matches => {
+            println!("ok: the signing key belongs to {}", operator_address)
+        }
+        OutputFormat::Text => {}
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::json!({
+                "operator_address": operator_address,
+                "expected_valoper": args.expected_valoper,
+                "matches": matches,
+            })
+        ),
+    }
+    if !matches {
+        return Err(eyre::Report::msg(format!(
+            "The signing key belongs to {}, not {}",
+            operator_address, args.expected_valoper
+        )));
+    }
 
-    // Create a client and broadcast the transaction
-    let Ok(client) = cosmrs::rpc::HttpClient::new(args.rpc_url.as_str()) else {
-        log::error!("Failed to create client");
-        return Err(eyre::Report::msg("Failed to create client"));
+    Ok(())
+}
+
+/// Ask for a new passphrase twice, or read it from the environment variable
+/// `env`.
+fn new_passphrase(env: Option<&str>) -> Result<String> {
+    let new_passphrase = match env {
+        Some(env) => passphrase(Some(env), "")?,
+        None => {
+            let first = passphrase(None, "New passphrase: ")?;
+            if first != passphrase(None, "Repeat passphrase: ")? {
+                return Err(eyre::Report::msg("The passphrases do not match"));
+            }
+            first
+        }
     };
-    let tx_bytes = match tx_raw.to_bytes() {
-        Ok(tx_bytes) => tx_bytes,
-        Err(e) => {
-            log::error!("Failed to convert transaction to bytes: {}", e);
+    if new_passphrase.is_empty() {
+        return Err(eyre::Report::msg("The passphrase must not be empty"));
+    }
+
+    Ok(new_passphrase)
+}
+
+/// Write an encrypted key file that only its owner may read, refusing to
+/// overwrite an existing file.
+fn write_key_file(path: &str, contents: &str) -> Result<()> {
+    let file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path);
+    if let Err(e) = file.and_then(|mut file| file.write_all(contents.as_bytes())) {
+        log::error!("Failed to write encrypted key: {}", e);
+        return Err(eyre::Report::msg(format!(
+            "Failed to write encrypted key: {}",
+            e
+        )));
+    }
+
+    Ok(())
+}
+
+fn encrypt_key(args: EncryptArgs) -> Result<()> {
+    let new_passphrase = new_passphrase(args.passphrase_env.as_deref())?;
+    let contents = keys::encrypt_key_file(&args.signing_key_path, &new_passphrase)?;
+    write_key_file(&args.out, &contents)?;
+    log::info!(
+        "Encrypted key written to {}; remove {} once the new file works",
+        args.out,
+        args.signing_key_path
+    );
+
+    Ok(())
+}
+
+fn generate_key(args: GenerateArgs, output: OutputFormat) -> Result<()> {
+    let new_passphrase = new_passphrase(args.passphrase_env.as_deref())?;
+    let (signing_key, contents) = keys::generate_key(&new_passphrase)?;
+    write_key_file(&args.out, &contents)?;
+    log::info!("Encrypted key written to {}; back it up", args.out);
+
+    let (account_prefix, valoper_prefix) = chain::prefixes(None, None, None);
+    print_key(&signing_key, &account_prefix, &valoper_prefix, output)
+}
+
+async fn run(args: WithdrawArgs, output: OutputFormat) -> Result<()> {
+    let sinks = sinks(&args)?;
+    let mut outcome = outcome::RunOutcome {
+        chain_id: args.node.chain_id.clone(),
+        ..Default::default()
+    };
+
+    // Every failure leaves through here, so sinks also hear about runs that
+    // stop before broadcasting, such as jail holds or read-only refusals
+    if let Err(e) = withdraw_once(&args, output, &sinks, &mut outcome).await {
+        outcome.success = false;
+        // A transaction that was committed but failed keeps its own code and log
+        if outcome.code == 0 {
+            outcome.log = e.to_string();
+        }
+        if output == OutputFormat::Json {
+            println!("{}", outcome.to_json());
+        }
+        sinks.report(&outcome).await;
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+/// One withdrawal run. Successful withdrawals are reported here; every
+/// failure, including a committed transaction that failed, is left to
+/// [`run`].
+async fn withdraw_once(
+    args: &WithdrawArgs,
+    output: OutputFormat,
+    sinks: &outcome::Sinks,
+    outcome: &mut outcome::RunOutcome,
+) -> Result<()> {
+    // Describe the transaction
+    let withdraw_tx = withdraw_tx(&args.key, &args.node, &args.tx)?;
+
+    // Derive the validator addresses from the private key
+    let validator_address = withdraw_tx.account_address()?;
+    let validator_operator_address = withdraw_tx.operator_address()?;
+    log::info!("Validator address: {}", validator_address);
+    log::info!("Validator operator address: {}", validator_operator_address);
+    outcome.validator = validator_operator_address.to_string();
+
+    let mut tx_body = withdraw_tx.body()?;
+
+    // Compounding and payouts are shares of the same commission
+    let shared = args
+        .payout
+        .iter()
+        .map(|(_, percent)| *percent)
+        .chain(args.compound)
+        .try_fold(Decimal::ZERO, Decimal::checked_add)
+        .unwrap_or(Decimal::from_atomics(u128::MAX));
+    let Some(rest) = Decimal::HUNDRED.checked_sub(shared) else {
+        return Err(eyre::Report::msg(format!(
+            "--compound and --payout add up to {}% of the commission, more than 100%",
+            shared
+        )));
+    };
+
+    // Only the commission withdrawal is executed for an authz granter
+    if args.tx.authz_granter.is_some() {
+        let moving = [
+            ("--include-rewards", args.include_rewards),
+            ("--compound", args.compound.is_some()),
+            ("--delegate-rewards", !args.delegate_rewards.is_empty()),
+            ("--payout", !args.payout.is_empty()),
+            ("--rotate-payout", !args.rotate_payout.is_empty()),
+            ("--ibc-transfer", args.ibc_transfer.is_some()),
+        ];
+        if let Some((flag, _)) = moving.iter().find(|(_, set)| *set) {
             return Err(eyre::Report::msg(format!(
-                "Failed to convert transaction to bytes: {}",
-                e
+                "--authz-granter cannot be combined with {}, which needs more than the commission withdrawal grant",
+                flag
             )));
         }
+    }
+
+    // Describe the run as a plan, for --plan and to diff the next one against
+    let plan = build_plan(
+        args,
+        &withdraw_tx,
+        &tx_body,
+        &validator_address,
+        &validator_operator_address,
+    )?;
+
+    // Show the plan instead of signing and broadcasting
+    if args.plan {
+        return print_plan(&plan, args);
+    }
+
+    // Create the clients
+    let channel =
+        endpoint::connect_grpc(&args.node.grpc_url, args.node.grpc_web, proxy(&args.node)).await?;
+    let rpc_client = endpoint::connect_rpc(&args.node.rpc_url, proxy(&args.node)).await?;
+
+    // Warn when the node runs software outside the tested matrix
+    if !args.skip_version_check {
+        let versions = compat::node_versions(channel.clone()).await?;
+        log::info!(
+            "Node runs {} {} (Cosmos SDK {}, CometBFT {})",
+            versions.app_name,
+            versions.app_version,
+            versions.sdk_version,
+            versions.comet_version
+        );
+        for component in compat::untested_components(&versions) {
+            log::warn!(
+                "Node runs untested {}; broadcast errors may be caused by incompatibilities",
+                component
+            );
+        }
+    }
+
+    // Pin every query to one height so the reads are mutually consistent
+    let query_height = match args.node.query_height {
+        Some(height) => height,
+        None => endpoint::latest_height(channel.clone()).await?,
+    };
+    log::info!("Querying state at height {}", query_height);
+
+    // Avoid dust withdrawals from schedules or triggers that fire too often
+    if let Some(min_interval) = args.min_interval {
+        let account_address = withdraw_tx.validator_account_address()?;
+        let withdraw_address =
+            commission::withdraw_address(channel.clone(), account_address.as_ref(), query_height)
+                .await?;
+        if let Some(last) = history::last_withdrawal(
+            channel.clone(),
+            validator_operator_address.as_ref(),
+            &account_address,
+            &withdraw_address,
+        )
+        .await?
+        {
+            let min_interval = Duration::from_secs(min_interval);
+            if let Some(remaining) = history::remaining(last.time, Time::now(), min_interval) {
+                log::info!(
+                    "Skipping: the last withdrawal was tx {} at height {} ({}), next one allowed in {}s",
+                    last.tx_hash,
+                    last.height,
+                    last.time.to_rfc3339(),
+                    remaining.as_secs()
+                );
+                return Ok(());
+            }
+        }
+    }
+
+    // Hold the withdrawal if the chain pays no commission, the validator is
+    // jailed or recently slashed, or the endpoints disagree
+    let mut client = WithdrawClient::new(withdraw_tx, channel.clone(), rpc_client);
+    client.on_already_in_cache = args.on_already_in_cache;
+    client.confirm_timeout = Duration::from_secs(args.confirm_timeout);
+    if read_only(args) {
+        client.set_read_only();
+    }
+    client.slash_lookback_blocks = (!args.ignore_jailed).then_some(args.slash_lookback_blocks);
+    if let Some(url) = &args.cross_check_grpc_url {
+        client.cross_check = Some(CrossCheck {
+            grpc: endpoint::connect_grpc(url, args.node.grpc_web, proxy(&args.node)).await?,
+            tolerance_percent: args.cross_check_tolerance,
+            on_mismatch: args.on_cross_check_mismatch,
+        });
+    }
+    client.check(query_height).await?;
+    let withdraw_tx = &mut client.tx;
+
+    // Delegations and payouts are paid from the validator account, which only
+    // receives the withdrawn funds if no separate withdraw address is set
+    if args.compound.is_some()
+        || !args.delegate_rewards.is_empty()
+        || !args.payout.is_empty()
+        || !args.rotate_payout.is_empty()
+        || args.ibc_transfer.is_some()
+    {
+        let delegator_address = withdraw_tx.validator_account_address()?;
+        let withdraw_address =
+            commission::withdraw_address(channel.clone(), delegator_address.as_ref(), query_height)
+                .await?;
+        if withdraw_address != delegator_address.as_ref() {
+            log::warn!(
+                "Withdrawn funds go to withdraw address {}, so delegations and payouts are paid from the balance of {}",
+                withdraw_address,
+                delegator_address
+            );
+        }
+    }
+
+    // Dust left in the account since the last sweep, which only
+    // --dust accumulate remembers between runs
+    let mut dust = match (args.dust, &args.dust_file) {
+        (split::DustPolicy::Accumulate, Some(path)) => read_dust(path)?,
+        _ => 0,
     };
-    let response = match client.broadcast_tx_commit(tx_bytes).await {
+
+    // Claim the validator account's delegation rewards in the same transaction
+    if args.include_rewards {
+        let delegator_address = withdraw_tx.validator_account_address()?;
+        let rewards = commission::delegation_rewards(
+            channel.clone(),
+            delegator_address.as_ref(),
+            query_height,
+        )
+        .await?;
+        log::info!(
+            "Also withdrawing the rewards of {} from {} validators",
+            delegator_address,
+            rewards.validators.len()
+        );
+        for validator in rewards.validators {
+            match validator.parse() {
+                Ok(validator) => withdraw_tx.reward_validators.push(validator),
+                Err(e) => {
+                    log::error!("Failed to parse validator address: {}", e);
+                    return Err(eyre::Report::msg(format!(
+                        "Failed to parse validator address {}: {}",
+                        validator, e
+                    )));
+                }
+            }
+        }
+
+        // Spread the claimed rewards over the configured validators
+        let weights = args
+            .delegate_rewards
+            .iter()
+            .map(|(_, weight)| *weight)
+            .collect::<Vec<_>>();
+        let total = commission::whole_amount(&rewards.total, &withdraw_tx.denom);
+        let mut amounts = split::split(total, &weights);
+        if !amounts.is_empty() {
+            let leftover = total.saturating_sub(amounts.iter().sum());
+            dust = settle_dust(args, &mut amounts, leftover, dust, withdraw_tx);
+        }
+        for ((validator, _), amount) in args.delegate_rewards.iter().zip(amounts) {
+            if amount == 0 {
+                log::info!(
+                    "Nothing to delegate to {}: its share is below one {}",
+                    validator,
+                    withdraw_tx.denom
+                );
+                continue;
+            }
+            let validator = match validator.parse() {
+                Ok(validator) => validator,
+                Err(e) => {
+                    log::error!("Failed to parse validator address: {}", e);
+                    return Err(eyre::Report::msg(format!(
+                        "Failed to parse validator address {}: {}",
+                        validator, e
+                    )));
+                }
+            };
+            let coin = coin(amount, &withdraw_tx.denom)?;
+            log::info!(
+                "Delegating {} of the rewards to {}",
+                withdraw_tx.display(amount),
+                validator
+            );
+            withdraw_tx.delegations.push((validator, coin));
+        }
+        tx_body = withdraw_tx.body()?;
+    }
+
+    // Delegate part of the commission back to the validator and pay out shares of it
+    if args.compound.is_some()
+        || !args.payout.is_empty()
+        || !args.rotate_payout.is_empty()
+        || args.ibc_transfer.is_some()
+    {
+        let pending = commission::pending_commission(
+            channel.clone(),
+            validator_operator_address.as_ref(),
+            query_height,
+        )
+        .await?;
+        // The signing key's sequence grows with every transaction, so
+        // consecutive runs take turns without keeping state
+        let mut payouts = args.payout.clone();
+        if !args.rotate_payout.is_empty() && rest > Decimal::ZERO {
+            let weights = args
+                .rotate_payout
+                .iter()
+                .map(|(_, weight)| *weight)
+                .collect::<Vec<_>>();
+            let (_, sequence) = account::account(
+                channel.clone(),
+                withdraw_tx.account_address()?.as_ref(),
+                query_height,
+            )
+            .await?;
+            if let Some(turn) = split::rotate(&weights, sequence) {
+                let address = &args.rotate_payout[turn].0;
+                log::info!(
+                    "Sequence {} picks rotating payout address {}",
+                    sequence,
+                    address
+                );
+                payouts.push((address.clone(), rest));
+            }
+        }
+        // Round every share down and settle the dust, the first share first
+        let percents = args
+            .compound
+            .iter()
+            .chain(payouts.iter().map(|(_, percent)| percent))
+            .chain(args.ibc_transfer.as_ref().map(|_| &rest))
+            .copied()
+            .collect::<Vec<_>>();
+        let mut amounts = percents
+            .iter()
+            .map(|percent| commission::commission_share(&pending, &withdraw_tx.denom, *percent))
+            .collect::<Vec<_>>();
+        if !amounts.is_empty() {
+            let total = percents
+                .iter()
+                .try_fold(Decimal::ZERO, |total, percent| total.checked_add(*percent))
+                .unwrap_or(Decimal::HUNDRED);
+            let leftover = commission::commission_share(&pending, &withdraw_tx.denom, total)
+                .saturating_sub(amounts.iter().sum());
+            dust = settle_dust(args, &mut amounts, leftover, dust, withdraw_tx);
+        }
+        let mut amounts = amounts.into_iter();
+        if let Some(percent) = args.compound {
+            let amount = amounts.next().unwrap_or_default();
+            if amount == 0 {
+                log::info!(
+                    "Nothing to compound: {}% of the pending commission is below one {}",
+                    percent,
+                    withdraw_tx.denom
+                );
+            } else {
+                let coin = coin(amount, &withdraw_tx.denom)?;
+                log::info!(
+                    "Compounding {} ({}% of the pending commission)",
+                    withdraw_tx.display(amount),
+                    percent
+                );
+                withdraw_tx.compound = Some(coin);
+            }
+        }
+        for ((address, percent), amount) in payouts.iter().zip(amounts.by_ref()) {
+            if amount == 0 {
+                log::info!(
+                    "Nothing to pay out to {}: {}% of the pending commission is below one {}",
+                    address,
+                    percent,
+                    withdraw_tx.denom
+                );
+                continue;
+            }
+            let address = match address.parse() {
+                Ok(address) => address,
+                Err(e) => {
+                    log::error!("Failed to parse payout address: {}", e);
+                    return Err(eyre::Report::msg(format!(
+                        "Failed to parse payout address {}: {}",
+                        address, e
+                    )));
+                }
+            };
+            let coin = coin(amount, &withdraw_tx.denom)?;
+            log::info!(
+                "Paying out {} ({}% of the pending commission) to {}",
+                withdraw_tx.display(amount),
+                percent,
+                address
+            );
+            withdraw_tx.payouts.push((address, coin));
+        }
+        // Send what is left to another chain
+        if let Some((channel_id, receiver)) = &args.ibc_transfer {
+            let amount = amounts.next().unwrap_or_default();
+            if amount == 0 {
+                log::info!(
+                    "Nothing to transfer: the rest of the pending commission is below one {}",
+                    withdraw_tx.denom
+                );
+            } else {
+                let counterparty_height =
+                    ibc::counterparty_height(channel.clone(), channel_id, query_height).await?;
+                let coin = coin(amount, &withdraw_tx.denom)?;
+                log::info!(
+                    "Transferring {} to {} over {} (counterparty at height {}-{})",
+                    withdraw_tx.display(amount),
+                    receiver,
+                    channel_id,
+                    counterparty_height.revision_number,
+                    counterparty_height.revision_height
+                );
+                withdraw_tx.ibc_transfer = Some(ibc::transfer_msg(
+                    channel_id,
+                    &withdraw_tx.validator_account_address()?,
+                    receiver,
+                    coin,
+                    counterparty_height,
+                    Time::now(),
+                ));
+            }
+        }
+        tx_body = withdraw_tx.body()?;
+    }
+
+    // Sign the transaction, unless this host is read-only
+    let tx_bytes = client.sign(&tx_body, query_height).await?;
+
+    // Broadcast the transaction
+    outcome.tx_hash = broadcast::tx_hash(&tx_bytes).to_string();
+    if dust > 0 {
+        outcome.dust = format!("{}{}", dust, client.tx.denom);
+    }
+    let response = match client.broadcast(tx_bytes).await {
         Ok(response) => response,
         Err(e) => {
-            log::error!("Failed to broadcast transaction: {}", e);
-            return Err(eyre::Report::msg(format!(
-                "Failed to broadcast transaction: {}",
-                e
-            )));
+            log::error!("{}", e);
+            return Err(e);
         }
     };
 
-    println!("Response: {:?}", response);
+    // Report the result
+    outcome.record(&response);
+    if output == OutputFormat::Text {
+        println!("Response: {:?}", response);
+    }
+    if !outcome.success {
+        log::error!(
+            "Withdrawal transaction {} failed with code {}: {}",
+            outcome.tx_hash,
+            outcome.code,
+            outcome.log
+        );
+        return Err(eyre::Report::msg(format!(
+            "Withdrawal transaction {} failed with code {}: {}",
+            outcome.tx_hash, outcome.code, outcome.log
+        )));
+    }
+    if output == OutputFormat::Json {
+        println!("{}", outcome.to_json());
+    }
+
+    // Only a committed transaction moves the dust
+    if let (split::DustPolicy::Accumulate, Some(path)) = (args.dust, &args.dust_file) {
+        if let Err(e) = fs::write(path, format!("{}\n", dust)) {
+            log::error!("Failed to write dust file {}: {}", path, e);
+        }
+    }
+    // The next --plan diffs against what was last withdrawn
+    if let Some(path) = &args.plan_state {
+        if let Err(e) = fs::write(path, plan.render()) {
+            log::error!("Failed to write plan state {}: {}", path, e);
+        }
+    }
+    log::info!(
+        "Withdrew {} commission for {} on {} in tx {} at height {}",
+        outcome.amount,
+        outcome.validator,
+        outcome.chain_id,
+        outcome.tx_hash,
+        outcome.height
+    );
+    sinks.report(outcome).await;
+
+    Ok(())
+}
+
+/// Apply the --dust policy to the `leftover` of rounding `amounts` down, and
+/// return the dust left in the account after `carried`.
+fn settle_dust(
+    args: &WithdrawArgs,
+    amounts: &mut [u128],
+    leftover: u128,
+    carried: u128,
+    withdraw_tx: &WithdrawTx,
+) -> u128 {
+    let (to_first, left) = split::settle_dust(args.dust, leftover, carried, args.dust_threshold);
+    if let Some(first) = amounts.first_mut() {
+        *first = first.saturating_add(to_first);
+    }
+    if to_first > 0 {
+        log::info!(
+            "Adding {} of dust to the first share",
+            withdraw_tx.display(to_first)
+        );
+    } else if leftover > 0 {
+        log::info!(
+            "Leaving {} of dust in the account ({} since the last sweep)",
+            withdraw_tx.display(leftover),
+            withdraw_tx.display(left)
+        );
+    }
+
+    left
+}
+
+/// The dust a previous run left in the account, or zero without a dust file.
+fn read_dust(path: &str) -> Result<u128> {
+    match fs::read_to_string(path) {
+        Ok(contents) => match contents.trim().parse() {
+            Ok(dust) => Ok(dust),
+            Err(e) => {
+                log::error!("Failed to parse dust file: {}", e);
+                Err(eyre::Report::msg(format!(
+                    "Failed to parse dust file {}: {}",
+                    path, e
+                )))
+            }
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(e) => {
+            log::error!("Failed to read dust file: {}", e);
+            Err(eyre::Report::msg(format!(
+                "Failed to read dust file {}: {}",
+                path, e
+            )))
+        }
+    }
+}
+
+/// Whether signing and broadcasting are disabled, by flag or environment.
+fn read_only(args: &WithdrawArgs) -> bool {
+    args.read_only || read_only_env()
+}
+
+/// Fail if the read-only environment variable is set, before anything is
+/// broadcast.
+fn refuse_read_only() -> Result<()> {
+    if read_only_env() {
+        return Err(eyre::Report::msg(format!(
+            "Read-only mode is set ({}), refusing to sign and broadcast",
+            READ_ONLY_ENV
+        )));
+    }
+
+    Ok(())
+}
+
+/// The SOCKS5 proxy to route endpoint connections through, if any.
+#[cfg_attr(not(feature = "socks"), allow(unused_variables))]
+fn proxy(node: &NodeArgs) -> Option<&str> {
+    #[cfg(feature = "socks")]
+    return node.proxy.as_deref();
+    #[cfg(not(feature = "socks"))]
+    None
+}
+
+/// Build the result sinks enabled on the command line.
+#[cfg_attr(
+    not(any(feature = "callback", feature = "statsd")),
+    allow(unused_variables)
+)]
+fn sinks(args: &WithdrawArgs) -> Result<outcome::Sinks> {
+    #[allow(unused_mut)]
+    let mut sinks = outcome::Sinks::default();
+
+    #[cfg(feature = "callback")]
+    {
+        sinks.callback = args.callback_url.as_ref().map(|url| callback::Callback {
+            url: url.clone(),
+            method: args.callback_method.clone(),
+            headers: args.callback_headers.clone(),
+            body_template: args.callback_body.clone(),
+        });
+    }
+
+    #[cfg(feature = "grafana")]
+    if let Some(url) = &args.grafana_url {
+        let token = match &args.grafana_token_path {
+            Some(path) => match fs::read_to_string(path) {
+                Ok(token) => Some(token.trim().to_string()),
+                Err(e) => {
+                    log::error!("Failed to read Grafana token from file: {}", e);
+                    return Err(eyre::Report::msg(format!(
+                        "Failed to read Grafana token from file: {}",
+                        e
+                    )));
+                }
+            },
+            None => None,
+        };
+        sinks.grafana = Some(grafana::Grafana {
+            url: url.clone(),
+            token,
+        });
+    }
+
+    #[cfg(feature = "pushgateway")]
+    {
+        sinks.pushgateway = args
+            .pushgateway_url
+            .as_ref()
+            .map(|url| pushgateway::Pushgateway { url: url.clone() });
+    }
+
+    #[cfg(feature = "statsd")]
+    {
+        sinks.statsd = args.statsd_addr.as_ref().map(|addr| statsd::Statsd {
+            addr: addr.clone(),
+            tags: args.statsd_tags.clone(),
+        });
+    }
+
+    Ok(sinks)
+}
+
+/// The plan of a run: the transaction as configured, before any chain state
+/// such as the pending commission is known.
+fn build_plan(
+    args: &WithdrawArgs,
+    withdraw_tx: &WithdrawTx,
+    tx_body: &Body,
+    validator_address: &cosmrs::AccountId,
+    validator_operator_address: &cosmrs::AccountId,
+) -> Result<plan::Plan> {
+    let mut plan = plan::Plan::default();
+    plan.set("chain_id", &args.node.chain_id);
+    plan.set("signer", validator_address);
+    plan.set("memo", &tx_body.memo);
+    plan.set("timeout_height", args.tx.timeout_height);
+    if let Some(granter) = &args.tx.authz_granter {
+        plan.set("authz_granter", granter);
+    }
+    // The reward messages depend on chain state, so the plan only notes them
+    if args.include_rewards {
+        plan.set("include_rewards", true);
+    }
+    for (validator, weight) in &args.delegate_rewards {
+        plan.set(format!("delegate_rewards.{}", validator), weight);
+    }
+    if let Some(percent) = args.compound {
+        plan.set("compound_percent", percent);
+    }
+    for (address, percent) in &args.payout {
+        plan.set(format!("payout.{}", address), format!("{}%", percent));
+    }
+    // Whose turn it is depends on the account sequence
+    for (address, weight) in &args.rotate_payout {
+        plan.set(format!("rotate_payout.{}", address), weight);
+    }
+    if let Some((channel_id, receiver)) = &args.ibc_transfer {
+        plan.set(format!("ibc_transfer.{}", channel_id), receiver);
+    }
+    if let Some(dust) = args.dust.to_possible_value() {
+        if args.dust != split::DustPolicy::Leave {
+            plan.set("dust", dust.get_name());
+        }
+    }
+    if args.dust == split::DustPolicy::Accumulate {
+        plan.set("dust_threshold", args.dust_threshold);
+    }
+    for (i, msg) in tx_body.messages.iter().enumerate() {
+        plan.set(format!("messages.{}.type_url", i), &msg.type_url);
+    }
+    plan.set("messages.0.validator_address", validator_operator_address);
+    // With --gas-prices the fee is only known after simulating
+    let fee = withdraw_tx.fee(tx_body)?;
+    match &withdraw_tx.gas_price {
+        Some((price, denom)) => {
+            plan.set("fee.gas_prices", format!("{}{}", price, denom));
+            plan.set("fee.gas_adjustment", withdraw_tx.gas_adjustment);
+        }
+        None => {
+            for (i, coin) in fee.amount.iter().enumerate() {
+                plan.set(format!("fee.amount.{}", i), coin);
+            }
+            plan.set("fee.gas_limit", fee.gas_limit);
+        }
+    }
+    if let Some(granter) = &fee.granter {
+        plan.set("fee.granter", granter);
+    }
+
+    Ok(plan)
+}
+
+/// Print a plan, optionally as a diff against a baseline, and save it if requested.
+fn print_plan(plan: &plan::Plan, args: &WithdrawArgs) -> Result<()> {
+    // An explicit baseline wins over the plan of the previous run
+    let baseline = match (&args.plan_baseline, &args.plan_state) {
+        (Some(path), _) => match fs::read_to_string(path) {
+            Ok(contents) => Some(plan::Plan::parse(&contents)?),
+            Err(e) => {
+                log::error!("Failed to read plan baseline: {}", e);
+                return Err(eyre::Report::msg(format!(
+                    "Failed to read plan baseline: {}",
+                    e
+                )));
+            }
+        },
+        (None, Some(path)) => match fs::read_to_string(path) {
+            Ok(contents) => Some(plan::Plan::parse(&contents)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                log::info!("No previous run recorded in {}", path);
+                None
+            }
+            Err(e) => {
+                log::error!("Failed to read plan state: {}", e);
+                return Err(eyre::Report::msg(format!(
+                    "Failed to read plan state {}: {}",
+                    path, e
+                )));
+            }
+        },
+        (None, None) => None,
+    };
+    match baseline {
+        Some(baseline) => {
+            let changes = plan.diff(&baseline);
+            for change in &changes {
+                println!("{}", change);
+            }
+            println!();
+            println!("Plan: {}.", plan::summarize(&changes));
+        }
+        None => print!("{}", plan.render()),
+    }
+
+    if let Some(path) = &args.plan_out {
+        if let Err(e) = fs::write(path, plan.render()) {
+            log::error!("Failed to write plan: {}", e);
+            return Err(eyre::Report::msg(format!("Failed to write plan: {}", e)));
+        }
+        log::info!("Plan written to {}", path);
+    }
 
     Ok(())
 }
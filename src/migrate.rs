@@ -0,0 +1,85 @@
+use std::path::Path;
+
+/// Subcommand that flat-flag invocations are mapped onto.
+const WITHDRAW: &str = "withdraw";
+
+/// Rewrite a command line (without the binary) that uses the legacy flat
+/// flags into the equivalent `withdraw` subcommand invocation. Command lines
+/// that already start with one of `subcommands` are returned unchanged.
+pub fn to_subcommand(args: &[String], subcommands: &[&str]) -> Vec<String> {
+    match args.first() {
+        Some(first) if subcommands.contains(&first.as_str()) => args.to_vec(),
+        _ => std::iter::once(WITHDRAW.to_string())
+            .chain(args.iter().cloned())
+            .collect(),
+    }
+}
+
+/// Split off a leading `withdraw-commission` binary, as found when a whole
+/// cron line is pasted.
+pub fn split_binary(args: &[String]) -> (Option<&String>, &[String]) {
+    match args.split_first() {
+        Some((first, rest))
+            if Path::new(first).file_name() == Some("withdraw-commission".as_ref()) =>
+        {
+            (Some(first), rest)
+        }
+        _ => (None, args),
+    }
+}
+
+/// Quote `arg` for a POSIX shell, leaving plain words untouched.
+pub fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-./:=@%+,".contains(c))
+    {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn maps_flat_flags_onto_withdraw() {
+        let subcommands = ["withdraw", "query"];
+        assert_eq!(
+            to_subcommand(&args(&["--signing-key-path", "key.hex"]), &subcommands),
+            args(&["withdraw", "--signing-key-path", "key.hex"])
+        );
+        assert_eq!(
+            to_subcommand(&args(&["query", "commission"]), &subcommands),
+            args(&["query", "commission"])
+        );
+    }
+
+    #[test]
+    fn splits_off_pasted_binary() {
+        let line = args(&["/usr/local/bin/withdraw-commission", "--plan"]);
+        let (binary, rest) = split_binary(&line);
+        assert_eq!(
+            binary.map(String::as_str),
+            Some("/usr/local/bin/withdraw-commission")
+        );
+        assert_eq!(rest, &args(&["--plan"])[..]);
+    }
+
+    #[test]
+    fn quotes_shell_words() {
+        assert_eq!(
+            shell_quote("--rpc-url=https://x:443"),
+            "--rpc-url=https://x:443"
+        );
+        assert_eq!(shell_quote("it's here"), r"'it'\''s here'");
+        assert_eq!(shell_quote(""), "''");
+    }
+}
@@ -0,0 +1,468 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use cosmrs::bip32::secp256k1::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+use cosmrs::crypto::{secp256k1::SigningKey, CompactBitArray, LegacyAminoMultisig, PublicKey};
+use cosmrs::proto::cosmos::crypto::multisig::v1beta1::MultiSignature;
+use cosmrs::proto::cosmos::distribution::v1beta1::{
+    MsgWithdrawDelegatorReward, MsgWithdrawValidatorCommission,
+};
+use cosmrs::proto::cosmos::tx::v1beta1::TxRaw;
+use cosmrs::proto::prost::Message;
+use cosmrs::tx::{mode_info::Multi, AuthInfo, Body, Fee, ModeInfo, SignMode, SignerInfo};
+use cosmrs::{AccountId, Any, Coin};
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+/// Amino prefix of `tendermint/PubKeyMultisigThreshold`.
+const MULTISIG_AMINO_PREFIX: [u8; 4] = [0x22, 0xc1, 0xf7, 0xe2];
+
+/// Amino prefix of `tendermint/PubKeySecp256k1`.
+const SECP256K1_AMINO_PREFIX: [u8; 4] = [0xeb, 0x5a, 0xe9, 0x87];
+
+/// A multisig public key as printed by `<daemon> keys show <name> --pubkey`.
+#[derive(Deserialize)]
+struct MultisigJson {
+    #[serde(rename = "@type")]
+    type_url: String,
+    threshold: u32,
+    public_keys: Vec<PublicKey>,
+}
+
+/// One member's signature over the amino JSON sign doc, as exchanged between
+/// `multisig sign` and `multisig combine`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartialSignature {
+    /// The canonical amino JSON that was signed
+    pub sign_doc: String,
+    /// Base64 protobuf transaction body the sign doc describes
+    pub body: String,
+    /// Base64 compressed public key of the member
+    pub public_key: String,
+    /// Base64 signature
+    pub signature: String,
+}
+
+/// Load a multisig public key from a file in the JSON form printed by
+/// `<daemon> keys show <name> --pubkey`.
+pub fn load_multisig(path: impl AsRef<Path>) -> Result<LegacyAminoMultisig> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            log::error!("Failed to read multisig public key: {}", e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to read multisig public key: {}",
+                e
+            )));
+        }
+    };
+    let multisig: MultisigJson = match serde_json::from_str(&contents) {
+        Ok(multisig) => multisig,
+        Err(e) => {
+            log::error!("Failed to parse multisig public key: {}", e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to parse multisig public key: {}",
+                e
+            )));
+        }
+    };
+    if multisig.type_url != LegacyAminoMultisig::TYPE_URL {
+        return Err(eyre::Report::msg(format!(
+            "Expected a {} public key, got {}",
+            LegacyAminoMultisig::TYPE_URL,
+            multisig.type_url
+        )));
+    }
+    if multisig.threshold == 0 || multisig.threshold as usize > multisig.public_keys.len() {
+        return Err(eyre::Report::msg(format!(
+            "Invalid multisig threshold {} of {}",
+            multisig.threshold,
+            multisig.public_keys.len()
+        )));
+    }
+
+    Ok(LegacyAminoMultisig {
+        threshold: multisig.threshold,
+        public_keys: multisig.public_keys,
+    })
+}
+
+/// The address of a multisig key: the truncated SHA-256 of its amino encoding.
+pub fn address(multisig: &LegacyAminoMultisig, prefix: &str) -> Result<AccountId> {
+    let mut amino = MULTISIG_AMINO_PREFIX.to_vec();
+    amino.push(0x08);
+    push_uvarint(&mut amino, multisig.threshold as u64);
+    for public_key in &multisig.public_keys {
+        let key = public_key.to_bytes();
+        amino.push(0x12);
+        push_uvarint(
+            &mut amino,
+            (SECP256K1_AMINO_PREFIX.len() + 1 + key.len()) as u64,
+        );
+        amino.extend(SECP256K1_AMINO_PREFIX);
+        push_uvarint(&mut amino, key.len() as u64);
+        amino.extend(key);
+    }
+
+    match AccountId::new(prefix, &Sha256::digest(&amino)[..20]) {
+        Ok(address) => Ok(address),
+        Err(e) => {
+            log::error!("Failed to derive multisig address: {}", e);
+            Err(eyre::Report::msg(format!(
+                "Failed to derive multisig address: {}",
+                e
+            )))
+        }
+    }
+}
+
+fn push_uvarint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// The canonical amino JSON sign doc of `body` and `fee`, which multisig
+/// members sign with `SIGN_MODE_LEGACY_AMINO_JSON`.
+pub fn amino_sign_doc(
+    chain_id: &str,
+    account_number: u64,
+    sequence: u64,
+    fee: &Fee,
+    body: &Body,
+) -> Result<String> {
+    let mut amino_fee = json!({
+        "amount": fee
+            .amount
+            .iter()
+            .map(|coin| json!({"amount": coin.amount.to_string(), "denom": coin.denom.to_string()}))
+            .collect::<Vec<_>>(),
+        "gas": fee.gas_limit.to_string(),
+    });
+    if let Some(payer) = &fee.payer {
+        amino_fee["payer"] = json!(payer.to_string());
+    }
+    if let Some(granter) = &fee.granter {
+        amino_fee["granter"] = json!(granter.to_string());
+    }
+    let mut sign_doc = json!({
+        "account_number": account_number.to_string(),
+        "chain_id": chain_id,
+        "fee": amino_fee,
+        "memo": body.memo,
+        "msgs": body.messages.iter().map(amino_msg).collect::<Result<Vec<_>>>()?,
+        "sequence": sequence.to_string(),
+    });
+    if body.timeout_height.value() != 0 {
+        sign_doc["timeout_height"] = json!(body.timeout_height.value().to_string());
+    }
+
+    // serde_json sorts object keys; Go additionally escapes HTML characters
+    Ok(serde_json::to_string(&sign_doc)?
+        .replace('<', "\\u003c")
+        .replace('>', "\\u003e")
+        .replace('&', "\\u0026"))
+}
+
+/// The amino JSON form of a message this tool builds.
+fn amino_msg(msg: &Any) -> Result<Value> {
+    let decode_error = |e: cosmrs::proto::prost::DecodeError| {
+        eyre::Report::msg(format!("Failed to decode {}: {}", msg.type_url, e))
+    };
+    match msg.type_url.as_str() {
+        "/cosmos.distribution.v1beta1.MsgWithdrawValidatorCommission" => {
+            let msg = MsgWithdrawValidatorCommission::decode(msg.value.as_slice())
+                .map_err(decode_error)?;
+            Ok(json!({
+                "type": "cosmos-sdk/MsgWithdrawValCommission",
+                "value": {"validator_address": msg.validator_address},
+            }))
+        }
+        "/cosmos.distribution.v1beta1.MsgWithdrawDelegatorReward" => {
+            let msg =
+                MsgWithdrawDelegatorReward::decode(msg.value.as_slice()).map_err(decode_error)?;
+            Ok(json!({
+                "type": "cosmos-sdk/MsgWithdrawDelegationReward",
+                "value": {
+                    "delegator_address": msg.delegator_address,
+                    "validator_address": msg.validator_address,
+                },
+            }))
+        }
+        other => Err(eyre::Report::msg(format!(
+            "{} cannot be signed by a multisig member yet",
+            other
+        ))),
+    }
+}
+
+/// Sign `sign_doc` as one member of `multisig`.
+pub fn sign(
+    signing_key: &SigningKey,
+    multisig: &LegacyAminoMultisig,
+    sign_doc: String,
+    body: &Body,
+) -> Result<PartialSignature> {
+    let public_key = signing_key.public_key();
+    if !multisig.public_keys.contains(&public_key) {
+        return Err(eyre::Report::msg(
+            "The signing key is not a member of the multisig",
+        ));
+    }
+    let signature = match signing_key.sign(sign_doc.as_bytes()) {
+        Ok(signature) => signature,
+        Err(e) => {
+            log::error!("Failed to sign transaction: {}", e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to sign transaction: {}",
+                e
+            )));
+        }
+    };
+
+    Ok(PartialSignature {
+        sign_doc,
+        body: STANDARD.encode(body.clone().into_bytes()?),
+        public_key: STANDARD.encode(public_key.to_bytes()),
+        signature: STANDARD.encode(signature.to_bytes()),
+    })
+}
+
+/// Assemble partial signatures from at least the threshold of members into
+/// raw transaction bytes.
+pub fn combine(multisig: &LegacyAminoMultisig, partials: &[PartialSignature]) -> Result<Vec<u8>> {
+    let Some(first) = partials.first() else {
+        return Err(eyre::Report::msg("No partial signatures given"));
+    };
+    if let Some(other) = partials
+        .iter()
+        .find(|partial| partial.sign_doc != first.sign_doc || partial.body != first.body)
+    {
+        return Err(eyre::Report::msg(format!(
+            "Partial signatures cover different transactions; {} signed {}, expected {}",
+            other.public_key, other.sign_doc, first.sign_doc
+        )));
+    }
+
+    // Order the signatures like the member keys, skipping duplicates
+    let mut signatures: Vec<Option<Vec<u8>>> = vec![None; multisig.public_keys.len()];
+    for partial in partials {
+        let public_key = decode(&partial.public_key)?;
+        let Some(index) = multisig
+            .public_keys
+            .iter()
+            .position(|key| key.to_bytes() == public_key)
+        else {
+            return Err(eyre::Report::msg(format!(
+                "{} is not a member of the multisig",
+                partial.public_key
+            )));
+        };
+        let signature = decode(&partial.signature)?;
+        if !verify(&public_key, partial.sign_doc.as_bytes(), &signature) {
+            return Err(eyre::Report::msg(format!(
+                "The signature of {} is invalid",
+                partial.public_key
+            )));
+        }
+        signatures[index] = Some(signature);
+    }
+    let signers = signatures
+        .iter()
+        .filter(|signature| signature.is_some())
+        .count();
+    if signers < multisig.threshold as usize {
+        return Err(eyre::Report::msg(format!(
+            "{} of {} required signatures given",
+            signers, multisig.threshold
+        )));
+    }
+
+    let mut bitarray = vec![0u8; multisig.public_keys.len().div_ceil(8)];
+    for (index, _) in signatures.iter().enumerate().filter(|(_, s)| s.is_some()) {
+        bitarray[index / 8] |= 1 << (7 - index % 8);
+    }
+    let signer_info = SignerInfo {
+        public_key: Some(multisig.clone().into()),
+        mode_info: ModeInfo::Multi(Multi {
+            bitarray: CompactBitArray::new((multisig.public_keys.len() % 8) as u32, bitarray),
+            mode_infos: vec![ModeInfo::single(SignMode::LegacyAminoJson); signers],
+        }),
+        sequence: sign_doc_sequence(&first.sign_doc)?,
+    };
+    let auth_info = AuthInfo {
+        fee: sign_doc_fee(&first.sign_doc)?,
+        signer_infos: vec![signer_info],
+    };
+    let multi_signature = MultiSignature {
+        signatures: signatures.into_iter().flatten().collect(),
+    };
+    let tx_raw = TxRaw {
+        body_bytes: decode(&first.body)?,
+        auth_info_bytes: auth_info.into_bytes()?,
+        signatures: vec![multi_signature.encode_to_vec()],
+    };
+
+    Ok(tx_raw.encode_to_vec())
+}
+
+/// The validator whose commission a partial signature withdraws.
+pub fn validator(partial: &PartialSignature) -> Option<String> {
+    let sign_doc: Value = serde_json::from_str(&partial.sign_doc).ok()?;
+    sign_doc["msgs"]
+        .as_array()?
+        .iter()
+        .find_map(|msg| msg["value"]["validator_address"].as_str())
+        .map(str::to_string)
+}
+
+fn verify(public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    match (
+        VerifyingKey::from_sec1_bytes(public_key),
+        Signature::from_slice(signature),
+    ) {
+        (Ok(key), Ok(signature)) => key.verify(message, &signature).is_ok(),
+        _ => false,
+    }
+}
+
+fn sign_doc_sequence(sign_doc: &str) -> Result<u64> {
+    let sign_doc: Value = serde_json::from_str(sign_doc)?;
+    match sign_doc["sequence"].as_str().map(str::parse) {
+        Some(Ok(sequence)) => Ok(sequence),
+        _ => Err(eyre::Report::msg("Sign doc has no valid sequence")),
+    }
+}
+
+/// The fee described by an amino JSON sign doc.
+fn sign_doc_fee(sign_doc: &str) -> Result<Fee> {
+    let sign_doc: Value = serde_json::from_str(sign_doc)?;
+    let fee = &sign_doc["fee"];
+    let invalid = || eyre::Report::msg("Sign doc has no valid fee");
+    let mut amount = Vec::new();
+    for coin in fee["amount"].as_array().ok_or_else(invalid)? {
+        let value: u128 = coin["amount"]
+            .as_str()
+            .and_then(|amount| amount.parse().ok())
+            .ok_or_else(invalid)?;
+        amount.push(Coin::new(
+            value,
+            coin["denom"].as_str().ok_or_else(invalid)?,
+        )?);
+    }
+    let address = |field: &str| -> Result<Option<AccountId>> {
+        match fee[field].as_str() {
+            Some(address) => Ok(Some(address.parse()?)),
+            None => Ok(None),
+        }
+    };
+
+    Ok(Fee {
+        amount,
+        gas_limit: fee["gas"]
+            .as_str()
+            .and_then(|gas| gas.parse().ok())
+            .ok_or_else(invalid)?,
+        payer: address("payer")?,
+        granter: address("granter")?,
+    })
+}
+
+fn decode(input: &str) -> Result<Vec<u8>> {
+    match STANDARD.decode(input) {
+        Ok(bytes) => Ok(bytes),
+        Err(e) => {
+            log::error!("Failed to decode partial signature: {}", e);
+            Err(eyre::Report::msg(format!(
+                "Failed to decode partial signature: {}",
+                e
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmrs::tendermint::block::Height;
+    use cosmrs::tx::Msg;
+
+    fn member(byte: u8) -> SigningKey {
+        SigningKey::from_slice(&[byte; 32]).unwrap()
+    }
+
+    fn multisig() -> LegacyAminoMultisig {
+        LegacyAminoMultisig {
+            threshold: 2,
+            public_keys: (1..=3).map(|byte| member(byte).public_key()).collect(),
+        }
+    }
+
+    fn body() -> Body {
+        let msg = cosmrs::distribution::MsgWithdrawValidatorCommission {
+            validator_address: address(&multisig(), "sommvaloper").unwrap(),
+        };
+        Body::new(
+            vec![msg.to_any().unwrap()],
+            "Withdraw validator commission",
+            Height::from(0u32),
+        )
+    }
+
+    fn fee() -> Fee {
+        Fee::from_amount_and_gas(Coin::new(1000, "usomm").unwrap(), 200_000u64)
+    }
+
+    #[test]
+    fn renders_canonical_amino_sign_doc() {
+        let sign_doc = amino_sign_doc("sommelier-3", 7, 3, &fee(), &body()).unwrap();
+        assert_eq!(
+            sign_doc,
+            format!(
+                r#"{{"account_number":"7","chain_id":"sommelier-3","fee":{{"amount":[{{"amount":"1000","denom":"usomm"}}],"gas":"200000"}},"memo":"Withdraw validator commission","msgs":[{{"type":"cosmos-sdk/MsgWithdrawValCommission","value":{{"validator_address":"{}"}}}}],"sequence":"3"}}"#,
+                address(&multisig(), "sommvaloper").unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn combines_threshold_of_signatures() {
+        let (multisig, body) = (multisig(), body());
+        let sign_doc = amino_sign_doc("sommelier-3", 7, 3, &fee(), &body).unwrap();
+        let partials: Vec<_> = [3, 1]
+            .into_iter()
+            .map(|byte| sign(&member(byte), &multisig, sign_doc.clone(), &body).unwrap())
+            .collect();
+        assert!(combine(&multisig, &partials[..1]).is_err());
+
+        let tx_raw = TxRaw::decode(combine(&multisig, &partials).unwrap().as_slice()).unwrap();
+        let auth_info =
+            cosmrs::proto::cosmos::tx::v1beta1::AuthInfo::decode(tx_raw.auth_info_bytes.as_slice())
+                .unwrap();
+        let Some(cosmrs::proto::cosmos::tx::v1beta1::mode_info::Sum::Multi(multi)) =
+            auth_info.signer_infos[0].mode_info.clone().unwrap().sum
+        else {
+            panic!("expected multisig mode info");
+        };
+        assert_eq!(multi.bitarray.unwrap().elems, [0b1010_0000]);
+        assert_eq!(auth_info.signer_infos[0].sequence, 3);
+        assert_eq!(
+            MultiSignature::decode(tx_raw.signatures[0].as_slice())
+                .unwrap()
+                .signatures[0],
+            decode(&partials[1].signature).unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_non_members() {
+        let body = body();
+        let sign_doc = amino_sign_doc("sommelier-3", 7, 3, &fee(), &body).unwrap();
+        assert!(sign(&member(4), &multisig(), sign_doc, &body).is_err());
+    }
+}
@@ -0,0 +1,103 @@
+use crate::keys;
+use ::keyring::Entry;
+use cosmrs::crypto::secp256k1::SigningKey;
+use eyre::Result;
+
+/// Service the signing keys are stored under in the OS keychain.
+const SERVICE: &str = "withdraw-commission";
+
+/// Where the signing key is read from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum KeyBackend {
+    /// A key file, mnemonic or `file` keyring given by the other key flags
+    #[default]
+    File,
+    /// The macOS Keychain, Linux Secret Service or Windows Credential Manager
+    Os,
+}
+
+/// Load the signing key stored as `name` in the OS keychain.
+pub fn load_key(name: &str) -> Result<SigningKey> {
+    load(&entry(name)?, name)
+}
+
+/// Store the hex-encoded `private_key` as `name` in the OS keychain,
+/// replacing any key already stored under that name.
+pub fn store_key(name: &str, private_key: &str) -> Result<()> {
+    store(&entry(name)?, name, private_key)
+}
+
+fn entry(name: &str) -> Result<Entry> {
+    match Entry::new(SERVICE, name) {
+        Ok(entry) => Ok(entry),
+        Err(e) => {
+            log::error!("Failed to open OS keychain entry: {}", e);
+            Err(eyre::Report::msg(format!(
+                "Failed to open OS keychain entry {}: {}",
+                name, e
+            )))
+        }
+    }
+}
+
+fn load(entry: &Entry, name: &str) -> Result<SigningKey> {
+    let private_key = match entry.get_password() {
+        Ok(private_key) => private_key,
+        Err(e) => {
+            log::error!("Failed to read key from OS keychain: {}", e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to read key {} from OS keychain: {}",
+                name, e
+            )));
+        }
+    };
+    signing_key(&private_key)
+}
+
+/// The signing key of a hex-encoded private key, parsed like a key file.
+fn signing_key(private_key: &str) -> Result<SigningKey> {
+    // Keys are stored as plain hex, the keychain itself protects them
+    keys::parse_key(private_key, || {
+        log::error!("Encrypted keys cannot be stored in the OS keychain");
+        Err(eyre::Report::msg(
+            "Encrypted keys cannot be stored in the OS keychain; store the hex key instead",
+        ))
+    })
+}
+
+fn store(entry: &Entry, name: &str, private_key: &str) -> Result<()> {
+    // Refuse to store something that cannot be loaded again
+    signing_key(private_key)?;
+    // Stored as hex, like key files, so the keychain's own tools show
+    // something that can be pasted back into a key file
+    match entry.set_password(private_key.trim()) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            log::error!("Failed to store key in OS keychain: {}", e);
+            Err(eyre::Report::msg(format!(
+                "Failed to store key {} in OS keychain: {}",
+                name, e
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stores_and_loads_hex_keys() {
+        ::keyring::set_default_credential_builder(::keyring::mock::default_credential_builder());
+        let entry = entry("validator").unwrap();
+        assert!(load(&entry, "validator").is_err());
+
+        assert!(store(&entry, "validator", "not hex").is_err());
+        assert!(store(&entry, "validator", "{\"crypto\": {}}").is_err());
+        let private_key = "07".repeat(32);
+        store(&entry, "validator", &format!("{}\n", private_key)).unwrap();
+        let loaded = load(&entry, "validator").unwrap();
+        let signing_key = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        assert_eq!(loaded.public_key(), signing_key.public_key());
+    }
+}
@@ -0,0 +1,129 @@
+use crate::abci::{self, TxEvent};
+use crate::failure::{self, FailureClass};
+use cosmrs::rpc::endpoint::broadcast::tx_commit;
+
+/// What happened during a run, as reported to result sinks.
+#[derive(Debug, Clone, Default)]
+pub struct RunOutcome {
+    pub chain_id: String,
+    pub validator: String,
+    pub success: bool,
+    pub tx_hash: String,
+    pub height: u64,
+    pub code: u32,
+    pub log: String,
+    /// Commission withdrawn, as reported by the `withdraw_commission` event.
+    pub amount: String,
+    /// Base units that rounding shares down left in the account since the
+    /// last sweep, as a coin.
+    pub dust: String,
+    pub events: Vec<TxEvent>,
+}
+
+impl RunOutcome {
+    pub fn status(&self) -> &'static str {
+        if self.success {
+            "success"
+        } else {
+            "failure"
+        }
+    }
+
+    /// Why the run failed, or `None` if it succeeded.
+    pub fn failure(&self) -> Option<FailureClass> {
+        (!self.success).then(|| failure::classify(self.code, &self.log))
+    }
+
+    /// Fill in the result of a committed broadcast.
+    pub fn record(&mut self, response: &tx_commit::Response) {
+        self.success = response.check_tx.code.is_ok() && response.tx_result.code.is_ok();
+        self.tx_hash = response.hash.to_string();
+        self.height = response.height.value();
+        (self.code, self.log) = if response.check_tx.code.is_err() {
+            (
+                response.check_tx.code.value(),
+                response.check_tx.log.clone(),
+            )
+        } else {
+            (
+                response.tx_result.code.value(),
+                response.tx_result.log.clone(),
+            )
+        };
+        self.events = abci::decode(&response.tx_result.events, &response.tx_result.log);
+        self.amount = abci::withdrawn_amount(&self.events).unwrap_or_default();
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "chain_id": self.chain_id,
+            "validator": self.validator,
+            "status": self.status(),
+            "tx_hash": self.tx_hash,
+            "height": self.height,
+            "code": self.code,
+            "log": self.log,
+            "amount": self.amount,
+            "dust": self.dust,
+            "events": self.events.iter().map(TxEvent::to_json).collect::<Vec<_>>(),
+            "failure": self.failure().map(|class| serde_json::json!({
+                "class": class.as_str(),
+                "remediation": class.remediation(),
+                "doc": class.doc_link(),
+            })),
+            "build": crate::build_info::to_json(),
+        })
+    }
+}
+
+/// Destinations that receive the outcome of a run.
+#[derive(Debug, Clone, Default)]
+pub struct Sinks {
+    #[cfg(feature = "callback")]
+    pub callback: Option<crate::callback::Callback>,
+    #[cfg(feature = "grafana")]
+    pub grafana: Option<crate::grafana::Grafana>,
+    #[cfg(feature = "pushgateway")]
+    pub pushgateway: Option<crate::pushgateway::Pushgateway>,
+    #[cfg(feature = "statsd")]
+    pub statsd: Option<crate::statsd::Statsd>,
+}
+
+impl Sinks {
+    /// Deliver the outcome to every configured sink. Delivery failures are
+    /// logged but never fail the run.
+    #[cfg_attr(
+        not(any(feature = "callback", feature = "statsd")),
+        allow(unused_variables)
+    )]
+    pub async fn report(&self, outcome: &RunOutcome) {
+        #[cfg(feature = "callback")]
+        if let Some(callback) = &self.callback {
+            if let Err(e) = crate::callback::send(callback, outcome).await {
+                log::error!("{}", e);
+            }
+        }
+
+        // Mark successful withdrawals on dashboards
+        #[cfg(feature = "grafana")]
+        if let (Some(grafana), true) = (&self.grafana, outcome.success) {
+            if let Err(e) = crate::grafana::annotate(grafana, outcome).await {
+                log::error!("{}", e);
+            }
+        }
+
+        #[cfg(feature = "pushgateway")]
+        if let Some(pushgateway) = &self.pushgateway {
+            if let Err(e) = crate::pushgateway::push(pushgateway, outcome).await {
+                log::error!("{}", e);
+            }
+        }
+
+        #[cfg(feature = "statsd")]
+        if let Some(statsd) = &self.statsd {
+            if let Err(e) = crate::statsd::send(statsd, outcome).await {
+                log::error!("{}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use eyre::Result;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A deterministic, reviewable description of the transaction the tool would
+/// broadcast. Entries are kept sorted so that two plans for the same inputs
+/// always render identically and can be diffed line by line.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Plan {
+    entries: BTreeMap<String, String>,
+}
+
+/// A single difference between a plan and its baseline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Add(String, String),
+    Remove(String, String),
+    Modify(String, String, String),
+    Keep(String, String),
+}
+
+impl Plan {
+    pub fn set(&mut self, key: impl Into<String>, value: impl ToString) {
+        self.entries.insert(key.into(), value.to_string());
+    }
+
+    /// Parse a plan previously written with `Plan::render`.
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut plan = Plan::default();
+        for (i, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            // Keys never contain " = ", so the first one ends the key
+            let Some((key, value)) = line.split_once(" = ") else {
+                return Err(eyre::Report::msg(format!(
+                    "Invalid plan entry on line {}: {}",
+                    i + 1,
+                    line
+                )));
+            };
+            let value = match unquote(value) {
+                Ok(value) => value,
+                Err(e) => {
+                    return Err(eyre::Report::msg(format!(
+                        "Invalid plan value on line {}: {}",
+                        i + 1,
+                        e
+                    )))
+                }
+            };
+            plan.set(key.trim(), value);
+        }
+
+        Ok(plan)
+    }
+
+    pub fn render(&self) -> String {
+        self.entries
+            .iter()
+            .map(|(key, value)| format!("{} = {}\n", key, quote(value)))
+            .collect()
+    }
+
+    /// Compare this plan against a baseline, returning every entry in key order.
+    pub fn diff(&self, baseline: &Plan) -> Vec<Change> {
+        let mut keys: Vec<&String> = self.entries.keys().chain(baseline.entries.keys()).collect();
+        keys.sort();
+        keys.dedup();
+
+        keys.into_iter()
+            .map(
+                |key| match (baseline.entries.get(key), self.entries.get(key)) {
+                    (None, Some(new)) => Change::Add(key.clone(), new.clone()),
+                    (Some(old), None) => Change::Remove(key.clone(), old.clone()),
+                    (Some(old), Some(new)) if old != new => {
+                        Change::Modify(key.clone(), old.clone(), new.clone())
+                    }
+                    (_, Some(new)) => Change::Keep(key.clone(), new.clone()),
+                    (None, None) => unreachable!("key comes from one of the plans"),
+                },
+            )
+            .collect()
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Add(key, value) => write!(f, "+ {} = {}", key, quote(value)),
+            Change::Remove(key, value) => write!(f, "- {} = {}", key, quote(value)),
+            Change::Modify(key, old, new) => {
+                write!(f, "~ {} = {} -> {}", key, quote(old), quote(new))
+            }
+            Change::Keep(key, value) => write!(f, "  {} = {}", key, quote(value)),
+        }
+    }
+}
+
+/// A value as written in a plan: as is when it reads back unchanged, and as a
+/// JSON string otherwise, e.g. an empty memo or one with a line break or
+/// surrounding spaces.
+fn quote(value: &str) -> String {
+    let plain = !value.is_empty()
+        && !value.starts_with('"')
+        && value.trim() == value
+        && !value.chars().any(char::is_control);
+    match plain {
+        true => value.to_string(),
+        false => serde_json::Value::from(value).to_string(),
+    }
+}
+
+/// The value a plan entry was written from by [`quote`].
+fn unquote(value: &str) -> Result<String> {
+    let value = value.trim();
+    match value.starts_with('"') {
+        true => Ok(serde_json::from_str(value)?),
+        false => Ok(value.to_string()),
+    }
+}
+
+/// Summarize a diff the way terraform does, e.g. "1 to add, 0 to change, 2 to remove".
+pub fn summarize(changes: &[Change]) -> String {
+    let (mut add, mut modify, mut remove) = (0, 0, 0);
+    for change in changes {
+        match change {
+            Change::Add(..) => add += 1,
+            Change::Modify(..) => modify += 1,
+            Change::Remove(..) => remove += 1,
+            Change::Keep(..) => {}
+        }
+    }
+
+    format!("{} to add, {} to change, {} to remove", add, modify, remove)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_any_memo() {
+        let mut plan = Plan::default();
+        for memo in [
+            "Withdraw validator commission",
+            "a = b",
+            "two\nlines",
+            " padded ",
+            "\"quoted\"",
+            "",
+        ] {
+            plan.set("memo", memo);
+            plan.set("fee.amount.0", "1000usomm");
+            let parsed = Plan::parse(&plan.render()).unwrap();
+            assert_eq!(parsed, plan, "memo {:?}", memo);
+            assert!(parsed
+                .diff(&plan)
+                .iter()
+                .all(|change| matches!(change, Change::Keep(..))));
+        }
+        assert_eq!(plan.render().lines().count(), 2);
+    }
+
+    #[test]
+    fn diffs_against_a_baseline() {
+        let mut baseline = Plan::default();
+        baseline.set("fee.amount.0", "1000usomm");
+        baseline.set("payout.somm1old", "10%");
+        baseline.set("memo", "same");
+        let mut plan = baseline.clone();
+        plan.set("fee.amount.0", "1000uatom");
+        plan.entries.remove("payout.somm1old");
+        plan.set("payout.somm1new", "10%");
+
+        let changes = plan.diff(&baseline);
+        assert_eq!(
+            changes.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "~ fee.amount.0 = 1000usomm -> 1000uatom",
+                "  memo = same",
+                "+ payout.somm1new = 10%",
+                "- payout.somm1old = 10%",
+            ]
+        );
+        assert_eq!(summarize(&changes), "1 to add, 1 to change, 1 to remove");
+    }
+}
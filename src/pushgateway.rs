@@ -0,0 +1,130 @@
+use crate::outcome::RunOutcome;
+use eyre::Result;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Prometheus job the metrics are pushed under.
+const JOB: &str = "withdraw-commission";
+
+/// A Prometheus Pushgateway that receives the metrics of every run, so
+/// scheduled one-shot runs still produce time series.
+#[derive(Debug, Clone)]
+pub struct Pushgateway {
+    pub url: String,
+}
+
+/// Push the run's metrics, grouped by chain and validator.
+pub async fn push(pushgateway: &Pushgateway, outcome: &RunOutcome) -> Result<()> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    // POST only replaces the metrics it sends, so a failed run keeps the
+    // last success timestamp of the group
+    let request = reqwest::Client::new()
+        .post(format!(
+            "{}/metrics/job/{}/chain_id/{}/validator/{}",
+            pushgateway.url.trim_end_matches('/'),
+            JOB,
+            outcome.chain_id,
+            outcome.validator
+        ))
+        .header(reqwest::header::CONTENT_TYPE, "text/plain; version=0.0.4")
+        .body(exposition(outcome, now));
+
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            return Err(eyre::Report::msg(format!(
+                "Failed to push metrics to the Pushgateway: {}",
+                e
+            )));
+        }
+    };
+    if !response.status().is_success() {
+        return Err(eyre::Report::msg(format!(
+            "Pushgateway returned {}",
+            response.status()
+        )));
+    }
+
+    Ok(())
+}
+
+/// The run's metrics in the Prometheus text format, for a run at `now`
+/// seconds since the epoch.
+fn exposition(outcome: &RunOutcome, now: u64) -> String {
+    let mut metrics = vec![
+        format!(
+            "# TYPE withdraw_commission_last_run_timestamp_seconds gauge\n\
+             withdraw_commission_last_run_timestamp_seconds {}\n",
+            now
+        ),
+        format!(
+            "# TYPE withdraw_commission_last_run_success gauge\n\
+             withdraw_commission_last_run_success {}\n",
+            u8::from(outcome.success)
+        ),
+    ];
+    if outcome.success {
+        metrics.push(format!(
+            "# TYPE withdraw_commission_last_success_timestamp_seconds gauge\n\
+             withdraw_commission_last_success_timestamp_seconds {}\n",
+            now
+        ));
+        let coins = outcome
+            .amount
+            .split(',')
+            .filter_map(|coin| {
+                let split = coin.find(|c: char| !c.is_ascii_digit())?;
+                let (amount, denom) = coin.split_at(split);
+                (!amount.is_empty()).then(|| {
+                    format!(
+                        "withdraw_commission_last_withdrawn_amount{{denom=\"{}\"}} {}\n",
+                        denom, amount
+                    )
+                })
+            })
+            .collect::<String>();
+        if !coins.is_empty() {
+            metrics.push(format!(
+                "# TYPE withdraw_commission_last_withdrawn_amount gauge\n{}",
+                coins
+            ));
+        }
+    }
+
+    metrics.concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exposes_withdrawn_coins() {
+        let outcome = RunOutcome {
+            success: true,
+            amount: "1500usomm,7ibc/27394FB0".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            exposition(&outcome, 1_800_000_000),
+            "# TYPE withdraw_commission_last_run_timestamp_seconds gauge\n\
+             withdraw_commission_last_run_timestamp_seconds 1800000000\n\
+             # TYPE withdraw_commission_last_run_success gauge\n\
+             withdraw_commission_last_run_success 1\n\
+             # TYPE withdraw_commission_last_success_timestamp_seconds gauge\n\
+             withdraw_commission_last_success_timestamp_seconds 1800000000\n\
+             # TYPE withdraw_commission_last_withdrawn_amount gauge\n\
+             withdraw_commission_last_withdrawn_amount{denom=\"usomm\"} 1500\n\
+             withdraw_commission_last_withdrawn_amount{denom=\"ibc/27394FB0\"} 7\n"
+        );
+    }
+
+    #[test]
+    fn failed_run_keeps_last_success() {
+        let exposition = exposition(&RunOutcome::default(), 1_800_000_000);
+        assert!(exposition.contains("withdraw_commission_last_run_success 0\n"));
+        assert!(!exposition.contains("last_success_timestamp"));
+    }
+}
@@ -0,0 +1,70 @@
+use crate::endpoint::{at_height, GrpcChannel};
+use cosmrs::proto::cosmos::distribution::v1beta1::{
+    query_client::QueryClient as DistributionQueryClient, QueryValidatorSlashesRequest,
+};
+use cosmrs::proto::cosmos::staking::v1beta1::{
+    query_client::QueryClient as StakingQueryClient, QueryValidatorRequest,
+};
+use eyre::Result;
+
+/// Collect reasons why an automated withdrawal should be held for human review:
+/// the validator is currently jailed, or it was slashed within the last
+/// `lookback_blocks` blocks. An empty result means nothing looks wrong.
+pub async fn slashing_concerns(
+    channel: GrpcChannel,
+    validator_operator_address: &str,
+    latest_height: u64,
+    lookback_blocks: u64,
+) -> Result<Vec<String>> {
+    let mut concerns = Vec::new();
+
+    let mut staking_client = StakingQueryClient::new(channel.clone());
+    let request = at_height(
+        QueryValidatorRequest {
+            validator_addr: validator_operator_address.to_string(),
+        },
+        latest_height,
+    );
+    let validator = match staking_client.validator(request).await {
+        Ok(response) => response.into_inner().validator,
+        Err(e) => {
+            log::error!("Failed to query validator: {}", e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to query validator: {}",
+                e
+            )));
+        }
+    };
+    if validator.is_some_and(|validator| validator.jailed) {
+        concerns.push("validator is jailed".to_string());
+    }
+
+    let mut distribution_client = DistributionQueryClient::new(channel);
+    let request = at_height(
+        QueryValidatorSlashesRequest {
+            validator_address: validator_operator_address.to_string(),
+            starting_height: latest_height.saturating_sub(lookback_blocks),
+            ending_height: latest_height,
+            pagination: None,
+        },
+        latest_height,
+    );
+    let slashes = match distribution_client.validator_slashes(request).await {
+        Ok(response) => response.into_inner().slashes,
+        Err(e) => {
+            log::error!("Failed to query validator slashes: {}", e);
+            return Err(eyre::Report::msg(format!(
+                "Failed to query validator slashes: {}",
+                e
+            )));
+        }
+    };
+    for slash in slashes {
+        concerns.push(format!(
+            "validator was slashed (period {}, fraction {})",
+            slash.validator_period, slash.fraction
+        ));
+    }
+
+    Ok(concerns)
+}
@@ -0,0 +1,71 @@
+use eyre::Result;
+use hyper_util::rt::TokioIo;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::net::TcpStream;
+use tokio_socks::tcp::Socks5Stream;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Parse a `socks5://host:port` or `socks5h://host:port` proxy URL into the
+/// proxy's `host:port`. Host names are always resolved by the proxy, so `.onion`
+/// endpoints work and no DNS queries leak around Tor.
+pub fn parse_proxy(input: &str) -> Result<String> {
+    let address = input
+        .strip_prefix("socks5h://")
+        .or_else(|| input.strip_prefix("socks5://"));
+    match address.map(|address| address.trim_end_matches('/')) {
+        Some(address) if address.contains(':') => Ok(address.to_string()),
+        _ => Err(eyre::Report::msg(format!(
+            "Invalid proxy {:?}, expected socks5h://host:port",
+            input
+        ))),
+    }
+}
+
+/// A connector that opens every connection through a SOCKS5 proxy.
+#[derive(Debug, Clone)]
+pub struct SocksConnector {
+    proxy: String,
+}
+
+impl SocksConnector {
+    pub fn new(proxy: &str) -> Self {
+        Self {
+            proxy: proxy.to_string(),
+        }
+    }
+}
+
+impl tower::Service<http::Uri> for SocksConnector {
+    type Response = TokioIo<TcpStream>;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: http::Uri) -> Self::Future {
+        let proxy = self.proxy.clone();
+        Box::pin(async move {
+            let host = uri.host().ok_or("URL has no host")?.to_string();
+            let port = uri.port_u16().unwrap_or(match uri.scheme_str() {
+                Some("https") => 443,
+                _ => 80,
+            });
+            let stream = Socks5Stream::connect(proxy.as_str(), (host, port)).await?;
+
+            Ok(TokioIo::new(stream.into_inner()))
+        })
+    }
+}
+
+/// The proxy URL for HTTP clients, forcing remote name resolution.
+pub fn reqwest_proxy(proxy: &str) -> Result<reqwest::Proxy> {
+    match reqwest::Proxy::all(format!("socks5h://{}", proxy)) {
+        Ok(proxy) => Ok(proxy),
+        Err(e) => Err(eyre::Report::msg(format!("Invalid proxy {}: {}", proxy, e))),
+    }
+}
@@ -0,0 +1,172 @@
+use crate::commission::parse_percent;
+use crate::decimal::{round, Decimal, Rounding};
+use eyre::Result;
+
+/// Parse an `<address>:<weight>` share, such as `sommvaloper1...:40`.
+pub fn parse_weight(input: &str) -> Result<(String, u64)> {
+    match input.rsplit_once(':') {
+        Some((address, weight)) if !address.trim().is_empty() => {
+            match weight.trim().parse::<u64>() {
+                Ok(weight) if weight > 0 => Ok((address.trim().to_string(), weight)),
+                _ => Err(eyre::Report::msg(format!(
+                    "Invalid weight in {:?}, expected a positive integer",
+                    input
+                ))),
+            }
+        }
+        _ => Err(eyre::Report::msg(format!(
+            "Invalid share {:?}, expected \"<address>:<weight>\"",
+            input
+        ))),
+    }
+}
+
+/// Parse an `<address>` or `<address>:<weight>` rotation entry; the weight
+/// defaults to 1.
+pub fn parse_rotation(input: &str) -> Result<(String, u64)> {
+    if input.contains(':') {
+        parse_weight(input)
+    } else if input.trim().is_empty() {
+        Err(eyre::Report::msg("Empty rotation entry"))
+    } else {
+        Ok((input.trim().to_string(), 1))
+    }
+}
+
+/// Parse an `<address>:<percent>` share, such as `somm1...:40%`.
+pub fn parse_percent_share(input: &str) -> Result<(String, Decimal)> {
+    match input.rsplit_once(':') {
+        Some((address, percent)) if !address.trim().is_empty() => {
+            Ok((address.trim().to_string(), parse_percent(percent.trim())?))
+        }
+        _ => Err(eyre::Report::msg(format!(
+            "Invalid share {:?}, expected \"<address>:<percent>%\"",
+            input
+        ))),
+    }
+}
+
+/// Split `amount` in proportion to `weights`, rounding every part down so the
+/// parts never add up to more than `amount`.
+pub fn split(amount: u128, weights: &[u64]) -> Vec<u128> {
+    let total: u128 = weights.iter().map(|weight| *weight as u128).sum();
+    weights
+        .iter()
+        .map(|weight| match total {
+            0 => Some(0),
+            // A part is at most `amount`, and splitting off the whole
+            // multiples of `total` keeps the products within u128
+            total => {
+                let weight = *weight as u128;
+                let remainder = (amount % total).checked_mul(weight)?;
+                round(
+                    amount / total * weight + remainder / total,
+                    remainder % total,
+                    total,
+                    Rounding::Down,
+                )
+            }
+        })
+        .collect::<Option<_>>()
+        .unwrap_or_else(|| vec![0; weights.len()])
+}
+
+/// What happens to the base units that rounding every share down leaves
+/// over.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DustPolicy {
+    /// Leave it in the account
+    #[default]
+    Leave,
+    /// Add it to the first share
+    First,
+    /// Leave it in the account until it adds up to the threshold, then add
+    /// all of it to the first share
+    Accumulate,
+}
+
+/// How much of `dust`, plus the `carried` dust of earlier splits, goes to the
+/// first share under `policy`, and how much is left in the account.
+pub fn settle_dust(policy: DustPolicy, dust: u128, carried: u128, threshold: u128) -> (u128, u128) {
+    match policy {
+        DustPolicy::Leave => (0, carried.saturating_add(dust)),
+        DustPolicy::First => (dust, carried),
+        DustPolicy::Accumulate => {
+            let total = carried.saturating_add(dust);
+            if total > 0 && total >= threshold {
+                (total, 0)
+            } else {
+                (0, total)
+            }
+        }
+    }
+}
+
+/// The entry whose turn `counter` is when entries take turns in proportion
+/// to `weights`, or `None` without entries.
+pub fn rotate(weights: &[u64], counter: u64) -> Option<usize> {
+    let total: u64 = weights.iter().sum();
+    if total == 0 {
+        return None;
+    }
+    let mut position = counter % total;
+    weights.iter().position(|weight| {
+        if position < *weight {
+            true
+        } else {
+            position -= weight;
+            false
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_by_weight_rounding_down() {
+        assert_eq!(split(1000, &[40, 60]), vec![400, 600]);
+        assert_eq!(split(10, &[1, 1, 1]), vec![3, 3, 3]);
+        assert_eq!(split(10, &[]), Vec::<u128>::new());
+        assert_eq!(
+            split(u128::MAX, &[1, u64::MAX]),
+            vec![u64::MAX as u128, 340282366920938463444927863358058659839]
+        );
+    }
+
+    #[test]
+    fn settles_dust_by_policy() {
+        assert_eq!(settle_dust(DustPolicy::Leave, 2, 5, 10), (0, 7));
+        assert_eq!(settle_dust(DustPolicy::First, 2, 5, 10), (2, 5));
+        assert_eq!(settle_dust(DustPolicy::Accumulate, 2, 5, 10), (0, 7));
+        assert_eq!(settle_dust(DustPolicy::Accumulate, 2, 8, 10), (10, 0));
+        assert_eq!(settle_dust(DustPolicy::Accumulate, 0, 0, 0), (0, 0));
+    }
+
+    #[test]
+    fn rotates_in_proportion_to_weights() {
+        let turns = (0..6).map(|counter| rotate(&[1, 2], counter).unwrap());
+        assert_eq!(turns.collect::<Vec<_>>(), vec![0, 1, 1, 0, 1, 1]);
+        assert_eq!(rotate(&[], 3), None);
+        assert_eq!(
+            parse_rotation("somm1abc").unwrap(),
+            ("somm1abc".to_string(), 1)
+        );
+    }
+
+    #[test]
+    fn parses_weights() {
+        assert_eq!(
+            parse_weight("sommvaloper1abc:40").unwrap(),
+            ("sommvaloper1abc".to_string(), 40)
+        );
+        assert!(parse_weight("sommvaloper1abc").is_err());
+        assert!(parse_weight("sommvaloper1abc:0").is_err());
+        assert_eq!(
+            parse_percent_share("somm1abc:12.5%").unwrap(),
+            ("somm1abc".to_string(), "12.5".parse().unwrap())
+        );
+        assert!(parse_percent_share("somm1abc:140%").is_err());
+    }
+}
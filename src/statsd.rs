@@ -0,0 +1,131 @@
+use crate::outcome::RunOutcome;
+use eyre::Result;
+use tokio::net::UdpSocket;
+
+/// Prefix of every metric name.
+const PREFIX: &str = "withdraw_commission";
+
+/// A StatsD or DogStatsD agent that receives the metrics of every run.
+#[derive(Debug, Clone)]
+pub struct Statsd {
+    /// Agent address as host:port
+    pub addr: String,
+    /// Extra tags sent with every metric, as key:value
+    pub tags: Vec<String>,
+}
+
+/// Send the run's metrics, tagged with the chain and validator.
+pub async fn send(statsd: &Statsd, outcome: &RunOutcome) -> Result<()> {
+    let socket = match UdpSocket::bind("0.0.0.0:0").await {
+        Ok(socket) => socket,
+        Err(e) => {
+            return Err(eyre::Report::msg(format!(
+                "Failed to open StatsD socket: {}",
+                e
+            )));
+        }
+    };
+    if let Err(e) = socket.connect(&statsd.addr).await {
+        return Err(eyre::Report::msg(format!(
+            "Failed to resolve StatsD address {}: {}",
+            statsd.addr, e
+        )));
+    }
+    // One datagram per metric keeps each under the agent's packet size
+    for line in lines(statsd, outcome) {
+        if let Err(e) = socket.send(line.as_bytes()).await {
+            return Err(eyre::Report::msg(format!(
+                "Failed to send StatsD metric: {}",
+                e
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// The run's metrics in the DogStatsD line format.
+fn lines(statsd: &Statsd, outcome: &RunOutcome) -> Vec<String> {
+    let mut tags = vec![
+        format!("chain_id:{}", outcome.chain_id),
+        format!("validator:{}", outcome.validator),
+    ];
+    tags.extend(statsd.tags.iter().cloned());
+
+    let mut lines = vec![format!(
+        "{}.runs:1|c|#{},status:{}",
+        PREFIX,
+        tags.join(","),
+        outcome.status()
+    )];
+    if let Some(failure) = outcome.failure() {
+        lines.push(format!(
+            "{}.failures:1|c|#{},class:{}",
+            PREFIX,
+            tags.join(","),
+            failure.as_str()
+        ));
+    }
+    for coin in outcome.amount.split(',') {
+        let Some(split) = coin.find(|c: char| !c.is_ascii_digit()) else {
+            continue;
+        };
+        let (amount, denom) = coin.split_at(split);
+        if !amount.is_empty() {
+            lines.push(format!(
+                "{}.withdrawn:{}|c|#{},denom:{}",
+                PREFIX,
+                amount,
+                tags.join(","),
+                denom
+            ));
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_metrics_with_chain_and_validator() {
+        let statsd = Statsd {
+            addr: "127.0.0.1:8125".to_string(),
+            tags: vec!["env:prod".to_string()],
+        };
+        let outcome = RunOutcome {
+            chain_id: "sommelier-3".to_string(),
+            validator: "sommvaloper1abc".to_string(),
+            success: true,
+            amount: "1500usomm".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            lines(&statsd, &outcome),
+            vec![
+                "withdraw_commission.runs:1|c|#chain_id:sommelier-3,validator:sommvaloper1abc,env:prod,status:success",
+                "withdraw_commission.withdrawn:1500|c|#chain_id:sommelier-3,validator:sommvaloper1abc,env:prod,denom:usomm",
+            ]
+        );
+    }
+
+    #[test]
+    fn counts_failures_by_class() {
+        let statsd = Statsd {
+            addr: "127.0.0.1:8125".to_string(),
+            tags: Vec::new(),
+        };
+        let outcome = RunOutcome {
+            chain_id: "sommelier-3".to_string(),
+            validator: "sommvaloper1abc".to_string(),
+            code: 13,
+            ..Default::default()
+        };
+        assert_eq!(
+            lines(&statsd, &outcome)[1],
+            "withdraw_commission.failures:1|c|#chain_id:sommelier-3,validator:sommvaloper1abc,class:fee"
+        );
+    }
+}
@@ -0,0 +1,576 @@
+use crate::decimal::{Decimal, Rounding};
+use crate::gas::GasTable;
+use cosmrs::bank::MsgSend;
+use cosmrs::distribution::{MsgWithdrawDelegatorReward, MsgWithdrawValidatorCommission};
+use cosmrs::proto::cosmos::authz::v1beta1::MsgExec;
+use cosmrs::proto::cosmos::tx::v1beta1::TxRaw;
+use cosmrs::proto::prost::Message;
+use cosmrs::staking::MsgDelegate;
+use cosmrs::tx::Msg;
+use cosmrs::{
+    crypto::secp256k1::SigningKey,
+    tendermint::{block::Height, chain::Id, Time},
+    tx::{AuthInfo, Body, Fee, SignDoc, SignerInfo},
+    AccountId, Any, Coin,
+};
+use eyre::Result;
+use std::str::FromStr;
+
+/// Memo attached to every withdrawal transaction.
+pub const MEMO: &str = "Withdraw validator commission";
+
+/// Substitute the `{date}` (UTC, YYYY-MM-DD at `now`), `{version}`,
+/// `{validator}` and `{chain_id}` placeholders of a memo template. Unknown
+/// placeholders are left untouched.
+pub fn render_memo(template: &str, validator: &str, chain_id: &str, now: Time) -> String {
+    let date = now.to_rfc3339();
+    [
+        ("date", date.get(..10).unwrap_or_default()),
+        ("version", env!("CARGO_PKG_VERSION")),
+        ("validator", validator),
+        ("chain_id", chain_id),
+    ]
+    .into_iter()
+    .fold(template.to_string(), |memo, (name, value)| {
+        memo.replace(&format!("{{{}}}", name), value)
+    })
+}
+
+/// Parse a fee such as `2500usomm` into its amount and denom.
+pub fn parse_fees(input: &str) -> Result<(u128, String)> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (amount, denom) = input.split_at(split);
+    match amount.parse::<u128>() {
+        Ok(amount) if !denom.is_empty() => Ok((amount, denom.to_string())),
+        _ => Err(eyre::Report::msg(format!(
+            "Invalid fees {:?}, expected an amount followed by a denom, e.g. 2500usomm",
+            input
+        ))),
+    }
+}
+
+/// Parse a gas price such as `0.025usomm` into its amount per unit of gas and
+/// denom.
+pub fn parse_gas_prices(input: &str) -> Result<(Decimal, String)> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(input.len());
+    let (price, denom) = input.split_at(split);
+    match price.parse::<Decimal>() {
+        Ok(price) if !denom.is_empty() => Ok((price, denom.to_string())),
+        _ => Err(eyre::Report::msg(format!(
+            "Invalid gas price {:?}, expected a decimal followed by a denom, e.g. 0.025usomm",
+            input
+        ))),
+    }
+}
+
+/// Type URL of the authz message that executes messages on a granter's behalf.
+pub const MSG_EXEC: &str = "/cosmos.authz.v1beta1.MsgExec";
+
+/// Fee amount paid in `denom`.
+pub const DEFAULT_FEE_AMOUNT: u128 = 1000;
+
+/// Factor applied to simulated gas, 1.3 as in `<daemon> tx --gas auto`
+/// setups.
+pub const DEFAULT_GAS_ADJUSTMENT: Decimal = Decimal::from_atomics(1_300_000_000_000_000_000);
+
+/// Builds and signs the commission withdrawal transaction. Nothing here talks
+/// to the network, so it also backs `--plan`.
+pub struct WithdrawTx {
+    pub signing_key: SigningKey,
+    pub chain_id: String,
+    pub account_prefix: String,
+    pub valoper_prefix: String,
+    pub denom: String,
+    /// Decimal places between the base `denom` and its whole token, used to
+    /// show amounts in whole tokens
+    pub denom_exponent: u32,
+    pub fee_amount: u128,
+    /// Denom the fee is paid in, `denom` if unset
+    pub fee_denom: Option<String>,
+    pub gas_table: GasTable,
+    /// Gas limit that replaces the gas table's estimate
+    pub gas_limit: Option<u64>,
+    /// Price per unit of gas and its denom; when set, the fee is worked out
+    /// from simulated gas instead of `fee_amount` and the gas table
+    pub gas_price: Option<(Decimal, String)>,
+    /// Factor applied to simulated gas to get the gas limit
+    pub gas_adjustment: Decimal,
+    pub timeout_height: u64,
+    pub memo: String,
+    /// Validator to withdraw for through an authz grant to the signing key;
+    /// `None` withdraws for the signing key's own validator
+    pub authz_granter: Option<AccountId>,
+    /// Account whose feegrant allowance pays the fee instead of the signer
+    pub fee_granter: Option<AccountId>,
+    /// Validators the validator's account delegates to whose rewards are
+    /// withdrawn in the same transaction
+    pub reward_validators: Vec<AccountId>,
+    /// Withdrawn commission delegated back to the validator in the same
+    /// transaction
+    pub compound: Option<Coin>,
+    /// Withdrawn rewards delegated to other validators in the same transaction
+    pub delegations: Vec<(AccountId, Coin)>,
+    /// Withdrawn commission sent on to payout addresses in the same transaction
+    pub payouts: Vec<(AccountId, Coin)>,
+    /// ICS-20 transfer of withdrawn commission to another chain, sent last
+    pub ibc_transfer: Option<Any>,
+}
+
+impl WithdrawTx {
+    /// A withdrawal on `chain_id` with Sommelier's prefixes and fee denom.
+    pub fn new(signing_key: SigningKey, chain_id: &str) -> Self {
+        let (account_prefix, valoper_prefix) = crate::chain::prefixes(None, None, None);
+        WithdrawTx {
+            signing_key,
+            chain_id: chain_id.to_string(),
+            account_prefix,
+            valoper_prefix,
+            denom: "usomm".to_string(),
+            denom_exponent: 6,
+            fee_amount: DEFAULT_FEE_AMOUNT,
+            fee_denom: None,
+            gas_table: GasTable::default(),
+            gas_limit: None,
+            gas_price: None,
+            gas_adjustment: DEFAULT_GAS_ADJUSTMENT,
+            timeout_height: 0,
+            memo: MEMO.to_string(),
+            authz_granter: None,
+            fee_granter: None,
+            reward_validators: Vec::new(),
+            compound: None,
+            delegations: Vec::new(),
+            payouts: Vec::new(),
+            ibc_transfer: None,
+        }
+    }
+
+    /// `amount` of `denom` with its value in whole tokens, for logs.
+    pub fn display(&self, amount: u128) -> String {
+        crate::decimal::display(amount, &self.denom, self.denom_exponent)
+    }
+
+    /// The account address of the signing key, which pays the fee.
+    pub fn account_address(&self) -> Result<AccountId> {
+        match self
+            .signing_key
+            .public_key()
+            .account_id(&self.account_prefix)
+        {
+            Ok(address) => Ok(address),
+            Err(e) => {
+                log::error!("Failed to get validator address: {}", e);
+                Err(eyre::Report::msg(format!(
+                    "Failed to get validator address: {}",
+                    e
+                )))
+            }
+        }
+    }
+
+    /// The validator operator address whose commission is withdrawn: the
+    /// signing key's, or the authz granter's.
+    pub fn operator_address(&self) -> Result<AccountId> {
+        let operator_address = match &self.authz_granter {
+            Some(granter) => AccountId::new(&self.valoper_prefix, &granter.to_bytes()),
+            None => self
+                .signing_key
+                .public_key()
+                .account_id(&self.valoper_prefix),
+        };
+        match operator_address {
+            Ok(address) => Ok(address),
+            Err(e) => {
+                log::error!("Failed to get validator operator address: {}", e);
+                Err(eyre::Report::msg(format!(
+                    "Failed to get validator operator address: {}",
+                    e
+                )))
+            }
+        }
+    }
+
+    /// The account address of the validator whose commission is withdrawn.
+    pub fn validator_account_address(&self) -> Result<AccountId> {
+        match &self.authz_granter {
+            Some(granter) => match AccountId::new(&self.account_prefix, &granter.to_bytes()) {
+                Ok(address) => Ok(address),
+                Err(e) => {
+                    log::error!("Failed to get granter address: {}", e);
+                    Err(eyre::Report::msg(format!(
+                        "Failed to get granter address: {}",
+                        e
+                    )))
+                }
+            },
+            None => self.account_address(),
+        }
+    }
+
+    /// The transaction body carrying a `MsgWithdrawValidatorCommission` and a
+    /// `MsgWithdrawDelegatorReward` per reward validator, followed by a
+    /// `MsgDelegate` when compounding, one per delegation, a `MsgSend` per
+    /// payout and the IBC transfer, wrapped in a `MsgExec` when withdrawing
+    /// through an authz grant.
+    pub fn body(&self) -> Result<Body> {
+        self.body_for(self.operator_address()?)
+    }
+
+    /// The transaction body withdrawing the commission of `validator_address`,
+    /// which differs from the signing key's for multisig-controlled validators.
+    pub fn body_for(&self, validator_address: AccountId) -> Result<Body> {
+        // Only the commission withdrawal is executed for the granter; the
+        // rest would need a grant per message type
+        if self.authz_granter.is_some()
+            && (!self.reward_validators.is_empty()
+                || self.compound.is_some()
+                || !self.delegations.is_empty()
+                || !self.payouts.is_empty()
+                || self.ibc_transfer.is_some())
+        {
+            return Err(eyre::Report::msg(
+                "Reward withdrawals, delegations, payouts and IBC transfers cannot be executed through an authz granter",
+            ));
+        }
+        let delegator_address =
+            match AccountId::new(&self.account_prefix, &validator_address.to_bytes()) {
+                Ok(address) => address,
+                Err(e) => {
+                    log::error!("Failed to get delegator address: {}", e);
+                    return Err(eyre::Report::msg(format!(
+                        "Failed to get delegator address: {}",
+                        e
+                    )));
+                }
+            };
+        let commission = MsgWithdrawValidatorCommission {
+            validator_address: validator_address.clone(),
+        }
+        .to_any();
+        let rewards = self.reward_validators.iter().map(|validator_address| {
+            MsgWithdrawDelegatorReward {
+                delegator_address: delegator_address.clone(),
+                validator_address: validator_address.clone(),
+            }
+            .to_any()
+        });
+        let delegations = self
+            .compound
+            .iter()
+            .map(|amount| (&validator_address, amount))
+            .chain(
+                self.delegations
+                    .iter()
+                    .map(|(validator_address, amount)| (validator_address, amount)),
+            )
+            .map(|(validator_address, amount)| {
+                MsgDelegate {
+                    delegator_address: delegator_address.clone(),
+                    validator_address: validator_address.clone(),
+                    amount: amount.clone(),
+                }
+                .to_any()
+            });
+        let payouts = self.payouts.iter().map(|(to_address, amount)| {
+            MsgSend {
+                from_address: delegator_address.clone(),
+                to_address: to_address.clone(),
+                amount: vec![amount.clone()],
+            }
+            .to_any()
+        });
+        let msgs = match std::iter::once(commission)
+            .chain(rewards)
+            .chain(delegations)
+            .chain(payouts)
+            .chain(self.ibc_transfer.iter().cloned().map(Ok))
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(msgs) => msgs,
+            Err(e) => {
+                log::error!("Failed to create any: {}", e);
+                return Err(eyre::Report::msg(format!("Failed to create any: {}", e)));
+            }
+        };
+        let msgs = match &self.authz_granter {
+            Some(_) => vec![Any {
+                type_url: MSG_EXEC.to_string(),
+                value: MsgExec {
+                    grantee: self.account_address()?.to_string(),
+                    msgs,
+                }
+                .encode_to_vec(),
+            }],
+            None => msgs,
+        };
+
+        Ok(Body::new(
+            msgs,
+            &self.memo,
+            Height::try_from(self.timeout_height)?,
+        ))
+    }
+
+    /// The fee for `body`, with gas estimated from the gas table unless a gas
+    /// limit is set, and paid
+    /// through the fee granter's allowance if one is set.
+    pub fn fee(&self, body: &Body) -> Result<Fee> {
+        let denom = self.fee_denom.as_deref().unwrap_or(&self.denom);
+        let coin = match Coin::new(self.fee_amount, denom) {
+            Ok(coin) => coin,
+            Err(e) => {
+                log::error!("Failed to create coin: {}", e);
+                return Err(eyre::Report::msg(format!("Failed to create coin: {}", e)));
+            }
+        };
+        let gas_limit = self.gas_limit.unwrap_or_else(|| {
+            self.gas_table
+                .estimate(body.messages.iter().map(|msg| msg.type_url.as_str()))
+        });
+
+        let mut fee = Fee::from_amount_and_gas(coin, gas_limit);
+        fee.granter = self.fee_granter.clone();
+
+        Ok(fee)
+    }
+
+    /// The fee for a transaction that used `gas_used` gas in simulation: the
+    /// gas limit is `gas_used` times `gas_adjustment` and the fee that gas
+    /// limit times `gas_price`, both rounded up.
+    pub fn simulated_fee(&self, gas_used: u64) -> Result<Fee> {
+        let Some((price, denom)) = &self.gas_price else {
+            return Err(eyre::Report::msg("No gas price set to work out the fee"));
+        };
+        let gas_limit = self
+            .gas_adjustment
+            .mul_integer(gas_used.into(), Rounding::Up)
+            .and_then(|gas_limit| u64::try_from(gas_limit).ok());
+        let Some(gas_limit) = gas_limit else {
+            return Err(eyre::Report::msg(format!(
+                "Gas limit for {} gas used times {} does not fit",
+                gas_used, self.gas_adjustment
+            )));
+        };
+        let Some(amount) = price.mul_integer(gas_limit.into(), Rounding::Up) else {
+            return Err(eyre::Report::msg(format!(
+                "Fee for {} gas at {}{} does not fit",
+                gas_limit, price, denom
+            )));
+        };
+        let coin = match Coin::new(amount, denom) {
+            Ok(coin) => coin,
+            Err(e) => {
+                log::error!("Failed to create coin: {}", e);
+                return Err(eyre::Report::msg(format!("Failed to create coin: {}", e)));
+            }
+        };
+
+        let mut fee = Fee::from_amount_and_gas(coin, gas_limit);
+        fee.granter = self.fee_granter.clone();
+
+        Ok(fee)
+    }
+
+    /// Encode `body` and `fee` with an empty signature, as the node expects for
+    /// gas simulation. Nothing is signed, so this works in read-only mode.
+    pub fn unsigned(&self, body: &Body, fee: Fee, sequence: u64) -> Result<Vec<u8>> {
+        let signer_info = SignerInfo::single_direct(Some(self.signing_key.public_key()), sequence);
+        let auth_info = AuthInfo {
+            fee,
+            signer_infos: vec![signer_info],
+        };
+        let tx_raw = TxRaw {
+            body_bytes: body.clone().into_bytes()?,
+            auth_info_bytes: auth_info.into_bytes()?,
+            signatures: vec![Vec::new()],
+        };
+
+        Ok(tx_raw.encode_to_vec())
+    }
+
+    /// Sign `body` and `fee` for the given account, returning the raw transaction bytes.
+    pub fn sign(
+        &self,
+        body: &Body,
+        fee: Fee,
+        account_number: u64,
+        sequence: u64,
+    ) -> Result<Vec<u8>> {
+        let chain_id = match Id::from_str(&self.chain_id) {
+            Ok(chain_id) => chain_id,
+            Err(e) => {
+                log::error!("Failed to parse chain ID: {}", e);
+                return Err(eyre::Report::msg(format!(
+                    "Failed to parse chain ID: {}",
+                    e
+                )));
+            }
+        };
+
+        // Set up the signer info
+        let signer_info = SignerInfo::single_direct(Some(self.signing_key.public_key()), sequence);
+        let sign_doc = match SignDoc::new(
+            body,
+            &AuthInfo {
+                fee,
+                signer_infos: vec![signer_info],
+            },
+            &chain_id,
+            account_number,
+        ) {
+            Ok(sign_doc) => sign_doc,
+            Err(e) => {
+                log::error!("Failed to create sign doc: {}", e);
+                return Err(eyre::Report::msg(format!(
+                    "Failed to create sign doc: {}",
+                    e
+                )));
+            }
+        };
+
+        // Sign the transaction
+        let tx_raw = match sign_doc.sign(&self.signing_key) {
+            Ok(tx_raw) => tx_raw,
+            Err(e) => {
+                log::error!("Failed to sign transaction: {}", e);
+                return Err(eyre::Report::msg(format!(
+                    "Failed to sign transaction: {}",
+                    e
+                )));
+            }
+        };
+        match tx_raw.to_bytes() {
+            Ok(tx_bytes) => Ok(tx_bytes),
+            Err(e) => {
+                log::error!("Failed to convert transaction to bytes: {}", e);
+                Err(eyre::Report::msg(format!(
+                    "Failed to convert transaction to bytes: {}",
+                    e
+                )))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prices_simulated_gas() {
+        let signing_key = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let mut tx = WithdrawTx::new(signing_key, "sommelier-3");
+        assert!(tx.simulated_fee(100_000).is_err());
+        tx.gas_price = Some(parse_gas_prices("0.025usomm").unwrap());
+        let fee = tx.simulated_fee(123_457).unwrap();
+        // 160494.1 gas and 4012.375usomm, both rounded up
+        assert_eq!(fee.gas_limit, 160_495);
+        assert_eq!(fee.amount[0].amount, 4013);
+        assert_eq!(fee.amount[0].denom.as_ref(), "usomm");
+        assert!(parse_gas_prices("0.025").is_err());
+        assert!(parse_gas_prices("usomm").is_err());
+    }
+
+    #[test]
+    fn parses_fees() {
+        assert_eq!(
+            parse_fees("2500usomm").unwrap(),
+            (2500, "usomm".to_string())
+        );
+        assert_eq!(
+            parse_fees("7ibc/27394FB0").unwrap(),
+            (7, "ibc/27394FB0".to_string())
+        );
+        assert!(parse_fees("usomm").is_err());
+        assert!(parse_fees("2500").is_err());
+    }
+
+    #[test]
+    fn renders_memo_placeholders() {
+        let now = Time::parse_from_rfc3339("2026-10-15T08:30:00Z").unwrap();
+        assert_eq!(
+            render_memo(
+                "commission {validator} {chain_id} {date} v{version} {other}",
+                "sommvaloper1abc",
+                "sommelier-3",
+                now
+            ),
+            format!(
+                "commission sommvaloper1abc sommelier-3 2026-10-15 v{} {{other}}",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    #[test]
+    fn withdraws_commission_and_rewards_together() {
+        let signing_key = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let mut withdraw_tx = WithdrawTx::new(signing_key, "sommelier-3");
+        let validator = withdraw_tx.operator_address().unwrap();
+        withdraw_tx.reward_validators = vec![validator.clone()];
+        assert_eq!(
+            withdraw_tx
+                .body()
+                .unwrap()
+                .messages
+                .iter()
+                .map(|msg| msg.type_url.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "/cosmos.distribution.v1beta1.MsgWithdrawValidatorCommission",
+                "/cosmos.distribution.v1beta1.MsgWithdrawDelegatorReward",
+            ]
+        );
+
+        // Withdrawal grants only cover the commission
+        withdraw_tx.authz_granter = Some(withdraw_tx.account_address().unwrap());
+        assert!(withdraw_tx.body().is_err());
+        withdraw_tx.reward_validators.clear();
+        let body = withdraw_tx.body().unwrap();
+        let exec = MsgExec::decode(body.messages[0].value.as_slice()).unwrap();
+        assert_eq!(
+            exec.msgs
+                .iter()
+                .map(|msg| msg.type_url.as_str())
+                .collect::<Vec<_>>(),
+            vec!["/cosmos.distribution.v1beta1.MsgWithdrawValidatorCommission"]
+        );
+    }
+
+    #[test]
+    fn executes_only_withdrawals_for_the_granter() {
+        let signing_key = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let mut withdraw_tx = WithdrawTx::new(signing_key, "sommelier-3");
+        let validator = withdraw_tx.operator_address().unwrap();
+        let coin = Coin::new(100, "usomm").unwrap();
+        withdraw_tx.compound = Some(coin.clone());
+        withdraw_tx.payouts = vec![(withdraw_tx.account_address().unwrap(), coin)];
+        let body = withdraw_tx.body().unwrap();
+        assert_eq!(
+            body.messages
+                .iter()
+                .map(|msg| msg.type_url.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "/cosmos.distribution.v1beta1.MsgWithdrawValidatorCommission",
+                "/cosmos.staking.v1beta1.MsgDelegate",
+                "/cosmos.bank.v1beta1.MsgSend",
+            ]
+        );
+
+        withdraw_tx.authz_granter = Some(withdraw_tx.account_address().unwrap());
+        assert!(withdraw_tx.body_for(validator.clone()).is_err());
+        withdraw_tx.compound = None;
+        assert!(withdraw_tx.body_for(validator.clone()).is_err());
+        withdraw_tx.payouts.clear();
+        let body = withdraw_tx.body_for(validator).unwrap();
+        assert_eq!(body.messages.len(), 1);
+        assert_eq!(body.messages[0].type_url, MSG_EXEC);
+    }
+}
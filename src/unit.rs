@@ -0,0 +1,121 @@
+use clap::ValueEnum;
+
+/// Scheduler definitions `--generate-unit` can render.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum UnitFormat {
+    /// A systemd service plus timer pair
+    SystemdTimer,
+    /// A Nomad periodic batch job
+    Nomad,
+}
+
+impl UnitFormat {
+    /// Default schedule in the format's own syntax.
+    pub fn default_schedule(&self) -> &'static str {
+        match self {
+            UnitFormat::SystemdTimer => "daily",
+            UnitFormat::Nomad => "@daily",
+        }
+    }
+}
+
+/// Flags that control unit generation and must not end up in the generated command.
+const GENERATOR_FLAGS: &[&str] = &["--generate-unit", "--unit-schedule"];
+
+/// Strip the unit generation flags (and their values) from a command line.
+pub fn command_args(raw_args: &[String]) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut skip_value = false;
+    for arg in raw_args {
+        if skip_value {
+            skip_value = false;
+            continue;
+        }
+        if GENERATOR_FLAGS.contains(&arg.as_str()) {
+            skip_value = true;
+            continue;
+        }
+        if GENERATOR_FLAGS
+            .iter()
+            .any(|flag| arg.starts_with(&format!("{}=", flag)))
+        {
+            continue;
+        }
+        args.push(arg.clone());
+    }
+
+    args
+}
+
+/// Render a unit that runs `binary` with `args` on `schedule`.
+pub fn render(format: UnitFormat, binary: &str, args: &[String], schedule: &str) -> String {
+    match format {
+        UnitFormat::SystemdTimer => {
+            let exec = std::iter::once(binary.to_string())
+                .chain(args.iter().map(|arg| systemd_quote(arg)))
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!(
+                "# withdraw-commission.service
+[Unit]
+Description=Withdraw validator commission
+Wants=network-online.target
+After=network-online.target
+
+[Service]
+Type=oneshot
+ExecStart={exec}
+
+# withdraw-commission.timer
+[Unit]
+Description=Run withdraw-commission on a schedule
+
+[Timer]
+OnCalendar={schedule}
+Persistent=true
+
+[Install]
+WantedBy=timers.target
+"
+            )
+        }
+        UnitFormat::Nomad => {
+            let args = args
+                .iter()
+                .map(|arg| format!("{:?}", arg))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!(
+                "job \"withdraw-commission\" {{
+  type = \"batch\"
+
+  periodic {{
+    crons            = [{schedule:?}]
+    prohibit_overlap = true
+  }}
+
+  group \"withdraw-commission\" {{
+    task \"withdraw-commission\" {{
+      driver = \"raw_exec\"
+
+      config {{
+        command = {binary:?}
+        args    = [{args}]
+      }}
+    }}
+  }}
+}}
+"
+            )
+        }
+    }
+}
+
+/// Quote an argument for an ExecStart line when it contains whitespace or quotes.
+fn systemd_quote(arg: &str) -> String {
+    if arg.is_empty() || arg.contains(|c: char| c.is_whitespace() || c == '"' || c == '\\') {
+        format!("{:?}", arg)
+    } else {
+        arg.to_string()
+    }
+}
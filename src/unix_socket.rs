@@ -0,0 +1,122 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use cosmrs::rpc::{
+    client::CompatMode,
+    dialect::{v0_34, v0_37, Dialect, LatestDialect},
+    endpoint::{broadcast::tx_commit, tx},
+    Client, Error, Response, SimpleRequest,
+};
+use cosmrs::tendermint::Hash;
+use http_body_util::{BodyExt, Full};
+use hyper_util::rt::TokioIo;
+use std::path::{Path, PathBuf};
+use tokio::net::UnixStream;
+use tonic::transport::{Channel, Endpoint};
+
+const SCHEME: &str = "unix://";
+
+/// The socket path of a `unix:///path/to/socket` URL.
+pub fn socket_path(url: &str) -> Option<&Path> {
+    url.strip_prefix(SCHEME).map(Path::new)
+}
+
+/// Connect to a gRPC server listening on a Unix socket.
+pub async fn connect_grpc(path: &Path) -> Result<Channel, tonic::transport::Error> {
+    let path = path.to_path_buf();
+    let connector = tower::service_fn(move |_: http::Uri| {
+        let path = path.clone();
+        async move { Ok::<_, std::io::Error>(TokioIo::new(UnixStream::connect(path).await?)) }
+    });
+
+    // The URI is only used for the :authority header, the connector ignores it
+    Endpoint::from_static("http://localhost")
+        .connect_with_connector(connector)
+        .await
+}
+
+/// A CometBFT RPC client speaking JSON-RPC over HTTP/1.1 on a Unix socket.
+#[derive(Debug, Clone)]
+pub struct UnixRpcClient {
+    path: PathBuf,
+    compat: CompatMode,
+}
+
+impl UnixRpcClient {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            compat: CompatMode::default(),
+        }
+    }
+
+    pub fn set_compat_mode(&mut self, compat: CompatMode) {
+        self.compat = compat;
+    }
+
+    async fn perform_with_dialect<R, S>(&self, request: R, _dialect: S) -> Result<R::Output, Error>
+    where
+        R: SimpleRequest<S>,
+        S: Dialect,
+    {
+        let stream = UnixStream::connect(&self.path).await.map_err(Error::io)?;
+        let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+            .await
+            .map_err(|e| Error::client_internal(e.to_string()))?;
+        tokio::spawn(connection);
+
+        let request = http::Request::post("/")
+            .header(http::header::HOST, "localhost")
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .body(Full::new(Bytes::from(request.into_json())))
+            .map_err(|e| Error::client_internal(e.to_string()))?;
+        let response = sender
+            .send_request(request)
+            .await
+            .map_err(|e| Error::client_internal(e.to_string()))?;
+        if response.status() != http::StatusCode::OK {
+            return Err(Error::client_internal(format!(
+                "HTTP request failed with non-200 status code: {}",
+                response.status()
+            )));
+        }
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .map_err(|e| Error::client_internal(e.to_string()))?
+            .to_bytes();
+
+        R::Response::from_string(&body).map(Into::into)
+    }
+}
+
+// Requests whose encoding differs between Tendermint 0.34 and CometBFT 0.37+
+// follow the compatibility mode, like `HttpClient` does.
+#[async_trait]
+impl Client for UnixRpcClient {
+    async fn perform<R>(&self, request: R) -> Result<R::Output, Error>
+    where
+        R: SimpleRequest,
+    {
+        self.perform_with_dialect(request, LatestDialect).await
+    }
+
+    async fn tx(&self, hash: Hash, prove: bool) -> Result<tx::Response, Error> {
+        let request = tx::Request::new(hash, prove);
+        match self.compat {
+            CompatMode::V0_37 => self.perform_with_dialect(request, v0_37::Dialect).await,
+            CompatMode::V0_34 => self.perform_with_dialect(request, v0_34::Dialect).await,
+        }
+    }
+
+    async fn broadcast_tx_commit<T>(&self, tx: T) -> Result<tx_commit::Response, Error>
+    where
+        T: Into<Vec<u8>> + Send,
+    {
+        let request = tx_commit::Request::new(tx);
+        match self.compat {
+            CompatMode::V0_37 => self.perform_with_dialect(request, v0_37::Dialect).await,
+            CompatMode::V0_34 => self.perform_with_dialect(request, v0_34::Dialect).await,
+        }
+    }
+}
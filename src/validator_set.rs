@@ -0,0 +1,244 @@
+use crate::endpoint::{self, at_height, GrpcChannel};
+use crate::tx::MSG_EXEC;
+use cosmrs::distribution::MsgWithdrawValidatorCommission;
+use cosmrs::proto::cosmos::authz::v1beta1::MsgExec;
+use cosmrs::proto::cosmos::base::query::v1beta1::PageRequest;
+use cosmrs::proto::cosmos::staking::v1beta1::{
+    query_client::QueryClient as StakingQueryClient, QueryValidatorsRequest,
+};
+use cosmrs::proto::prost::Message;
+use cosmrs::tx::Msg;
+use cosmrs::{AccountId, Any};
+use eyre::Result;
+
+/// One way of naming validators for a validator-set wide operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selector {
+    /// A validator operator address
+    Address(String),
+    /// Validators whose moniker matches a `*`/`?` pattern, ignoring case
+    Moniker(String),
+    /// A list of validator operator addresses served over HTTP(S)
+    Url(String),
+}
+
+/// Parse a selector: `moniker:<pattern>`, an `http(s)://` URL, or a
+/// validator operator address.
+pub fn parse_selector(input: &str) -> Result<Selector> {
+    let input = input.trim();
+    if let Some(pattern) = input.strip_prefix("moniker:") {
+        if pattern.is_empty() {
+            return Err(eyre::Report::msg("Moniker pattern is empty"));
+        }
+        return Ok(Selector::Moniker(pattern.to_string()));
+    }
+    if input.starts_with("http://") || input.starts_with("https://") {
+        return Ok(Selector::Url(input.to_string()));
+    }
+    match input.parse::<AccountId>() {
+        Ok(_) => Ok(Selector::Address(input.to_string())),
+        Err(e) => Err(eyre::Report::msg(format!(
+            "Expected moniker:<pattern>, a URL or a validator operator address, got {:?}: {}",
+            input, e
+        ))),
+    }
+}
+
+/// Whether `moniker` matches `pattern`, where `*` stands for any run of
+/// characters and `?` for exactly one. Case is ignored.
+pub fn moniker_matches(pattern: &str, moniker: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let moniker: Vec<char> = moniker.to_lowercase().chars().collect();
+    // Greedy matching that backtracks to the last `*`
+    let (mut p, mut m) = (0, 0);
+    let mut star = None;
+    while m < moniker.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == moniker[m]) {
+            p += 1;
+            m += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, m));
+            p += 1;
+        } else if let Some((star_p, star_m)) = star {
+            p = star_p + 1;
+            m = star_m + 1;
+            star = Some((star_p, star_m + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// The operator addresses of a validator list: one per line with `#`
+/// comments, or a JSON array of strings.
+pub fn parse_list(body: &str) -> Result<Vec<String>> {
+    if body.trim_start().starts_with('[') {
+        return match serde_json::from_str::<Vec<String>>(body) {
+            Ok(list) => Ok(list
+                .into_iter()
+                .map(|entry| entry.trim().to_string())
+                .collect()),
+            Err(e) => Err(eyre::Report::msg(format!("Invalid validator list: {}", e))),
+        };
+    }
+
+    Ok(body
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Every validator of the chain at `height`, as (operator address, moniker).
+pub async fn validators(channel: GrpcChannel, height: u64) -> Result<Vec<(String, String)>> {
+    let mut client = StakingQueryClient::new(channel);
+    let mut validators = Vec::new();
+    let mut next_key = Vec::new();
+    loop {
+        let request = at_height(
+            QueryValidatorsRequest {
+                // Every status, so unbonded validators still get their commission
+                status: String::new(),
+                pagination: Some(PageRequest {
+                    key: next_key,
+                    ..Default::default()
+                }),
+            },
+            height,
+        );
+        let response = match client.validators(request).await {
+            Ok(response) => response.into_inner(),
+            Err(e) => {
+                log::error!("Failed to query validators: {}", e);
+                return Err(eyre::Report::msg(format!(
+                    "Failed to query validators: {}",
+                    e
+                )));
+            }
+        };
+        validators.extend(response.validators.into_iter().map(|validator| {
+            let moniker = validator.description.unwrap_or_default().moniker;
+            (validator.operator_address, moniker)
+        }));
+
+        next_key = response
+            .pagination
+            .map(|page| page.next_key)
+            .unwrap_or_default();
+        if next_key.is_empty() {
+            return Ok(validators);
+        }
+    }
+}
+
+/// Resolve `selectors` against the chain's `validators` into operator
+/// addresses, in selector order and without duplicates. Fetching list URLs
+/// goes through the SOCKS5 `proxy` if set.
+pub async fn resolve(
+    selectors: &[Selector],
+    validators: &[(String, String)],
+    proxy: Option<&str>,
+) -> Result<Vec<String>> {
+    let mut resolved = Vec::new();
+    for selector in selectors {
+        let addresses = match selector {
+            Selector::Address(address) => vec![address.clone()],
+            Selector::Moniker(pattern) => {
+                let matched: Vec<_> = validators
+                    .iter()
+                    .filter(|(_, moniker)| moniker_matches(pattern, moniker))
+                    .map(|(address, _)| address.clone())
+                    .collect();
+                if matched.is_empty() {
+                    log::warn!("No validator moniker matches {:?}", pattern);
+                }
+                matched
+            }
+            Selector::Url(url) => parse_list(&endpoint::fetch(url, proxy).await?)?,
+        };
+        for address in addresses {
+            if !validators.iter().any(|(known, _)| *known == address) {
+                return Err(eyre::Report::msg(format!(
+                    "{} is not a validator on this chain",
+                    address
+                )));
+            }
+            if !resolved.contains(&address) {
+                resolved.push(address);
+            }
+        }
+    }
+
+    Ok(resolved)
+}
+
+/// One authz exec by `grantee` that withdraws the commission of every
+/// validator in `operators`.
+pub fn withdraw_msg(grantee: &AccountId, operators: &[AccountId]) -> Result<Any> {
+    let msgs = match operators
+        .iter()
+        .map(|validator_address| {
+            MsgWithdrawValidatorCommission {
+                validator_address: validator_address.clone(),
+            }
+            .to_any()
+        })
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(msgs) => msgs,
+        Err(e) => {
+            log::error!("Failed to create any: {}", e);
+            return Err(eyre::Report::msg(format!("Failed to create any: {}", e)));
+        }
+    };
+
+    Ok(Any {
+        type_url: MSG_EXEC.to_string(),
+        value: MsgExec {
+            grantee: grantee.to_string(),
+            msgs,
+        }
+        .encode_to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_moniker_patterns() {
+        assert!(moniker_matches("acme-*", "ACME-Cosmos"));
+        assert!(moniker_matches("*node?", "white label node1"));
+        assert!(moniker_matches("*a*b*", "xaxxbx"));
+        assert!(!moniker_matches("acme-*", "not-acme-cosmos"));
+        assert!(!moniker_matches("node?", "node"));
+        assert!(moniker_matches("*", ""));
+    }
+
+    #[test]
+    fn parses_selectors_and_lists() {
+        assert_eq!(
+            parse_selector("moniker:acme-*").unwrap(),
+            Selector::Moniker("acme-*".to_string())
+        );
+        assert_eq!(
+            parse_selector("https://example.com/validators.txt").unwrap(),
+            Selector::Url("https://example.com/validators.txt".to_string())
+        );
+        assert!(parse_selector("moniker:").is_err());
+        assert!(parse_selector("acme").is_err());
+
+        assert_eq!(
+            parse_list("# managed\nsommvaloper1a\n\n  sommvaloper1b # acme\n").unwrap(),
+            vec!["sommvaloper1a", "sommvaloper1b"]
+        );
+        assert_eq!(
+            parse_list(r#"["sommvaloper1a", " sommvaloper1b"]"#).unwrap(),
+            vec!["sommvaloper1a", "sommvaloper1b"]
+        );
+        assert!(parse_list("[1, 2]").is_err());
+    }
+}
@@ -0,0 +1,809 @@
+//! Snapshot tests for user-facing output. The CLI and its output are a
+//! contract for automation, so any change here should be deliberate: rerun
+//! with `UPDATE_SNAPSHOTS=1` to accept it and review the snapshot diff.
+
+use std::path::{Path, PathBuf};
+use std::{env, fs, process::Command};
+
+const KEY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/key.hex");
+const CONFIG: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/config.toml");
+
+fn run(args: &[&str]) -> String {
+    run_with_env(args, &[])
+}
+
+fn run_with_env(args: &[&str], vars: &[(&str, &str)]) -> String {
+    let output = output(args, vars);
+    assert!(
+        output.status.success(),
+        "withdraw-commission {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    String::from_utf8(output.stdout).expect("stdout is not UTF-8")
+}
+
+/// Run a command that must fail, returning its stdout and stderr.
+fn run_failing(args: &[&str], vars: &[(&str, &str)]) -> (String, String) {
+    let output = output(args, vars);
+    assert!(
+        !output.status.success(),
+        "withdraw-commission {:?} succeeded",
+        args
+    );
+
+    (
+        String::from_utf8(output.stdout).expect("stdout is not UTF-8"),
+        String::from_utf8(output.stderr).expect("stderr is not UTF-8"),
+    )
+}
+
+fn output(args: &[&str], vars: &[(&str, &str)]) -> std::process::Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_withdraw-commission"));
+    for (name, _) in env::vars().filter(|(name, _)| name.starts_with("WITHDRAW_COMMISSION_")) {
+        command.env_remove(name);
+    }
+    command
+        .args(args)
+        .env_remove("RUNNING_IN_CONTAINER")
+        .env_remove("RUST_LOG")
+        .envs(vars.iter().copied())
+        // Keep a config file in the real home directory out of the snapshots
+        .env("HOME", env!("CARGO_TARGET_TMPDIR"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .expect("failed to run withdraw-commission")
+}
+
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(name);
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, actual).expect("failed to write snapshot");
+        return;
+    }
+
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("missing snapshot {}, run with UPDATE_SNAPSHOTS=1", name));
+    assert_eq!(
+        expected, actual,
+        "output differs from snapshot {}, run with UPDATE_SNAPSHOTS=1 to accept",
+        name
+    );
+}
+
+// Optional features add flags, so the help snapshot is for the default build
+#[cfg(all(
+    feature = "callback",
+    feature = "grafana",
+    feature = "os-keychain",
+    feature = "pushgateway",
+    feature = "socks",
+    feature = "statsd"
+))]
+#[test]
+fn help() {
+    assert_snapshot("help.txt", &run(&["--help"]));
+}
+
+#[test]
+fn plan() {
+    assert_snapshot("plan.txt", &run(&["--signing-key-path", KEY, "--plan"]));
+}
+
+#[test]
+fn plan_authz_granter() {
+    let output = run(&[
+        "withdraw",
+        "--signing-key-path",
+        KEY,
+        "--authz-granter",
+        "sommvaloper15pq7xy8m3r945w78wx0d6hngm43p5kencvmjja",
+        "--plan",
+    ]);
+    assert_snapshot("plan_authz_granter.txt", &output);
+}
+
+#[test]
+fn authz_granter_rejects_moving_the_granters_funds() {
+    let (stdout, stderr) = run_failing(
+        &[
+            "withdraw",
+            "--signing-key-path",
+            KEY,
+            "--authz-granter",
+            "sommvaloper15pq7xy8m3r945w78wx0d6hngm43p5kencvmjja",
+            "--compound",
+            "50",
+            "--plan",
+        ],
+        &[],
+    );
+    assert!(stdout.is_empty(), "unexpected output: {}", stdout);
+    assert!(
+        stderr.contains("--authz-granter cannot be combined with --compound"),
+        "unexpected error: {}",
+        stderr
+    );
+
+    // The grant only covers the commission, not the granter's own rewards
+    let (_, stderr) = run_failing(
+        &[
+            "withdraw",
+            "--signing-key-path",
+            KEY,
+            "--authz-granter",
+            "sommvaloper15pq7xy8m3r945w78wx0d6hngm43p5kencvmjja",
+            "--include-rewards",
+            "--plan",
+        ],
+        &[],
+    );
+    assert!(
+        stderr.contains("--authz-granter cannot be combined with --include-rewards"),
+        "unexpected error: {}",
+        stderr
+    );
+}
+
+#[test]
+fn plan_fee_granter() {
+    let output = run(&[
+        "withdraw",
+        "--signing-key-path",
+        KEY,
+        "--fee-granter",
+        "somm1rpzcfm5xsfemc7767ufec27nxjw9qmmxpq4dvw",
+        "--plan",
+    ]);
+    assert_snapshot("plan_fee_granter.txt", &output);
+}
+
+#[test]
+fn plan_memo_template() {
+    let output = run(&[
+        "withdraw",
+        "--signing-key-path",
+        KEY,
+        "--memo",
+        "commission {validator} on {chain_id}",
+        "--plan",
+    ]);
+    assert_snapshot("plan_memo.txt", &output);
+}
+
+#[test]
+fn plan_gas_prices() {
+    let output = run(&[
+        "withdraw",
+        "--signing-key-path",
+        KEY,
+        "--gas-prices",
+        "0.025usomm",
+        "--plan",
+    ]);
+    assert_snapshot("plan_gas_prices.txt", &output);
+}
+
+#[test]
+fn plan_dust_accumulate() {
+    let output = run(&[
+        "withdraw",
+        "--signing-key-path",
+        KEY,
+        "--plan",
+        "--payout",
+        "somm1rpzcfm5xsfemc7767ufec27nxjw9qmmxpq4dvw:50%",
+        "--dust",
+        "accumulate",
+        "--dust-file",
+        "/var/lib/withdraw-commission/dust",
+    ]);
+    assert_snapshot("plan_dust.txt", &output);
+}
+
+#[test]
+fn plan_diff() {
+    let baseline = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots/plan.txt");
+    let output = run(&[
+        "--signing-key-path",
+        KEY,
+        "--plan",
+        "--denom",
+        "uatom",
+        "--plan-baseline",
+        baseline.to_str().unwrap(),
+    ]);
+    assert_snapshot("plan_diff.txt", &output);
+}
+
+#[test]
+fn plan_diffs_against_the_previous_run() {
+    let state = Path::new(env!("CARGO_TARGET_TMPDIR")).join("previous-run.plan");
+    fs::copy(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots/plan.txt"),
+        &state,
+    )
+    .unwrap();
+    let output = run(&[
+        "--signing-key-path",
+        KEY,
+        "--plan",
+        "--denom",
+        "uatom",
+        "--plan-state",
+        state.to_str().unwrap(),
+    ]);
+    assert_snapshot("plan_diff.txt", &output);
+}
+
+#[test]
+fn plan_without_a_previous_run() {
+    let state = Path::new(env!("CARGO_TARGET_TMPDIR")).join("never-ran.plan");
+    let _ = fs::remove_file(&state);
+    let output = output(
+        &[
+            "--signing-key-path",
+            KEY,
+            "--plan",
+            "--plan-state",
+            state.to_str().unwrap(),
+        ],
+        &[],
+    );
+    assert!(output.status.success());
+    assert_snapshot("plan.txt", &String::from_utf8(output.stdout).unwrap());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("No previous run recorded in"),
+        "unexpected log: {}",
+        stderr
+    );
+}
+
+#[test]
+fn plan_round_trips_multiline_memo() {
+    let saved = Path::new(env!("CARGO_TARGET_TMPDIR")).join("multiline-memo.plan");
+    let memo = "Commission = {validator}\n  signed off by treasury ";
+    run(&[
+        "--signing-key-path",
+        KEY,
+        "--plan",
+        "--memo",
+        memo,
+        "--plan-out",
+        saved.to_str().unwrap(),
+    ]);
+    let output = run(&[
+        "--signing-key-path",
+        KEY,
+        "--plan",
+        "--memo",
+        memo,
+        "--plan-baseline",
+        saved.to_str().unwrap(),
+    ]);
+    assert!(
+        output.ends_with("Plan: 0 to add, 0 to change, 0 to remove.\n"),
+        "unexpected diff: {}",
+        output
+    );
+}
+
+#[test]
+fn generate_unit() {
+    let output = run(&[
+        "--signing-key-path",
+        "/etc/withdraw-commission/key.hex",
+        "--generate-unit",
+        "systemd-timer",
+    ]);
+    let output = output.replace(
+        env!("CARGO_BIN_EXE_withdraw-commission"),
+        "withdraw-commission",
+    );
+    assert_snapshot("generate_unit_systemd.txt", &output);
+}
+
+#[test]
+fn withdraw_subcommand_matches_flat_flags() {
+    assert_eq!(
+        run(&["withdraw", "--signing-key-path", KEY, "--plan"]),
+        run(&["--signing-key-path", KEY, "--plan"])
+    );
+}
+
+#[test]
+fn keys_show() {
+    assert_snapshot(
+        "keys_show.txt",
+        &run(&["keys", "show", "--signing-key-path", KEY]),
+    );
+}
+
+#[test]
+fn config_profile() {
+    let output = run(&[
+        "--config",
+        CONFIG,
+        "--profile",
+        "testnet",
+        "--timeout-height",
+        "100",
+        "--plan",
+    ]);
+    assert_snapshot("plan_profile.txt", &output);
+}
+
+#[test]
+fn env_overrides_config_and_flags_override_env() {
+    let output = run_with_env(
+        &[
+            "--config",
+            CONFIG,
+            "--profile",
+            "testnet",
+            "--denom",
+            "uatom",
+            "--plan",
+        ],
+        &[
+            ("WITHDRAW_COMMISSION_CHAIN_ID", "sommelier-env-1"),
+            ("WITHDRAW_COMMISSION_DENOM", "usomm"),
+            ("WITHDRAW_COMMISSION_FEE_AMOUNT", "10"),
+        ],
+    );
+    assert!(
+        output.contains("chain_id = sommelier-env-1\n"),
+        "{}",
+        output
+    );
+    assert!(output.contains("fee.amount.0 = 10uatom\n"), "{}", output);
+}
+
+#[test]
+fn migrate_cmdline() {
+    assert_eq!(
+        run(&[
+            "migrate-cmdline",
+            "--",
+            "/usr/local/bin/withdraw-commission",
+            "--signing-key-path",
+            "/etc/withdraw commission/key.hex",
+            "--plan",
+        ]),
+        "/usr/local/bin/withdraw-commission withdraw --signing-key-path '/etc/withdraw commission/key.hex' --plan\n"
+    );
+}
+
+#[test]
+fn keys_show_from_file_keyring() {
+    assert_eq!(
+        run(&[
+            "keys",
+            "show",
+            "--key-name",
+            "validator",
+            "--keyring-dir",
+            "tests/fixtures/keyring-file",
+            "--keyring-passphrase-path",
+            "tests/fixtures/keyring-passphrase",
+        ]),
+        run(&["keys", "show", "--signing-key-path", KEY])
+    );
+}
+
+#[test]
+fn keys_encrypt_round_trips() {
+    let out = Path::new(env!("CARGO_TARGET_TMPDIR")).join("encrypted-key.json");
+    let _ = fs::remove_file(&out);
+    let passphrase = [("PASSPHRASE", "correct horse battery")];
+    run_with_env(
+        &[
+            "keys",
+            "encrypt",
+            "--signing-key-path",
+            KEY,
+            "--out",
+            out.to_str().unwrap(),
+            "--passphrase-env",
+            "PASSPHRASE",
+        ],
+        &passphrase,
+    );
+    assert_eq!(
+        run_with_env(
+            &[
+                "keys",
+                "show",
+                "--signing-key-path",
+                out.to_str().unwrap(),
+                "--passphrase-env",
+                "PASSPHRASE",
+            ],
+            &passphrase,
+        ),
+        run(&["keys", "show", "--signing-key-path", KEY])
+    );
+}
+
+#[test]
+fn signing_key_from_env() {
+    assert_eq!(
+        run_with_env(
+            &["keys", "show", "--signing-key-env", "VALIDATOR_KEY"],
+            &[("VALIDATOR_KEY", fs::read_to_string(KEY).unwrap().trim())],
+        ),
+        run(&["keys", "show", "--signing-key-path", KEY])
+    );
+}
+
+#[test]
+fn keys_generate_writes_a_usable_key() {
+    let out = Path::new(env!("CARGO_TARGET_TMPDIR")).join("generated-key.json");
+    let _ = fs::remove_file(&out);
+    let passphrase = [("PASSPHRASE", "correct horse battery")];
+    let generated = run_with_env(
+        &[
+            "keys",
+            "generate",
+            "--out",
+            out.to_str().unwrap(),
+            "--passphrase-env",
+            "PASSPHRASE",
+        ],
+        &passphrase,
+    );
+    assert!(generated.starts_with("address: somm1"));
+    assert_eq!(
+        run_with_env(
+            &[
+                "keys",
+                "show",
+                "--signing-key-path",
+                out.to_str().unwrap(),
+                "--passphrase-env",
+                "PASSPHRASE",
+            ],
+            &passphrase,
+        ),
+        generated
+    );
+}
+
+#[test]
+fn keys_verify_accepts_expected_valoper() {
+    assert_eq!(
+        run(&[
+            "keys",
+            "verify",
+            "--signing-key-path",
+            KEY,
+            "--expected-valoper",
+            "sommvaloper18pll7w4dwhp38sq79xjerth0dw6jh2tnhmvzw8",
+        ]),
+        "ok: the signing key belongs to sommvaloper18pll7w4dwhp38sq79xjerth0dw6jh2tnhmvzw8\n"
+    );
+}
+
+/// Sign the withdrawal of the multisig fixture with members 1 and 3, writing
+/// the partial signatures to files starting with `prefix`.
+fn multisig_partials(prefix: &str) -> (PathBuf, Vec<PathBuf>) {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/multisig");
+    let pubkey = fixtures.join("pubkey.json");
+    let mut partials = Vec::new();
+    for (name, key) in [
+        ("partial-1.json", PathBuf::from(KEY)),
+        ("partial-3.json", fixtures.join("member3.hex")),
+    ] {
+        let out = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{}-{}", prefix, name));
+        run(&[
+            "multisig",
+            "sign",
+            "--multisig-pubkey",
+            pubkey.to_str().unwrap(),
+            "--signing-key-path",
+            key.to_str().unwrap(),
+            "--account-number",
+            "12",
+            "--sequence",
+            "4",
+            "--out",
+            out.to_str().unwrap(),
+        ]);
+        partials.push(out);
+    }
+
+    (pubkey, partials)
+}
+
+#[test]
+fn multisig_sign_and_combine_offline() {
+    let (pubkey, partials) = multisig_partials("offline");
+    let output = run(&[
+        "multisig",
+        "combine",
+        "--multisig-pubkey",
+        pubkey.to_str().unwrap(),
+        partials[0].to_str().unwrap(),
+        partials[1].to_str().unwrap(),
+        "--output",
+        "json",
+    ]);
+    assert_snapshot("multisig_combine.json", &output);
+}
+
+#[test]
+fn multisig_combine_refuses_to_broadcast_read_only() {
+    let (pubkey, partials) = multisig_partials("read-only");
+    let (stdout, stderr) = run_failing(
+        &[
+            "multisig",
+            "combine",
+            "--multisig-pubkey",
+            pubkey.to_str().unwrap(),
+            partials[0].to_str().unwrap(),
+            partials[1].to_str().unwrap(),
+            "--broadcast",
+            // Nothing listens here, so a broadcast attempt would fail differently
+            "--rpc-url",
+            "http://127.0.0.1:9",
+        ],
+        &[("WITHDRAW_COMMISSION_READ_ONLY", "1")],
+    );
+    assert!(stdout.is_empty(), "unexpected output: {}", stdout);
+    assert!(
+        stderr.contains("Read-only mode is set"),
+        "unexpected error: {}",
+        stderr
+    );
+}
+
+#[test]
+fn multisig_sign_rejects_gas_prices() {
+    let pubkey = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/multisig/pubkey.json");
+    let out = Path::new(env!("CARGO_TARGET_TMPDIR")).join("gas-prices-partial.json");
+    let (_, stderr) = run_failing(
+        &[
+            "multisig",
+            "sign",
+            "--multisig-pubkey",
+            pubkey.to_str().unwrap(),
+            "--signing-key-path",
+            KEY,
+            "--account-number",
+            "12",
+            "--sequence",
+            "4",
+            "--gas-prices",
+            "0.025usomm",
+            "--out",
+            out.to_str().unwrap(),
+        ],
+        &[],
+    );
+    assert!(
+        stderr.contains("--gas-prices is not supported by multisig sign"),
+        "unexpected error: {}",
+        stderr
+    );
+    assert!(!out.exists());
+}
+
+/// Plan against a missing state file, which logs one line, and return the
+/// log lines.
+fn plan_log_lines(vars: &[(&str, &str)]) -> Vec<String> {
+    let state = Path::new(env!("CARGO_TARGET_TMPDIR")).join("log-format.plan");
+    let _ = fs::remove_file(&state);
+    let output = output(
+        &[
+            "--signing-key-path",
+            KEY,
+            "--plan",
+            "--plan-state",
+            state.to_str().unwrap(),
+        ],
+        vars,
+    );
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .starts_with("chain_id = "));
+
+    String::from_utf8(output.stderr)
+        .unwrap()
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn log_format_selection() {
+    // JSON on stderr in containers or on request, keeping stdout for the plan
+    for vars in [
+        &[("RUNNING_IN_CONTAINER", "1")][..],
+        &[("WITHDRAW_COMMISSION_LOG_FORMAT", "json")][..],
+    ] {
+        let messages = plan_log_lines(vars)
+            .iter()
+            .map(|line| {
+                let line: serde_json::Value = serde_json::from_str(line)
+                    .unwrap_or_else(|e| panic!("log line {:?} is not JSON: {}", line, e));
+                assert!(line["timestamp"].is_string() && line["level"].is_string());
+                line["message"].as_str().unwrap().to_string()
+            })
+            .collect::<Vec<_>>();
+        assert!(
+            messages
+                .iter()
+                .any(|message| message.starts_with("No previous run recorded in")),
+            "unexpected log with {:?}: {:?}",
+            vars,
+            messages
+        );
+    }
+
+    // Text otherwise, even in a container when asked for
+    for vars in [
+        &[][..],
+        &[("RUNNING_IN_CONTAINER", "0")][..],
+        &[
+            ("RUNNING_IN_CONTAINER", "1"),
+            ("WITHDRAW_COMMISSION_LOG_FORMAT", "text"),
+        ][..],
+    ] {
+        let lines = plan_log_lines(vars);
+        assert!(
+            lines.iter().all(|line| line.starts_with('['))
+                && lines.iter().any(|line| line.starts_with("[INFO ")
+                    && line.contains("No previous run recorded in")),
+            "unexpected log with {:?}: {:?}",
+            vars,
+            lines
+        );
+    }
+}
+
+#[test]
+fn version() {
+    assert_eq!(
+        run(&["--version"]),
+        format!("withdraw-commission {}\n", env!("CARGO_PKG_VERSION"))
+    );
+    let (_, stderr) = run_failing(&["--verbose"], &[]);
+    assert!(
+        stderr.contains("the following required arguments were not provided"),
+        "unexpected error: {}",
+        stderr
+    );
+}
+
+// The features line lists the default build's features
+#[cfg(all(
+    feature = "callback",
+    feature = "grafana",
+    feature = "os-keychain",
+    feature = "pushgateway",
+    feature = "socks",
+    feature = "statsd"
+))]
+#[test]
+fn version_verbose() {
+    // The commit and build date change with every build
+    let output = run(&["--version", "--verbose"])
+        .lines()
+        .map(|line| match line.split_once(": ") {
+            Some((name @ ("commit" | "build date"), _)) => format!("{}: <{}>\n", name, name),
+            _ => format!("{}\n", line),
+        })
+        .collect::<String>();
+    assert_snapshot("version_verbose.txt", &output);
+}
+
+// Callbacks to a local HTTP listener
+#[cfg(feature = "callback")]
+mod callback {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// Accept one HTTP request on a local port, answer it with 204 and return
+    /// the URL to send it to and a handle yielding its body.
+    fn receive_request() -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/withdrawals", listener.local_addr().unwrap());
+        listener.set_nonblocking(true).unwrap();
+        let handle = thread::spawn(move || {
+            let deadline = Instant::now() + Duration::from_secs(30);
+            let stream = loop {
+                match listener.accept() {
+                    Ok((stream, _)) => break stream,
+                    Err(_) if Instant::now() < deadline => thread::sleep(Duration::from_millis(10)),
+                    Err(e) => panic!("no callback received: {}", e),
+                }
+            };
+            stream.set_nonblocking(false).unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim_end().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 204 No Content\r\ncontent-length: 0\r\n\r\n")
+                .unwrap();
+
+            String::from_utf8(body).expect("callback body is not UTF-8")
+        });
+
+        (url, handle)
+    }
+
+    /// Withdraw against endpoints that refuse connections, with `extra` flags.
+    fn withdraw_unreachable(extra: &[&str]) -> (String, String) {
+        let mut args = vec![
+            "--signing-key-path",
+            KEY,
+            "--grpc-url",
+            "http://127.0.0.1:1",
+            "--rpc-url",
+            "http://127.0.0.1:1",
+        ];
+        args.extend_from_slice(extra);
+        run_failing(&args, &[])
+    }
+
+    #[test]
+    fn callback_reports_runs_that_fail_before_broadcasting() {
+        let (url, request) = receive_request();
+        let (stdout, _) = withdraw_unreachable(&["--output", "json", "--callback-url", &url]);
+        let body: serde_json::Value = serde_json::from_str(&request.join().unwrap()).unwrap();
+
+        // The callback gets the same object --output json prints
+        let printed: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+        assert_eq!(body, printed);
+        assert_eq!(body["status"], "failure");
+        assert_eq!(body["chain_id"], "sommelier-3");
+        assert_eq!(
+            body["validator"],
+            run(&["keys", "show", "--signing-key-path", KEY])
+                .lines()
+                .find_map(|line| line.strip_prefix("operator_address: "))
+                .unwrap()
+        );
+        assert_eq!(body["tx_hash"], "");
+        assert_eq!(body["failure"]["class"], "endpoint");
+        assert!(body["log"]
+            .as_str()
+            .unwrap()
+            .starts_with("Failed to connect to gRPC endpoint http://127.0.0.1:1"));
+        assert_eq!(body["build"]["version"], env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn callback_body_template() {
+        let (url, request) = receive_request();
+        withdraw_unreachable(&[
+            "--callback-url",
+            &url,
+            "--callback-body",
+            r#"{"validator": "{validator}", "state": "{status}", "class": "{failure_class}", "hint": "{remediation}"}"#,
+        ]);
+        assert_snapshot("callback_failure.json", &request.join().unwrap());
+    }
+}